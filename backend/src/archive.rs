@@ -0,0 +1,471 @@
+//! On-the-fly zip and tar archives for folder downloads. Entries are read
+//! from disk and sent in small chunks, so memory use does not depend on the
+//! size of the folder.
+
+use axum::body::Body;
+use bytes::{BufMut, Bytes, BytesMut};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const TAR_BLOCK: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+}
+
+impl ArchiveFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "zip" => Some(Self::Zip),
+            "tar" => Some(Self::Tar),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Zip => "application/zip",
+            Self::Tar => "application/x-tar",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::Tar => "tar",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub source: PathBuf,
+    /// `/`-separated path inside the archive
+    pub name: String,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// Lists the regular files below `dir`, named relative to its parent so the
+/// archive unpacks into a single folder. Symlinks are skipped: they could
+/// point outside the download root.
+pub async fn collect_entries(dir: &Path) -> io::Result<Vec<Entry>> {
+    let prefix = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut entries = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), prefix)];
+
+    while let Some((current, name)) = pending.pop() {
+        let mut read_dir = tokio::fs::read_dir(&current).await?;
+        while let Some(item) = read_dir.next_entry().await? {
+            let meta = tokio::fs::symlink_metadata(item.path()).await?;
+            let item_name = format!("{}/{}", name, item.file_name().to_string_lossy());
+            if meta.is_dir() {
+                pending.push((item.path(), item_name));
+            } else if meta.is_file() {
+                entries.push(Entry {
+                    source: item.path(),
+                    name: item_name,
+                    size: meta.len(),
+                    modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                });
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(entries)
+}
+
+/// Starts writing the archive in a background task and returns its body.
+pub fn stream(format: ArchiveFormat, entries: Vec<Entry>) -> Body {
+    let (tx, rx) = mpsc::channel::<io::Result<Bytes>>(4);
+
+    tokio::spawn(async move {
+        let mut sink = Sink { tx, written: 0 };
+        let result = match format {
+            ArchiveFormat::Zip => write_zip(&mut sink, &entries).await,
+            ArchiveFormat::Tar => write_tar(&mut sink, &entries).await,
+        };
+        if let Err(e) = result {
+            if e.kind() != io::ErrorKind::BrokenPipe {
+                tracing::warn!("Archive stream aborted: {}", e);
+                // Surfacing the error truncates the response so the client
+                // does not mistake a partial archive for a complete one.
+                let _ = sink.tx.send(Err(e)).await;
+            }
+        }
+    });
+
+    Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx))
+}
+
+struct Sink {
+    tx: mpsc::Sender<io::Result<Bytes>>,
+    written: u64,
+}
+
+impl Sink {
+    async fn write(&mut self, data: Bytes) -> io::Result<()> {
+        self.written += data.len() as u64;
+        self.tx
+            .send(Ok(data))
+            .await
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client disconnected"))
+    }
+
+    /// Copies exactly `entry.size` bytes, zero-filling if the file shrank
+    /// since it was listed, so the headers already sent stay valid.
+    async fn copy_file(&mut self, entry: &Entry, mut crc: Option<&mut crc32fast::Hasher>) -> io::Result<()> {
+        let mut file = tokio::fs::File::open(&entry.source).await?;
+        let mut remaining = entry.size;
+
+        while remaining > 0 {
+            let mut buf = vec![0u8; remaining.min(CHUNK_SIZE as u64) as usize];
+            let mut filled = 0;
+            while filled < buf.len() {
+                let n = file.read(&mut buf[filled..]).await?;
+                if n == 0 {
+                    break;
+                }
+                filled += n;
+            }
+            // On a short read the rest of `buf` stays zeroed
+            if let Some(crc) = crc.as_deref_mut() {
+                crc.update(&buf);
+            }
+            remaining -= buf.len() as u64;
+            self.write(Bytes::from(buf)).await?;
+        }
+        Ok(())
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// --- tar (GNU flavour, for long names and large files) ---
+
+/// Writes `value` as a NUL-terminated octal number, falling back to GNU
+/// base-256 encoding when it does not fit.
+fn tar_number(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    if digits >= 22 || value < 1u64 << (3 * digits) {
+        let s = format!("{:0width$o}", value, width = digits);
+        field[..digits].copy_from_slice(s.as_bytes());
+        field[digits] = 0;
+    } else {
+        field.fill(0);
+        field[0] = 0x80;
+        let bytes = value.to_be_bytes();
+        let len = field.len();
+        field[len - 8..].copy_from_slice(&bytes);
+    }
+}
+
+fn tar_header(name: &[u8], size: u64, mtime: u64, typeflag: u8) -> [u8; TAR_BLOCK] {
+    let mut header = [0u8; TAR_BLOCK];
+    let name_len = name.len().min(100);
+    header[..name_len].copy_from_slice(&name[..name_len]);
+    tar_number(&mut header[100..108], 0o644);
+    tar_number(&mut header[108..116], 0);
+    tar_number(&mut header[116..124], 0);
+    tar_number(&mut header[124..136], size);
+    tar_number(&mut header[136..148], mtime);
+    header[156] = typeflag;
+    header[257..265].copy_from_slice(b"ustar  \0");
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].fill(b' ');
+    let sum: u32 = header.iter().map(|b| *b as u32).sum();
+    let s = format!("{:06o}\0 ", sum);
+    header[148..156].copy_from_slice(s.as_bytes());
+    header
+}
+
+fn tar_padding(size: u64) -> usize {
+    (TAR_BLOCK - (size % TAR_BLOCK as u64) as usize) % TAR_BLOCK
+}
+
+async fn write_tar(sink: &mut Sink, entries: &[Entry]) -> io::Result<()> {
+    for entry in entries {
+        let name = entry.name.as_bytes();
+        let mtime = unix_seconds(entry.modified);
+
+        if name.len() > 100 {
+            // GNU long name: a pseudo-entry whose data is the real name
+            let mut block = BytesMut::new();
+            block.put_slice(&tar_header(b"././@LongLink", name.len() as u64 + 1, 0, b'L'));
+            block.put_slice(name);
+            block.put_u8(0);
+            block.put_bytes(0, tar_padding(name.len() as u64 + 1));
+            sink.write(block.freeze()).await?;
+        }
+
+        sink.write(Bytes::copy_from_slice(&tar_header(name, entry.size, mtime, b'0'))).await?;
+        sink.copy_file(entry, None).await?;
+        let padding = tar_padding(entry.size);
+        if padding > 0 {
+            sink.write(Bytes::from(vec![0u8; padding])).await?;
+        }
+    }
+
+    sink.write(Bytes::from(vec![0u8; TAR_BLOCK * 2])).await
+}
+
+// --- zip (stored, streamed with data descriptors, zip64 when needed) ---
+
+const ZIP_LIMIT: u64 = 0xFFFF_FFFF;
+const ZIP_FLAGS: u16 = 0x0808; // data descriptor + UTF-8 names
+
+struct CentralRecord {
+    name: String,
+    crc: u32,
+    size: u64,
+    offset: u64,
+    dos_time: u16,
+    dos_date: u16,
+}
+
+fn dos_datetime(time: SystemTime) -> (u16, u16) {
+    let dt = time::OffsetDateTime::from(time);
+    if dt.year() < 1980 {
+        return (0, (1 << 5) | 1); // 1980-01-01
+    }
+    let time = ((dt.hour() as u16) << 11) | ((dt.minute() as u16) << 5) | (dt.second() as u16 / 2);
+    let date = (((dt.year() - 1980) as u16) << 9) | ((dt.month() as u16) << 5) | dt.day() as u16;
+    (time, date)
+}
+
+async fn write_zip(sink: &mut Sink, entries: &[Entry]) -> io::Result<()> {
+    let mut records = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let offset = sink.written;
+        let zip64 = entry.size >= ZIP_LIMIT;
+        let (dos_time, dos_date) = dos_datetime(entry.modified);
+
+        let mut header = BytesMut::new();
+        header.put_u32_le(0x0403_4b50);
+        header.put_u16_le(if zip64 { 45 } else { 20 });
+        header.put_u16_le(ZIP_FLAGS);
+        header.put_u16_le(0); // stored
+        header.put_u16_le(dos_time);
+        header.put_u16_le(dos_date);
+        header.put_u32_le(0); // crc, in the data descriptor
+        let placeholder = if zip64 { ZIP_LIMIT as u32 } else { 0 };
+        header.put_u32_le(placeholder);
+        header.put_u32_le(placeholder);
+        header.put_u16_le(entry.name.len() as u16);
+        header.put_u16_le(if zip64 { 20 } else { 0 });
+        header.put_slice(entry.name.as_bytes());
+        if zip64 {
+            header.put_u16_le(0x0001);
+            header.put_u16_le(16);
+            header.put_u64_le(0);
+            header.put_u64_le(0);
+        }
+        sink.write(header.freeze()).await?;
+
+        let mut crc = crc32fast::Hasher::new();
+        sink.copy_file(entry, Some(&mut crc)).await?;
+        let crc = crc.finalize();
+
+        let mut descriptor = BytesMut::new();
+        descriptor.put_u32_le(0x0807_4b50);
+        descriptor.put_u32_le(crc);
+        if zip64 {
+            descriptor.put_u64_le(entry.size);
+            descriptor.put_u64_le(entry.size);
+        } else {
+            descriptor.put_u32_le(entry.size as u32);
+            descriptor.put_u32_le(entry.size as u32);
+        }
+        sink.write(descriptor.freeze()).await?;
+
+        records.push(CentralRecord {
+            name: entry.name.clone(),
+            crc,
+            size: entry.size,
+            offset,
+            dos_time,
+            dos_date,
+        });
+    }
+
+    let cd_offset = sink.written;
+    let mut cd = BytesMut::new();
+    for record in &records {
+        let mut extra = BytesMut::new();
+        if record.size >= ZIP_LIMIT {
+            extra.put_u64_le(record.size);
+            extra.put_u64_le(record.size);
+        }
+        if record.offset >= ZIP_LIMIT {
+            extra.put_u64_le(record.offset);
+        }
+        let zip64 = !extra.is_empty();
+
+        cd.put_u32_le(0x0201_4b50);
+        cd.put_u16_le((3 << 8) | 45); // made by: unix
+        cd.put_u16_le(if zip64 { 45 } else { 20 });
+        cd.put_u16_le(ZIP_FLAGS);
+        cd.put_u16_le(0);
+        cd.put_u16_le(record.dos_time);
+        cd.put_u16_le(record.dos_date);
+        cd.put_u32_le(record.crc);
+        cd.put_u32_le(record.size.min(ZIP_LIMIT) as u32);
+        cd.put_u32_le(record.size.min(ZIP_LIMIT) as u32);
+        cd.put_u16_le(record.name.len() as u16);
+        cd.put_u16_le(if zip64 { extra.len() as u16 + 4 } else { 0 });
+        cd.put_u16_le(0); // comment
+        cd.put_u16_le(0); // disk
+        cd.put_u16_le(0); // internal attributes
+        cd.put_u32_le(0o100644 << 16);
+        cd.put_u32_le(record.offset.min(ZIP_LIMIT) as u32);
+        cd.put_slice(record.name.as_bytes());
+        if zip64 {
+            cd.put_u16_le(0x0001);
+            cd.put_u16_le(extra.len() as u16);
+            cd.put_slice(&extra);
+        }
+    }
+    let cd_size = cd.len() as u64;
+    sink.write(cd.freeze()).await?;
+
+    let count = records.len() as u64;
+    let mut end = BytesMut::new();
+    if count >= 0xFFFF || cd_offset >= ZIP_LIMIT || cd_size >= ZIP_LIMIT {
+        let zip64_end_offset = cd_offset + cd_size;
+        end.put_u32_le(0x0606_4b50);
+        end.put_u64_le(44);
+        end.put_u16_le((3 << 8) | 45);
+        end.put_u16_le(45);
+        end.put_u32_le(0);
+        end.put_u32_le(0);
+        end.put_u64_le(count);
+        end.put_u64_le(count);
+        end.put_u64_le(cd_size);
+        end.put_u64_le(cd_offset);
+
+        end.put_u32_le(0x0706_4b50);
+        end.put_u32_le(0);
+        end.put_u64_le(zip64_end_offset);
+        end.put_u32_le(1);
+    }
+    end.put_u32_le(0x0605_4b50);
+    end.put_u16_le(0);
+    end.put_u16_le(0);
+    end.put_u16_le(count.min(0xFFFF) as u16);
+    end.put_u16_le(count.min(0xFFFF) as u16);
+    end.put_u32_le(cd_size.min(ZIP_LIMIT) as u32);
+    end.put_u32_le(cd_offset.min(ZIP_LIMIT) as u32);
+    end.put_u16_le(0);
+    sink.write(end.freeze()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tar_number_encoding() {
+        let mut field = [0u8; 12];
+        tar_number(&mut field, 5);
+        assert_eq!(&field, b"00000000005\0");
+
+        // 8 GiB no longer fits in 11 octal digits
+        tar_number(&mut field, 8 << 30);
+        assert_eq!(field[0], 0x80);
+        assert_eq!(&field[4..], &(8u64 << 30).to_be_bytes());
+    }
+
+    #[test]
+    fn test_tar_header_checksum() {
+        let header = tar_header(b"dir/file.txt", 5, 0, b'0');
+        let stored = u32::from_str_radix(std::str::from_utf8(&header[148..154]).unwrap(), 8).unwrap();
+        let mut copy = header;
+        copy[148..156].fill(b' ');
+        assert_eq!(stored, copy.iter().map(|b| *b as u32).sum::<u32>());
+    }
+
+    fn u16_at(data: &[u8], at: usize) -> usize {
+        u16::from_le_bytes([data[at], data[at + 1]]) as usize
+    }
+
+    fn u32_at(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_zip_round_trip() {
+        let dir = std::env::temp_dir().join(format!("vt-archive-{}", std::process::id()));
+        let files: [(&str, Vec<u8>); 3] = [
+            ("a.txt", b"hello".to_vec()),
+            ("empty", Vec::new()),
+            ("sub/big.bin", (0..CHUNK_SIZE * 2 + 7).map(|i| i as u8).collect()),
+        ];
+        for (name, data) in &files {
+            let path = dir.join("root").join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, data).unwrap();
+        }
+
+        let entries = collect_entries(&dir.join("root")).await.unwrap();
+        let (tx, mut rx) = mpsc::channel(4);
+        let writer = tokio::spawn(async move {
+            let mut sink = Sink { tx, written: 0 };
+            write_zip(&mut sink, &entries).await
+        });
+        let mut zip = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            zip.extend_from_slice(&chunk.unwrap());
+        }
+        writer.await.unwrap().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // End of central directory
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(&zip, end), 0x0605_4b50);
+        assert_eq!(u16_at(&zip, end + 10), files.len());
+        let cd_size = u32_at(&zip, end + 12) as usize;
+        let mut at = u32_at(&zip, end + 16) as usize;
+        assert_eq!(at + cd_size, end);
+
+        let mut found = Vec::new();
+        for _ in 0..files.len() {
+            assert_eq!(u32_at(&zip, at), 0x0201_4b50);
+            let crc = u32_at(&zip, at + 16);
+            let size = u32_at(&zip, at + 24) as usize;
+            let name_len = u16_at(&zip, at + 28);
+            let offset = u32_at(&zip, at + 42) as usize;
+            let name = std::str::from_utf8(&zip[at + 46..at + 46 + name_len]).unwrap();
+
+            // Local header, then the stored data and its descriptor
+            assert_eq!(u32_at(&zip, offset), 0x0403_4b50);
+            assert_eq!(&zip[offset + 30..offset + 30 + name_len], name.as_bytes());
+            let data_start = offset + 30 + name_len + u16_at(&zip, offset + 28);
+            let data = &zip[data_start..data_start + size];
+            assert_eq!(crc32fast::hash(data), crc);
+            assert_eq!(u32_at(&zip, data_start + size), 0x0807_4b50);
+            assert_eq!(u32_at(&zip, data_start + size + 4), crc);
+
+            let (_, expected) = files.iter().find(|(n, _)| format!("root/{}", n) == name).unwrap();
+            assert_eq!(data, expected.as_slice());
+            found.push(name.to_string());
+            at += 46 + name_len + u16_at(&zip, at + 30);
+        }
+        assert_eq!(found, ["root/a.txt", "root/empty", "root/sub/big.bin"]);
+    }
+}
@@ -0,0 +1,314 @@
+//! Outgoing webhooks and shell hooks on torrent lifecycle events. The
+//! dispatcher listens on the event bus and delivers every matching
+//! automation in its own task, retrying failures before logging the result.
+
+use crate::AppState;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use shared::db::Db;
+use shared::{AppEvent, Automation, AutomationAction, AutomationTrigger, LifecycleEvent};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+const MAX_ATTEMPTS: u32 = 3;
+/// Doubled after every failed attempt.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+const DEFAULT_TEMPLATE: &str =
+    r#"{"event":"{{event}}","hash":"{{hash}}","name":"{{name}}","message":"{{message}}"}"#;
+
+#[derive(Clone)]
+struct Payload {
+    trigger: AutomationTrigger,
+    hash: String,
+    name: String,
+    message: String,
+}
+
+impl Payload {
+    fn from_event(event: &LifecycleEvent) -> Option<Self> {
+        let trigger = AutomationTrigger::of(event)?;
+        let (hash, name) = match event {
+            LifecycleEvent::TorrentAdded { hash, name }
+            | LifecycleEvent::TorrentCompleted { hash, name }
+            | LifecycleEvent::TorrentRemoved { hash, name }
+            | LifecycleEvent::TorrentErrored { hash, name, .. }
+            | LifecycleEvent::TrackerError { hash, name, .. } => (hash.clone(), name.clone()),
+            LifecycleEvent::DiskLow { .. } => return None,
+        };
+        Some(Self { trigger, hash, name, message: event.message() })
+    }
+}
+
+/// Escapes `value` for use inside a JSON string literal.
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+fn render(template: &str, payload: &Payload) -> String {
+    let template = if template.is_empty() { DEFAULT_TEMPLATE } else { template };
+    template
+        .replace("{{event}}", payload.trigger.as_str())
+        .replace("{{hash}}", &json_escape(&payload.hash))
+        .replace("{{name}}", &json_escape(&payload.name))
+        .replace("{{message}}", &json_escape(&payload.message))
+}
+
+/// Hex HMAC-SHA256 of `body`, sent as `X-VibeTorrent-Signature: sha256=<hex>`.
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body.as_bytes());
+    mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+async fn call_webhook(
+    client: &reqwest::Client,
+    url: &str,
+    method: &str,
+    template: &str,
+    secret: Option<&str>,
+    payload: &Payload,
+) -> Result<String, String> {
+    let method = reqwest::Method::from_bytes(method.as_bytes()).map_err(|e| e.to_string())?;
+    let mut request = client
+        .request(method.clone(), url)
+        .header("X-VibeTorrent-Event", payload.trigger.as_str());
+    if method != reqwest::Method::GET {
+        let body = render(template, payload);
+        if let Some(secret) = secret {
+            request = request.header("X-VibeTorrent-Signature", format!("sha256={}", sign(secret, &body)));
+        }
+        request = request.header("Content-Type", "application/json").body(body);
+    }
+
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    let status = format!("HTTP {}", response.status().as_u16());
+    if response.status().is_success() {
+        Ok(status)
+    } else {
+        Err(status)
+    }
+}
+
+async fn run_script(command: &str, payload: &Payload) -> Result<String, String> {
+    let child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        // Passed as environment, never spliced into the command line
+        .env("VT_EVENT", payload.trigger.as_str())
+        .env("VT_HASH", &payload.hash)
+        .env("VT_NAME", &payload.name)
+        .env("VT_MESSAGE", &payload.message)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    let output = tokio::time::timeout(SCRIPT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("timed out after {}s", SCRIPT_TIMEOUT.as_secs()))?
+        .map_err(|e| e.to_string())?;
+
+    let code = output.status.code().map_or_else(|| "signal".to_string(), |c| c.to_string());
+    if output.status.success() {
+        return Ok(format!("exit {}", code));
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    match stderr.lines().rev().find(|l| !l.trim().is_empty()) {
+        Some(line) => Err(format!("exit {}: {}", code, line.trim())),
+        None => Err(format!("exit {}", code)),
+    }
+}
+
+async fn deliver(db: Db, client: reqwest::Client, automation: Automation, payload: Payload) {
+    let mut attempts = 0;
+    let result = loop {
+        attempts += 1;
+        let result = match &automation.action {
+            AutomationAction::Webhook { url, method, body_template, secret } => {
+                call_webhook(&client, url, method, body_template, secret.as_deref(), &payload).await
+            }
+            AutomationAction::Script { command } => run_script(command, &payload).await,
+        };
+        match result {
+            Err(e) if attempts < MAX_ATTEMPTS => {
+                tracing::debug!("Automation {} attempt {} failed: {}", automation.name, attempts, e);
+                tokio::time::sleep(RETRY_DELAY * 2u32.pow(attempts - 1)).await;
+            }
+            result => break result,
+        }
+    };
+
+    let (success, detail) = match result {
+        Ok(detail) => (true, detail),
+        Err(detail) => {
+            tracing::warn!("Automation {} failed after {} attempts: {}", automation.name, attempts, detail);
+            (false, detail)
+        }
+    };
+    if let Err(e) = db
+        .add_automation_delivery(
+            automation.id,
+            payload.trigger,
+            &payload.name,
+            attempts as i64,
+            success,
+            &detail,
+            crate::history::unix_now(),
+        )
+        .await
+    {
+        tracing::warn!("Failed to log automation delivery: {}", e);
+    }
+}
+
+async fn dispatch(state: &AppState, client: &reqwest::Client, event: &LifecycleEvent) {
+    let Some(payload) = Payload::from_event(event) else { return };
+    let automations = match state.db.list_automations().await {
+        Ok(automations) => automations,
+        Err(e) => {
+            tracing::warn!("Failed to load automations: {}", e);
+            return;
+        }
+    };
+    for automation in automations {
+        if automation.enabled && automation.triggers.contains(&payload.trigger) {
+            tokio::spawn(deliver(state.db.clone(), client.clone(), automation, payload.clone()));
+        }
+    }
+}
+
+/// Runs forever, delivering automations for lifecycle events.
+pub async fn run(state: AppState) {
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .user_agent(concat!("VibeTorrent/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default();
+    let mut events = state.event_bus.subscribe_internal();
+
+    loop {
+        match events.recv().await {
+            Ok((_, AppEvent::Lifecycle(event, _))) => dispatch(&state, &client, &event).await,
+            Ok(_) => {}
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Automation dispatcher missed {} events", skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    fn payload(name: &str) -> Payload {
+        Payload {
+            trigger: AutomationTrigger::Completed,
+            hash: "ABC".to_string(),
+            name: name.to_string(),
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_escapes_values() {
+        let body = render("", &payload("say \"hi\"\n"));
+        let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(json["event"], "completed");
+        assert_eq!(json["name"], "say \"hi\"\n");
+        assert_eq!(render("{{hash}}-{{event}}", &payload("x")), "ABC-completed");
+    }
+
+    #[test]
+    fn test_sign() {
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    /// Reads one HTTP/1.1 request and returns its head and body.
+    async fn read_request(stream: &mut TcpStream) -> (String, String) {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 1024];
+        loop {
+            let n = stream.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "connection closed mid-request");
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf).to_string();
+            let Some((head, body)) = text.split_once("\r\n\r\n") else { continue };
+            let length = head
+                .lines()
+                .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                .map_or(0, |v| v.parse().unwrap());
+            if body.len() >= length {
+                return (head.to_string(), body.to_string());
+            }
+        }
+    }
+
+    /// Answers one request per status in `statuses`, then returns them all.
+    async fn serve(listener: TcpListener, statuses: &[u16]) -> Vec<(String, String)> {
+        let mut requests = Vec::new();
+        for status in statuses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            requests.push(read_request(&mut stream).await);
+            let response = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+        requests
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery_retries_and_logs() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move { serve(listener, &[500, 200]).await });
+
+        let path = std::env::temp_dir().join(format!("vt-automation-{}.db", std::process::id()));
+        let db = Db::new(&format!("sqlite:{}", path.display())).await.unwrap();
+        let action = AutomationAction::Webhook {
+            url,
+            method: "POST".to_string(),
+            body_template: r#"{"event":"{{event}}","name":"{{name}}"}"#.to_string(),
+            secret: Some("key".to_string()),
+        };
+        let id = db.create_automation("hook", &[AutomationTrigger::Completed], &action, 0).await.unwrap();
+        let automation = db.list_automations().await.unwrap().remove(0);
+        deliver(db.clone(), reqwest::Client::new(), automation, payload("say \"hi\"")).await;
+
+        let requests = server.await.unwrap();
+        let deliveries = db.list_automation_deliveries(10).await.unwrap();
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+
+        // The 500 was retried with the same request
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0], requests[1]);
+        let (head, body) = &requests[0];
+        assert!(head.starts_with("POST /hook HTTP/1.1"));
+        assert_eq!(body, r#"{"event":"completed","name":"say \"hi\""}"#);
+        let head = head.to_ascii_lowercase();
+        assert!(head.contains("x-vibetorrent-event: completed"));
+        assert!(head.contains(&format!("x-vibetorrent-signature: sha256={}", sign("key", body))));
+
+        assert_eq!(deliveries.len(), 1);
+        let delivery = &deliveries[0];
+        assert_eq!(delivery.automation_id, id);
+        assert_eq!(delivery.torrent_name, "say \"hi\"");
+        assert_eq!(delivery.attempts, 2);
+        assert!(delivery.success);
+        assert_eq!(delivery.detail, "HTTP 200");
+    }
+}
@@ -1,30 +1,50 @@
-use std::collections::HashMap;
-use shared::{AppEvent, NotificationLevel, SystemNotification, Torrent, TorrentUpdate};
+use std::collections::{HashMap, HashSet};
+use shared::{AppEvent, LifecycleEvent, Torrent, TorrentUpdate};
 
 #[derive(Debug)]
 pub enum DiffResult {
     NoChange,
-    FullUpdate,
+    /// Clients need the whole list again; the lifecycle events of the tick
+    /// still go out after it.
+    FullUpdate(Vec<AppEvent>),
     Partial(Vec<AppEvent>),
 }
 
-pub fn diff_torrents(old: &[Torrent], new: &[Torrent]) -> DiffResult {
-    if old.len() != new.len() {
-        return DiffResult::FullUpdate;
+/// rTorrent reports tracker failures through `d.message` as well, prefixed
+/// with "Tracker:".
+fn error_event(t: &Torrent) -> LifecycleEvent {
+    let (hash, name, message) = (t.hash.clone(), t.name.clone(), t.error_message.clone());
+    if t.error_message.starts_with("Tracker:") {
+        LifecycleEvent::TrackerError { hash, name, message }
+    } else {
+        LifecycleEvent::TorrentErrored { hash, name, message }
     }
+}
 
+pub fn diff_torrents(old: &[Torrent], new: &[Torrent]) -> DiffResult {
     let old_map: HashMap<&str, &Torrent> = old.iter().map(|t| (t.hash.as_str(), t)).collect();
-    
-    for new_t in new {
-        if !old_map.contains_key(new_t.hash.as_str()) {
-            return DiffResult::FullUpdate;
-        }
-    }
+    let new_hashes: HashSet<&str> = new.iter().map(|t| t.hash.as_str()).collect();
 
     let mut events = Vec::new();
+    let mut full_update = false;
+    for old_t in old.iter().filter(|t| !new_hashes.contains(t.hash.as_str())) {
+        // The owner is only known from the list before the removal
+        events.push(AppEvent::Removed(old_t.hash.clone(), old_t.owner));
+        events.push(AppEvent::Lifecycle(
+            LifecycleEvent::TorrentRemoved { hash: old_t.hash.clone(), name: old_t.name.clone() },
+            old_t.owner,
+        ));
+    }
 
     for new_t in new {
-        let old_t = old_map.get(new_t.hash.as_str()).unwrap();
+        let Some(old_t) = old_map.get(new_t.hash.as_str()) else {
+            events.push(AppEvent::Added(new_t.clone()));
+            events.push(AppEvent::Lifecycle(
+                LifecycleEvent::TorrentAdded { hash: new_t.hash.clone(), name: new_t.name.clone() },
+                new_t.owner,
+            ));
+            continue;
+        };
 
         // Manuel diff creating TorrentUpdate (which is the Patch struct)
         let mut patch = TorrentUpdate::default();
@@ -41,15 +61,26 @@ pub fn diff_torrents(old: &[Torrent], new: &[Torrent]) -> DiffResult {
             has_changes = true; 
             
             if old_t.percent_complete < 100.0 && new_t.percent_complete >= 100.0 {
-                events.push(AppEvent::Notification(SystemNotification {
-                    level: NotificationLevel::Success,
-                    message: format!("Torrent tamamlandı: {}", new_t.name),
-                }));
+                events.push(AppEvent::Lifecycle(
+                    LifecycleEvent::TorrentCompleted { hash: new_t.hash.clone(), name: new_t.name.clone() },
+                    new_t.owner,
+                ));
             }
         }
         if old_t.status != new_t.status { patch.status = Some(new_t.status.clone()); has_changes = true; }
-        if old_t.error_message != new_t.error_message { patch.error_message = Some(new_t.error_message.clone()); has_changes = true; }
+        if old_t.error_message != new_t.error_message {
+            patch.error_message = Some(new_t.error_message.clone());
+            has_changes = true;
+
+            if !new_t.error_message.is_empty() {
+                events.push(AppEvent::Lifecycle(error_event(new_t), new_t.owner));
+            }
+        }
         if old_t.label != new_t.label { patch.label = Some(new_t.label.clone()); has_changes = true; }
+        // Who may see the torrent changed; per-user lists are rebuilt
+        if old_t.owner != new_t.owner {
+            full_update = true;
+        }
 
         if has_changes {
             // Set the hash (not an Option in Patch usually, but check shared/src/lib.rs)
@@ -60,10 +91,96 @@ pub fn diff_torrents(old: &[Torrent], new: &[Torrent]) -> DiffResult {
         }
     }
 
-    if events.is_empty() {
+    if full_update {
+        events.retain(|e| matches!(e, AppEvent::Lifecycle(..)));
+        DiffResult::FullUpdate(events)
+    } else if events.is_empty() {
         DiffResult::NoChange
     } else {
         tracing::debug!("Generated {} partial updates", events.len());
         DiffResult::Partial(events)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::TorrentStatus;
+
+    fn torrent(hash: &str) -> Torrent {
+        Torrent {
+            hash: hash.to_string(),
+            name: hash.to_lowercase(),
+            size: 100,
+            completed: 0,
+            down_rate: 0,
+            up_rate: 0,
+            eta: 0,
+            percent_complete: 0.0,
+            status: TorrentStatus::Downloading,
+            error_message: String::new(),
+            added_date: 0,
+            label: None,
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn test_added_and_removed() {
+        let mut owned = torrent("BBB");
+        owned.owner = Some(3);
+        let old = vec![torrent("AAA"), owned];
+        let DiffResult::Partial(events) = diff_torrents(&[], &old[..1]) else {
+            panic!("expected a partial update");
+        };
+        assert!(matches!(&events[1], AppEvent::Lifecycle(LifecycleEvent::TorrentAdded { hash, .. }, _) if hash == "AAA"));
+        assert!(matches!(diff_torrents(&old, &old), DiffResult::NoChange));
+
+        let mut changed = torrent("AAA");
+        changed.down_rate = 5;
+        let new = vec![changed, torrent("CCC")];
+        let DiffResult::Partial(events) = diff_torrents(&old, &new) else {
+            panic!("expected a partial update");
+        };
+        assert!(matches!(&events[0], AppEvent::Removed(hash, Some(3)) if hash == "BBB"));
+        assert!(matches!(&events[1], AppEvent::Lifecycle(LifecycleEvent::TorrentRemoved { name, .. }, Some(3)) if name == "bbb"));
+        assert!(matches!(&events[2], AppEvent::Update(patch) if patch.down_rate == Some(5)));
+        assert!(matches!(&events[3], AppEvent::Added(t) if t.hash == "CCC"));
+        assert!(matches!(&events[4], AppEvent::Lifecycle(LifecycleEvent::TorrentAdded { .. }, None)));
+    }
+
+    #[test]
+    fn test_lifecycle_events() {
+        let old = vec![torrent("AAA"), torrent("BBB")];
+        let mut done = torrent("AAA");
+        done.percent_complete = 100.0;
+        let mut failing = torrent("BBB");
+        failing.error_message = "Tracker: [Failure reason \"unregistered torrent\"]".to_string();
+        let DiffResult::Partial(events) = diff_torrents(&old, &[done, failing]) else {
+            panic!("expected a partial update");
+        };
+        let lifecycle: Vec<_> = events
+            .into_iter()
+            .filter_map(|e| match e {
+                AppEvent::Lifecycle(l, _) => Some(l),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(&lifecycle[0], LifecycleEvent::TorrentCompleted { hash, .. } if hash == "AAA"));
+        assert!(matches!(&lifecycle[1], LifecycleEvent::TrackerError { hash, .. } if hash == "BBB"));
+    }
+
+    #[test]
+    fn test_owner_change_keeps_lifecycle_events() {
+        let old = vec![torrent("AAA"), torrent("BBB")];
+        let mut done = torrent("AAA");
+        done.percent_complete = 100.0;
+        let mut claimed = torrent("BBB");
+        claimed.owner = Some(2);
+        let DiffResult::FullUpdate(events) = diff_torrents(&old, &[done, claimed]) else {
+            panic!("expected a full update");
+        };
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], AppEvent::Lifecycle(LifecycleEvent::TorrentCompleted { hash, .. }, _) if hash == "AAA"));
+    }
+}
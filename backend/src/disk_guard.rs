@@ -0,0 +1,254 @@
+//! Disk usage reporting and the low-space guard, which stops downloading
+//! torrents on a filesystem that is running out of space and starts them
+//! again once it has recovered.
+
+use shared::xmlrpc::{parse_multicall_response, RpcParam, RtorrentClient, XmlRpcError};
+use shared::{AppEvent, DiskSpace, LifecycleEvent, NotificationLevel, SystemNotification};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use crate::event_bus::EventBus;
+use crate::AppState;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Disk usage changes slowly and takes a full torrent listing plus a
+/// `statvfs` per directory, so stats polls reuse it for this long.
+const USAGE_REFRESH: Duration = Duration::from_secs(30);
+/// Torrents are resumed once free space is this much above the threshold,
+/// so they do not flap around it.
+const RESUME_HYSTERESIS_PERCENT: u64 = 10;
+const SETTING_PAUSED: &str = "disk_guard.paused";
+
+/// Parses sizes like `500M`, `10G` or a plain number of bytes.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (number, multiplier) = match s.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => {
+            let multiplier: u64 = match c.to_ascii_uppercase() {
+                'K' => 1 << 10,
+                'M' => 1 << 20,
+                'G' => 1 << 30,
+                'T' => 1 << 40,
+                _ => return Err(format!("Unknown size suffix '{}'", c)),
+            };
+            (&s[..i], multiplier)
+        }
+        _ => (s, 1),
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .map_err(|e| format!("Invalid size '{}': {}", s, e))?
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("Size '{}' is too large", s))
+}
+
+/// `statvfs` fails for directories rTorrent has not created yet, so fall
+/// back to the closest existing ancestor.
+fn disk_space_near(path: &Path) -> Option<DiskSpace> {
+    path.ancestors()
+        .find(|p| p.exists())
+        .and_then(|p| shared::disk::disk_space(p).ok())
+}
+
+struct TorrentLocation {
+    hash: String,
+    directory: String,
+    complete: bool,
+    started: bool,
+}
+
+async fn fetch_locations(client: &RtorrentClient) -> Result<Vec<TorrentLocation>, XmlRpcError> {
+    let params: Vec<RpcParam> = ["", "main", "d.hash=", "d.directory=", "d.complete=", "d.state="]
+        .iter()
+        .map(|s| RpcParam::from(*s))
+        .collect();
+    let rows = parse_multicall_response(&client.call("d.multicall2", &params).await?)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TorrentLocation {
+            hash: row.first().cloned().unwrap_or_default(),
+            directory: row.get(1).cloned().unwrap_or_default(),
+            complete: row.get(2).map(|s| s == "1").unwrap_or(false),
+            started: row.get(3).map(|s| s == "1").unwrap_or(false),
+        })
+        .collect())
+}
+
+/// Resolves each directory to its filesystem, one `statvfs` per directory.
+async fn usage_by_directory(directories: BTreeSet<String>) -> HashMap<String, DiskSpace> {
+    tokio::task::spawn_blocking(move || {
+        directories
+            .into_iter()
+            .filter_map(|dir| disk_space_near(Path::new(&dir)).map(|space| (dir, space)))
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Free space on `directory.default` plus usage of every mount that holds
+/// torrent data, for `GlobalStats`.
+async fn fetch_disk_usage(client: &RtorrentClient) -> Result<(Option<i64>, Vec<DiskSpace>), XmlRpcError> {
+    let default_dir = shared::xmlrpc::parse_string_response(&client.call("directory.default", &[]).await?)?;
+    let mut directories: BTreeSet<String> = fetch_locations(client)
+        .await?
+        .into_iter()
+        .map(|t| t.directory)
+        .filter(|d| !d.is_empty())
+        .collect();
+    directories.insert(default_dir.clone());
+
+    let usage = usage_by_directory(directories).await;
+    let free_space = usage.get(&default_dir).map(|s| s.available_bytes as i64);
+
+    let mut mounts: Vec<DiskSpace> = Vec::new();
+    for space in usage.into_values() {
+        if !mounts.iter().any(|m| m.mount_point == space.mount_point) {
+            mounts.push(space);
+        }
+    }
+    mounts.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+
+    Ok((free_space, mounts))
+}
+
+/// Disk usage for `GlobalStats`, refreshed every `USAGE_REFRESH`.
+#[derive(Default)]
+pub struct DiskUsageCache {
+    usage: (Option<i64>, Vec<DiskSpace>),
+    refreshed_at: Option<Instant>,
+}
+
+impl DiskUsageCache {
+    pub async fn get(&mut self, client: &RtorrentClient) -> (Option<i64>, Vec<DiskSpace>) {
+        if self.refreshed_at.is_none_or(|at| at.elapsed() >= USAGE_REFRESH) {
+            match fetch_disk_usage(client).await {
+                Ok(usage) => self.usage = usage,
+                Err(e) => tracing::debug!("Disk usage refresh failed: {}", e),
+            }
+            self.refreshed_at = Some(Instant::now());
+        }
+        self.usage.clone()
+    }
+}
+
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GB", bytes as f64 / (1u64 << 30) as f64)
+}
+
+fn notify(event_bus: &EventBus, level: NotificationLevel, message: String) {
+    let _ = event_bus.send(AppEvent::Notification(SystemNotification { level, message }));
+}
+
+/// Runs forever. Torrents stopped by the guard are remembered in the
+/// database so they are still resumed after a restart.
+pub async fn run(state: AppState, min_free_bytes: u64) {
+    let AppState { scgi_socket_path, db, event_bus, .. } = &state;
+    let client = RtorrentClient::new(scgi_socket_path);
+    let resume_bytes = min_free_bytes + min_free_bytes * RESUME_HYSTERESIS_PERCENT / 100;
+    let mut paused: BTreeSet<String> = db
+        .get_setting(SETTING_PAUSED)
+        .await
+        .ok()
+        .flatten()
+        .map(|v| v.split(',').filter(|h| !h.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+
+    tracing::info!("Disk guard active: pausing downloads below {}", format_gib(min_free_bytes));
+
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+
+        let torrents = match fetch_locations(&client).await {
+            Ok(torrents) => torrents,
+            Err(e) => {
+                tracing::debug!("Disk guard skipped a check: {}", e);
+                continue;
+            }
+        };
+        let usage = usage_by_directory(torrents.iter().map(|t| t.directory.clone()).collect()).await;
+        let before = paused.clone();
+
+        // Stop downloads on filesystems below the threshold
+        let mut stopped: HashMap<String, (u64, usize)> = HashMap::new();
+        for torrent in torrents.iter().filter(|t| t.started && !t.complete) {
+            let Some(space) = usage.get(&torrent.directory) else { continue };
+            if space.available_bytes >= min_free_bytes {
+                continue;
+            }
+            if let Err(e) = client.call("d.stop", &[RpcParam::from(torrent.hash.as_str())]).await {
+                tracing::warn!("Disk guard failed to stop {}: {}", torrent.hash, e);
+                continue;
+            }
+            paused.insert(torrent.hash.clone());
+            stopped.entry(space.mount_point.clone()).or_insert((space.available_bytes, 0)).1 += 1;
+        }
+        for (mount, (available, count)) in stopped {
+            tracing::warn!("Low disk space on {} ({}), stopped {} torrents", mount, format_gib(available), count);
+            let event = LifecycleEvent::DiskLow { mount_point: mount, available_bytes: available, paused: count };
+            let _ = event_bus.send(AppEvent::Lifecycle(event, None));
+        }
+
+        // Resume what we stopped once there is room again
+        let by_hash: HashMap<&str, &TorrentLocation> = torrents.iter().map(|t| (t.hash.as_str(), t)).collect();
+        let mut resumed = 0;
+        let mut still_paused = BTreeSet::new();
+        for hash in &paused {
+            // Forget torrents that were removed or finished meanwhile
+            let Some(torrent) = by_hash.get(hash.as_str()) else { continue };
+            if torrent.complete {
+                continue;
+            }
+            let recovered = usage
+                .get(&torrent.directory)
+                .is_some_and(|space| space.available_bytes >= resume_bytes);
+            if !recovered {
+                still_paused.insert(hash.clone());
+                continue;
+            }
+            if !torrent.started {
+                match client.call("d.start", &[RpcParam::from(hash.as_str())]).await {
+                    Ok(_) => resumed += 1,
+                    Err(e) => {
+                        tracing::warn!("Disk guard failed to resume {}: {}", hash, e);
+                        still_paused.insert(hash.clone());
+                    }
+                }
+            }
+        }
+        paused = still_paused;
+
+        if resumed > 0 {
+            tracing::info!("Disk space recovered, resumed {} torrents", resumed);
+            notify(
+                event_bus,
+                NotificationLevel::Info,
+                format!("Disk alanı yeterli, {} torrent devam ettirildi.", resumed),
+            );
+        }
+
+        if paused != before {
+            let value = paused.iter().cloned().collect::<Vec<_>>().join(",");
+            if let Err(e) = db.set_setting(SETTING_PAUSED, &value).await {
+                tracing::warn!("Failed to persist disk guard state: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024"), Ok(1024));
+        assert_eq!(parse_size("500M"), Ok(500 << 20));
+        assert_eq!(parse_size("10g"), Ok(10 << 30));
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("G").is_err());
+        assert!(parse_size("99999999999T").is_err());
+    }
+}
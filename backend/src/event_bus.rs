@@ -0,0 +1,179 @@
+//! Broadcast of `AppEvent`s with sequence numbers and a bounded replay
+//! buffer. SSE clients that reconnect or fall behind get only the events
+//! they missed, and a full list only when the gap is no longer buffered.
+
+use shared::AppEvent;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Events kept for replay. Stats are not counted: they are superseded every
+/// second, so replaying old ones would only cost bytes.
+const REPLAY_CAPACITY: usize = 1024;
+
+pub type Sequenced = (u64, AppEvent);
+
+#[derive(Default)]
+struct ReplayBuffer {
+    last_seq: u64,
+    /// Highest sequence number dropped from the buffer; anything after it
+    /// can still be replayed.
+    evicted_through: u64,
+    events: VecDeque<Sequenced>,
+}
+
+impl ReplayBuffer {
+    fn push(&mut self, event: &AppEvent) -> u64 {
+        self.last_seq += 1;
+        if !matches!(event, AppEvent::Stats(_)) {
+            self.events.push_back((self.last_seq, event.clone()));
+            if self.events.len() > REPLAY_CAPACITY {
+                if let Some((seq, _)) = self.events.pop_front() {
+                    self.evicted_through = seq;
+                }
+            }
+        }
+        self.last_seq
+    }
+
+    /// Events after `last_seen`, or `None` if some of them are gone (or
+    /// `last_seen` was never issued).
+    fn since(&self, last_seen: u64) -> Option<Vec<Sequenced>> {
+        if last_seen > self.last_seq || last_seen < self.evicted_through {
+            return None;
+        }
+        Some(self.events.iter().filter(|(seq, _)| *seq > last_seen).cloned().collect())
+    }
+}
+
+/// Resume point handed to a (re)connecting client.
+pub struct Resume {
+    pub receiver: broadcast::Receiver<Sequenced>,
+    pub last_seq: u64,
+    /// `None` means the client needs a full list.
+    pub missed: Option<Vec<Sequenced>>,
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Sequenced>,
+    replay: Arc<Mutex<ReplayBuffer>>,
+    /// Receivers held by background tasks rather than clients
+    internal_receivers: Arc<AtomicUsize>,
+    /// Process start time, part of every event ID so IDs from before a
+    /// restart are not mistaken for current ones.
+    epoch: u64,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self {
+            sender,
+            replay: Arc::new(Mutex::new(ReplayBuffer::default())),
+            internal_receivers: Arc::new(AtomicUsize::new(0)),
+            epoch: crate::history::unix_now() as u64,
+        }
+    }
+
+    pub fn send(&self, event: AppEvent) -> u64 {
+        // Sequencing and broadcasting under one lock keeps the channel in
+        // sequence order and makes `resume` race-free
+        let mut replay = self.replay.lock().unwrap();
+        let seq = replay.push(&event);
+        let _ = self.sender.send((seq, event));
+        seq
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Sequenced> {
+        self.sender.subscribe()
+    }
+
+    /// For server-side listeners that live as long as the process. They
+    /// are left out of `receiver_count`, so they do not keep fast polling on.
+    pub fn subscribe_internal(&self) -> broadcast::Receiver<Sequenced> {
+        self.internal_receivers.fetch_add(1, Ordering::Relaxed);
+        self.sender.subscribe()
+    }
+
+    /// Connected clients.
+    pub fn receiver_count(&self) -> usize {
+        self.sender
+            .receiver_count()
+            .saturating_sub(self.internal_receivers.load(Ordering::Relaxed))
+    }
+
+    /// Subscribes and collects what was missed since `last_seen` in one
+    /// step, so no event falls between the replay and the live stream.
+    pub fn resume(&self, last_seen: Option<u64>) -> Resume {
+        let replay = self.replay.lock().unwrap();
+        Resume {
+            receiver: self.sender.subscribe(),
+            last_seq: replay.last_seq,
+            missed: last_seen.and_then(|seq| replay.since(seq)),
+        }
+    }
+
+    /// Like `resume`, for a receiver that lagged: `(last_seq, missed)`.
+    pub fn since(&self, last_seen: u64) -> (u64, Option<Vec<Sequenced>>) {
+        let replay = self.replay.lock().unwrap();
+        (replay.last_seq, replay.since(last_seen))
+    }
+
+    pub fn event_id(&self, seq: u64) -> String {
+        format!("{}-{}", self.epoch, seq)
+    }
+
+    /// The sequence number in an event ID issued by this process.
+    pub fn parse_event_id(&self, id: &str) -> Option<u64> {
+        let (epoch, seq) = id.trim().split_once('-')?;
+        if epoch.parse::<u64>().ok()? != self.epoch {
+            return None;
+        }
+        seq.parse().ok()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::{GlobalStats, NotificationLevel, SystemNotification};
+
+    fn notification(message: &str) -> AppEvent {
+        AppEvent::Notification(SystemNotification { level: NotificationLevel::Info, message: message.to_string() })
+    }
+
+    #[test]
+    fn test_replay_since() {
+        let mut buffer = ReplayBuffer::default();
+        assert_eq!(buffer.since(0).map(|e| e.len()), Some(0));
+        for i in 0..REPLAY_CAPACITY + 10 {
+            buffer.push(&notification(&i.to_string()));
+            buffer.push(&AppEvent::Stats(GlobalStats::default()));
+        }
+        let last = buffer.last_seq;
+        assert_eq!(buffer.since(last).map(|e| e.len()), Some(0));
+        // Stats are sequenced but not replayed
+        assert_eq!(buffer.since(last - 4).map(|e| e.len()), Some(2));
+        assert!(buffer.since(last + 1).is_none());
+        assert!(buffer.since(1).is_none());
+        assert!(buffer.since(buffer.evicted_through).is_some());
+    }
+
+    #[test]
+    fn test_event_id_round_trip() {
+        let bus = EventBus::new();
+        assert_eq!(bus.parse_event_id(&bus.event_id(42)), Some(42));
+        assert_eq!(bus.parse_event_id("1-42"), None);
+        assert_eq!(bus.parse_event_id("garbage"), None);
+    }
+}
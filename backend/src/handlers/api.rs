@@ -0,0 +1,332 @@
+//! Versioned REST/JSON API under `/api/v1`, for scripts and tools that do
+//! not speak the msgpack server function protocol. Handlers reuse the
+//! `*_inner` helpers behind the server functions.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use leptos::prelude::ServerFnError;
+use serde::{Deserialize, Serialize};
+use shared::server_fns::{settings, torrent};
+use shared::xmlrpc::RtorrentClient;
+use shared::{
+    AddTorrentRequest, AuthUser, GlobalLimitRequest, SetFilePriorityRequest, SetLabelRequest, Torrent, TorrentActionRequest,
+    TorrentFile, TorrentPeer, TorrentTracker,
+};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+use crate::AppState;
+
+#[derive(Serialize, ToSchema)]
+pub struct ApiErrorBody {
+    pub error: String,
+}
+
+pub struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self(StatusCode::BAD_REQUEST, message.into())
+    }
+
+    fn not_found() -> Self {
+        Self(StatusCode::NOT_FOUND, "Torrent not found".to_string())
+    }
+}
+
+impl From<ServerFnError> for ApiError {
+    fn from(e: ServerFnError) -> Self {
+        let message = match e {
+            ServerFnError::ServerError(message) => message,
+            other => other.to_string(),
+        };
+        Self(StatusCode::BAD_GATEWAY, message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(ApiErrorBody { error: self.1 })).into_response()
+    }
+}
+
+type ApiResult<T> = Result<T, ApiError>;
+
+fn client(state: &AppState) -> RtorrentClient {
+    RtorrentClient::new(&state.scgi_socket_path)
+}
+
+/// 404s for hashes that are not in the latest poll, so typos do not reach
+/// rTorrent, and for other users' torrents.
+fn require_torrent(state: &AppState, user: &AuthUser, hash: &str) -> ApiResult<Torrent> {
+    state
+        .tx
+        .borrow()
+        .iter()
+        .find(|t| t.hash.eq_ignore_ascii_case(hash) && user.sees(t))
+        .cloned()
+        .ok_or_else(ApiError::not_found)
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ListQuery {
+    /// Only torrents with this label
+    pub label: Option<String>,
+}
+
+/// List torrents as of the latest poll.
+#[utoipa::path(
+    get,
+    path = "/api/v1/torrents",
+    params(ListQuery),
+    responses((status = 200, body = Vec<Torrent>)),
+    tag = "torrents"
+)]
+pub async fn list_torrents(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Query(query): Query<ListQuery>,
+) -> Json<Vec<Torrent>> {
+    let mut torrents: Vec<Torrent> = state
+        .tx
+        .borrow()
+        .iter()
+        .filter(|t| user.sees(t))
+        .filter(|t| query.label.as_deref().is_none_or(|label| t.label.as_deref() == Some(label)))
+        .cloned()
+        .collect();
+    torrents.sort_by(|a, b| a.name.cmp(&b.name));
+    Json(torrents)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/torrents/{hash}",
+    params(("hash" = String, Path, description = "Info hash")),
+    responses((status = 200, body = Torrent), (status = 404, body = ApiErrorBody)),
+    tag = "torrents"
+)]
+pub async fn get_torrent(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(hash): Path<String>,
+) -> ApiResult<Json<Torrent>> {
+    require_torrent(&state, &user, &hash).map(Json)
+}
+
+/// Add a magnet link or torrent URL.
+#[utoipa::path(
+    post,
+    path = "/api/v1/torrents",
+    request_body = AddTorrentRequest,
+    responses((status = 201), (status = 400, body = ApiErrorBody), (status = 502, body = ApiErrorBody)),
+    tag = "torrents"
+)]
+pub async fn add_torrent(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<AddTorrentRequest>,
+) -> ApiResult<StatusCode> {
+    if req.uri.trim().is_empty() {
+        return Err(ApiError::bad_request("uri is required"));
+    }
+    let (uri, save_path) = (req.uri.trim(), req.save_path.as_deref());
+    torrent::add_torrent_inner(&client(&state), &state.browse_roots, uri, save_path, Some(user.id)).await?;
+    state.notify_poll.notify_one();
+    Ok(StatusCode::CREATED)
+}
+
+/// Start, stop or delete a torrent.
+#[utoipa::path(
+    post,
+    path = "/api/v1/torrents/action",
+    request_body = TorrentActionRequest,
+    responses((status = 204), (status = 400, body = ApiErrorBody), (status = 404, body = ApiErrorBody)),
+    tag = "torrents"
+)]
+pub async fn torrent_action(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<TorrentActionRequest>,
+) -> ApiResult<StatusCode> {
+    if !torrent::TORRENT_ACTIONS.contains(&req.action.as_str()) {
+        return Err(ApiError::bad_request(format!(
+            "Unknown action, expected one of: {}",
+            torrent::TORRENT_ACTIONS.join(", ")
+        )));
+    }
+    let target = require_torrent(&state, &user, &req.hash)?;
+    torrent::torrent_action_inner(&client(&state), &target.hash, &req.action).await?;
+    state.notify_poll.notify_one();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct DeleteQuery {
+    /// Also delete downloaded data
+    #[serde(default)]
+    pub delete_data: bool,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/torrents/{hash}",
+    params(("hash" = String, Path, description = "Info hash"), DeleteQuery),
+    responses((status = 204), (status = 404, body = ApiErrorBody)),
+    tag = "torrents"
+)]
+pub async fn delete_torrent(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(hash): Path<String>,
+    Query(query): Query<DeleteQuery>,
+) -> ApiResult<StatusCode> {
+    let target = require_torrent(&state, &user, &hash)?;
+    let action = if query.delete_data { "delete_with_data" } else { "delete" };
+    torrent::torrent_action_inner(&client(&state), &target.hash, action).await?;
+    state.notify_poll.notify_one();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/torrents/{hash}/files",
+    params(("hash" = String, Path, description = "Info hash")),
+    responses((status = 200, body = Vec<TorrentFile>), (status = 404, body = ApiErrorBody)),
+    tag = "torrents"
+)]
+pub async fn get_files(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(hash): Path<String>,
+) -> ApiResult<Json<Vec<TorrentFile>>> {
+    let target = require_torrent(&state, &user, &hash)?;
+    Ok(Json(torrent::get_files_inner(&client(&state), &target.hash).await?))
+}
+
+/// Set the priority (0 off, 1 normal, 2 high) of one file.
+#[utoipa::path(
+    post,
+    path = "/api/v1/torrents/files/priority",
+    request_body = SetFilePriorityRequest,
+    responses((status = 204), (status = 400, body = ApiErrorBody), (status = 404, body = ApiErrorBody)),
+    tag = "torrents"
+)]
+pub async fn set_file_priority(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<SetFilePriorityRequest>,
+) -> ApiResult<StatusCode> {
+    if req.priority > 2 {
+        return Err(ApiError::bad_request("priority must be 0, 1 or 2"));
+    }
+    let target = require_torrent(&state, &user, &req.hash)?;
+    torrent::set_files_priority_inner(&client(&state), &target.hash, &[req.file_index], req.priority).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/torrents/{hash}/peers",
+    params(("hash" = String, Path, description = "Info hash")),
+    responses((status = 200, body = Vec<TorrentPeer>), (status = 404, body = ApiErrorBody)),
+    tag = "torrents"
+)]
+pub async fn get_peers(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(hash): Path<String>,
+) -> ApiResult<Json<Vec<TorrentPeer>>> {
+    let target = require_torrent(&state, &user, &hash)?;
+    Ok(Json(torrent::get_peers_inner(&client(&state), &target.hash).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/torrents/{hash}/trackers",
+    params(("hash" = String, Path, description = "Info hash")),
+    responses((status = 200, body = Vec<TorrentTracker>), (status = 404, body = ApiErrorBody)),
+    tag = "torrents"
+)]
+pub async fn get_trackers(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(hash): Path<String>,
+) -> ApiResult<Json<Vec<TorrentTracker>>> {
+    let target = require_torrent(&state, &user, &hash)?;
+    Ok(Json(torrent::get_trackers_inner(&client(&state), &target.hash).await?))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LabelSummary {
+    pub name: String,
+    pub torrents: usize,
+}
+
+/// Labels in use, with the number of torrents carrying each.
+#[utoipa::path(
+    get,
+    path = "/api/v1/labels",
+    responses((status = 200, body = Vec<LabelSummary>)),
+    tag = "labels"
+)]
+pub async fn list_labels(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+) -> Json<Vec<LabelSummary>> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let torrents = state.tx.borrow();
+    for label in torrents.iter().filter(|t| user.sees(t)).filter_map(|t| t.label.clone()).filter(|l| !l.is_empty()) {
+        *counts.entry(label).or_default() += 1;
+    }
+    Json(counts.into_iter().map(|(name, torrents)| LabelSummary { name, torrents }).collect())
+}
+
+/// Set a torrent's label; an empty label removes it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/torrents/label",
+    request_body = SetLabelRequest,
+    responses((status = 204), (status = 404, body = ApiErrorBody)),
+    tag = "labels"
+)]
+pub async fn set_label(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<SetLabelRequest>,
+) -> ApiResult<StatusCode> {
+    let target = require_torrent(&state, &user, &req.hash)?;
+    torrent::set_label_inner(&client(&state), &target.hash, req.label.trim()).await?;
+    state.notify_poll.notify_one();
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Global rate limits in bytes per second, 0 means unlimited.
+#[utoipa::path(
+    get,
+    path = "/api/v1/limits",
+    responses((status = 200, body = GlobalLimitRequest)),
+    tag = "settings"
+)]
+pub async fn get_limits(State(state): State<AppState>) -> ApiResult<Json<GlobalLimitRequest>> {
+    Ok(Json(settings::get_global_limits_inner(&client(&state)).await?))
+}
+
+/// Change global rate limits; omitted fields are left as they are.
+#[utoipa::path(
+    put,
+    path = "/api/v1/limits",
+    request_body = GlobalLimitRequest,
+    responses((status = 204), (status = 400, body = ApiErrorBody)),
+    tag = "settings"
+)]
+pub async fn set_limits(State(state): State<AppState>, Json(req): Json<GlobalLimitRequest>) -> ApiResult<StatusCode> {
+    if req.max_download_rate.is_some_and(|r| r < 0) || req.max_upload_rate.is_some_and(|r| r < 0) {
+        return Err(ApiError::bad_request("limits must not be negative"));
+    }
+    settings::set_global_limits_inner(&client(&state), req.max_download_rate, req.max_upload_rate).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,116 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use serde::Deserialize;
+use shared::paths::{resolve_torrent_path, PathError};
+use shared::xmlrpc::RtorrentClient;
+use shared::AuthUser;
+use tower_http::services::ServeFile;
+use crate::archive::{self, ArchiveFormat};
+use crate::visibility::sees_hash;
+use crate::AppState;
+
+#[derive(Deserialize)]
+pub struct DownloadQuery {
+    /// Path relative to the torrent root, empty for the whole torrent
+    #[serde(default)]
+    pub path: String,
+    /// Archive format for folders: `zip` (default) or `tar`
+    pub format: Option<String>,
+}
+
+/// `attachment` or `inline` disposition with an ASCII fallback and the
+/// RFC 5987 UTF-8 name.
+pub fn content_disposition(kind: &'static str, file_name: &str) -> HeaderValue {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' { c } else { '_' })
+        .collect();
+    let encoded: String = file_name
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+
+    HeaderValue::from_str(&format!("{}; filename=\"{}\"; filename*=UTF-8''{}", kind, fallback, encoded))
+        .unwrap_or_else(|_| HeaderValue::from_static(kind))
+}
+
+pub fn path_error_response(e: PathError) -> Response {
+    let status = match &e {
+        PathError::OutsideRoot | PathError::IsRoot => StatusCode::FORBIDDEN,
+        PathError::InvalidPath(_) => StatusCode::NOT_FOUND,
+        PathError::Rpc(_) | PathError::InvalidRoot(_) => StatusCode::BAD_GATEWAY,
+    };
+    (status, e.to_string()).into_response()
+}
+
+/// Serves a torrent's file (with Range support) or streams a folder as an archive.
+pub async fn download_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path(hash): Path<String>,
+    Query(query): Query<DownloadQuery>,
+    request: Request,
+) -> Response {
+    if !sees_hash(&user, &state.tx.borrow(), &hash) {
+        return (StatusCode::NOT_FOUND, "Torrent not found").into_response();
+    }
+    let client = RtorrentClient::new(&state.scgi_socket_path);
+    let target = match resolve_torrent_path(&client, &hash, &query.path).await {
+        Ok(target) => target,
+        Err(e) => return path_error_response(e),
+    };
+    let file_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "download".to_string());
+
+    let meta = match tokio::fs::metadata(&target).await {
+        Ok(meta) => meta,
+        Err(e) => return (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+
+    if meta.is_file() {
+        return match ServeFile::new(&target).try_call(request).await {
+            Ok(res) => {
+                let mut res = res.map(Body::new);
+                res.headers_mut()
+                    .insert(header::CONTENT_DISPOSITION, content_disposition("attachment", &file_name));
+                res
+            }
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+    }
+
+    let format = match query.format.as_deref() {
+        None => ArchiveFormat::Zip,
+        Some(f) => match ArchiveFormat::parse(f) {
+            Some(format) => format,
+            None => return (StatusCode::BAD_REQUEST, "Unsupported archive format").into_response(),
+        },
+    };
+    let entries = match archive::collect_entries(&target).await {
+        Ok(entries) => entries,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    tracing::info!("Streaming {} archive of {:?} ({} files)", format.extension(), target, entries.len());
+    (
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static(format.content_type())),
+            (
+                header::CONTENT_DISPOSITION,
+                content_disposition("attachment", &format!("{}.{}", file_name, format.extension())),
+            ),
+        ],
+        archive::stream(format, entries),
+    )
+        .into_response()
+}
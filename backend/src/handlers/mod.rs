@@ -5,9 +5,11 @@ use axum::{
 };
 use rust_embed::RustEmbed;
 
+pub mod api;
 pub mod auth;
+pub mod download;
 pub mod setup;
-pub mod notifications;
+pub mod stream;
 
 #[derive(RustEmbed)]
 #[folder = "../frontend/dist"]
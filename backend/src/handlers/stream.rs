@@ -0,0 +1,145 @@
+use axum::{
+    body::Body,
+    extract::{Path, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use shared::paths::resolve_torrent_path;
+use shared::server_fns::torrent::{fetch_file_span, fetch_piece_map};
+use shared::xmlrpc::RtorrentClient;
+use shared::AuthUser;
+use std::time::Duration;
+use tower_http::services::ServeFile;
+use crate::handlers::download::{content_disposition, path_error_response};
+use crate::visibility::sees_hash;
+use crate::AppState;
+
+/// How long a request for a missing piece waits before giving up with 416.
+const PIECE_WAIT: Duration = Duration::from_secs(10);
+const PIECE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Parses a single `bytes=` range against a file of `size` bytes into an
+/// inclusive `(start, end)`. Multi-range requests are not used by media
+/// elements and are treated as unsatisfiable.
+fn parse_range(header: Option<&str>, size: u64) -> Option<(u64, u64)> {
+    let Some(spec) = header else {
+        return (size > 0).then(|| (0, size - 1));
+    };
+    let spec = spec.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (size.saturating_sub(suffix), size.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, size.checked_sub(1)?),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(size.checked_sub(1)?)),
+    };
+    (start <= end && end < size).then_some((start, end))
+}
+
+fn unsatisfiable(size: u64) -> Response {
+    let mut res = StatusCode::RANGE_NOT_SATISFIABLE.into_response();
+    if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", size)) {
+        res.headers_mut().insert(header::CONTENT_RANGE, value);
+    }
+    res
+}
+
+/// Serves a torrent file for `<video>`/`<audio>` playback while it is still
+/// downloading. A requested range is cut short at the first missing piece;
+/// if its very first piece is missing the request waits briefly for it and
+/// answers 416 if it does not arrive.
+pub async fn stream_handler(
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    Path((hash, file_index)): Path<(String, u32)>,
+    mut request: Request,
+) -> Response {
+    if !sees_hash(&user, &state.tx.borrow(), &hash) {
+        return (StatusCode::NOT_FOUND, "Torrent not found").into_response();
+    }
+    let client = RtorrentClient::new(&state.scgi_socket_path);
+
+    let span = match fetch_file_span(&client, &hash, file_index).await {
+        Ok(span) => span,
+        Err(e) => return (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+    };
+    let target = match resolve_torrent_path(&client, &hash, &span.path).await {
+        Ok(target) => target,
+        Err(e) => return path_error_response(e),
+    };
+
+    let size = span.size.max(0) as u64;
+    let range_header = request
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let Some((start, end)) = parse_range(range_header.as_deref(), size) else {
+        return unsatisfiable(size);
+    };
+
+    let offset = span.offset.max(0) as u64;
+    let deadline = tokio::time::Instant::now() + PIECE_WAIT;
+    let available_end = loop {
+        let map = match fetch_piece_map(&client, &hash).await {
+            Ok(map) => map,
+            Err(e) => return (StatusCode::BAD_GATEWAY, e.to_string()).into_response(),
+        };
+        let piece_size = map.piece_size.max(1) as u64;
+        let first = ((offset + start) / piece_size) as u32;
+        let last = ((offset + end) / piece_size) as u32;
+
+        match map.first_missing(first, last) {
+            None => break end,
+            Some(missing) if missing > first => {
+                // Serve up to the end of the last complete piece
+                break (missing as u64 * piece_size - 1 - offset).min(end);
+            }
+            Some(_) if tokio::time::Instant::now() >= deadline => return unsatisfiable(size),
+            Some(_) => tokio::time::sleep(PIECE_POLL_INTERVAL).await,
+        }
+    };
+
+    // Let ServeFile do the actual range handling, with the end clamped
+    if range_header.is_some() || available_end < size.saturating_sub(1) {
+        if let Ok(value) = HeaderValue::from_str(&format!("bytes={}-{}", start, available_end)) {
+            request.headers_mut().insert(header::RANGE, value);
+        }
+    }
+
+    match ServeFile::new(&target).try_call(request).await {
+        Ok(res) => {
+            let mut res = res.map(Body::new);
+            let file_name = target
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            res.headers_mut()
+                .insert(header::CONTENT_DISPOSITION, content_disposition("inline", &file_name));
+            res
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range(None, 100), Some((0, 99)));
+        assert_eq!(parse_range(Some("bytes=10-"), 100), Some((10, 99)));
+        assert_eq!(parse_range(Some("bytes=10-500"), 100), Some((10, 99)));
+        assert_eq!(parse_range(Some("bytes=-30"), 100), Some((70, 99)));
+        assert_eq!(parse_range(Some("bytes=100-"), 100), None);
+        assert_eq!(parse_range(Some("bytes=0-1,5-6"), 100), None);
+        assert_eq!(parse_range(None, 0), None);
+    }
+}
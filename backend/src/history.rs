@@ -0,0 +1,179 @@
+//! Transfer history sampler. The poll loop feeds it every `GlobalStats` it
+//! fetches; once a minute the averaged rates and the byte deltas since the
+//! previous minute are written to the database.
+
+use shared::history::{self, DAY, HOUR, MINUTE};
+use shared::xmlrpc::{parse_i64_response, parse_multicall_response, RpcParam, RtorrentClient, XmlRpcError};
+use shared::{GlobalStats, TransferSample};
+use std::collections::HashMap;
+
+/// rTorrent's totals are per session, a drop means it restarted (or the
+/// torrent was re-added) and counting started again from zero.
+fn delta(previous: Option<i64>, current: i64) -> i64 {
+    match previous {
+        Some(previous) if current >= previous => current - previous,
+        Some(_) => current,
+        // No baseline yet, whatever happened before the backend started is unknown
+        None => 0,
+    }
+}
+
+pub(crate) fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[derive(Default)]
+struct Window {
+    ts: i64,
+    samples: i64,
+    down_sum: i64,
+    up_sum: i64,
+    down_peak: i64,
+    up_peak: i64,
+}
+
+struct TorrentTotals {
+    name: String,
+    downloaded: i64,
+    uploaded: i64,
+}
+
+pub struct Sampler {
+    db: shared::db::Db,
+    window: Option<Window>,
+    global_totals: Option<(i64, i64)>,
+    torrent_totals: HashMap<String, TorrentTotals>,
+    last_maintenance: i64,
+}
+
+impl Sampler {
+    pub fn new(db: shared::db::Db) -> Self {
+        Self {
+            db,
+            window: None,
+            global_totals: None,
+            torrent_totals: HashMap::new(),
+            last_maintenance: 0,
+        }
+    }
+
+    pub async fn record(&mut self, client: &RtorrentClient, stats: &GlobalStats) {
+        let now = unix_now();
+        let minute = now.div_euclid(MINUTE) * MINUTE;
+
+        if self.window.as_ref().is_some_and(|w| w.ts != minute) {
+            if let Some(window) = self.window.take() {
+                if let Err(e) = self.flush(client, window).await {
+                    tracing::warn!("Failed to record transfer history: {}", e);
+                }
+            }
+        }
+
+        let window = self.window.get_or_insert_with(|| Window { ts: minute, ..Default::default() });
+        window.samples += 1;
+        window.down_sum += stats.down_rate;
+        window.up_sum += stats.up_rate;
+        window.down_peak = window.down_peak.max(stats.down_rate);
+        window.up_peak = window.up_peak.max(stats.up_rate);
+
+        if now - self.last_maintenance >= HOUR {
+            self.last_maintenance = now;
+            self.maintain(now).await;
+        }
+    }
+
+    async fn flush(&mut self, client: &RtorrentClient, window: Window) -> anyhow::Result<()> {
+        let (downloaded, uploaded) = self.global_deltas(client).await?;
+        let samples = window.samples.max(1);
+        self.db
+            .insert_transfer_sample(&TransferSample {
+                ts: window.ts,
+                resolution: MINUTE,
+                down_rate: window.down_sum / samples,
+                up_rate: window.up_sum / samples,
+                down_peak: window.down_peak,
+                up_peak: window.up_peak,
+                downloaded,
+                uploaded,
+            })
+            .await?;
+
+        let day = window.ts.div_euclid(DAY) * DAY;
+        for (hash, name, downloaded, uploaded) in self.torrent_deltas(client).await? {
+            self.db.add_torrent_transfer(day, &hash, &name, downloaded, uploaded).await?;
+        }
+        Ok(())
+    }
+
+    async fn global_deltas(&mut self, client: &RtorrentClient) -> Result<(i64, i64), XmlRpcError> {
+        let down = parse_i64_response(&client.call("throttle.global_down.total", &[]).await?)?;
+        let up = parse_i64_response(&client.call("throttle.global_up.total", &[]).await?)?;
+        let previous = self.global_totals.replace((down, up));
+
+        Ok((delta(previous.map(|p| p.0), down), delta(previous.map(|p| p.1), up)))
+    }
+
+    /// Bytes each torrent moved since the last flush, only for torrents that
+    /// moved any.
+    async fn torrent_deltas(&mut self, client: &RtorrentClient) -> Result<Vec<(String, String, i64, i64)>, XmlRpcError> {
+        let params: Vec<RpcParam> = ["", "main", "d.hash=", "d.name=", "d.down.total=", "d.up.total="]
+            .iter()
+            .map(|s| RpcParam::from(*s))
+            .collect();
+        let rows = parse_multicall_response(&client.call("d.multicall2", &params).await?)?;
+
+        let mut current = HashMap::with_capacity(rows.len());
+        let mut deltas = Vec::new();
+        for row in rows {
+            let (Some(hash), Some(name)) = (row.first(), row.get(1)) else { continue };
+            let totals = TorrentTotals {
+                name: name.clone(),
+                downloaded: row.get(2).and_then(|v| v.parse().ok()).unwrap_or(0),
+                uploaded: row.get(3).and_then(|v| v.parse().ok()).unwrap_or(0),
+            };
+            let previous = self.torrent_totals.get(hash);
+            let downloaded = delta(previous.map(|p| p.downloaded), totals.downloaded);
+            let uploaded = delta(previous.map(|p| p.uploaded), totals.uploaded);
+            if downloaded > 0 || uploaded > 0 {
+                deltas.push((hash.clone(), totals.name.clone(), downloaded, uploaded));
+            }
+            current.insert(hash.clone(), totals);
+        }
+        self.torrent_totals = current;
+        Ok(deltas)
+    }
+
+    /// Folds old minute rows into hours and old hours into days, then drops
+    /// what is past retention.
+    async fn maintain(&self, now: i64) {
+        let hour_cutoff = (now - history::MINUTE_RETENTION).div_euclid(HOUR) * HOUR;
+        let day_cutoff = (now - history::HOUR_RETENTION).div_euclid(DAY) * DAY;
+
+        let result = async {
+            self.db.downsample_transfer_samples(MINUTE, HOUR, hour_cutoff).await?;
+            self.db.downsample_transfer_samples(HOUR, DAY, day_cutoff).await?;
+            self.db.delete_transfer_samples_before(DAY, now - history::DAY_RETENTION).await?;
+            self.db.delete_torrent_transfers_before(now - history::TORRENT_RETENTION).await
+        }
+        .await;
+        if let Err(e) = result {
+            tracing::warn!("Transfer history maintenance failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta() {
+        assert_eq!(delta(None, 500), 0);
+        assert_eq!(delta(Some(100), 500), 400);
+        // Counter reset after an rTorrent restart
+        assert_eq!(delta(Some(500), 120), 120);
+    }
+}
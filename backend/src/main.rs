@@ -1,9 +1,23 @@
+mod archive;
+mod automation;
 mod diff;
+mod disk_guard;
+mod event_bus;
 mod handlers;
+mod history;
+mod notify;
+#[cfg(feature = "metrics")]
+mod prometheus;
+#[cfg(feature = "qbittorrent")]
+mod qbittorrent;
 #[cfg(feature = "push-notifications")]
 mod push;
 mod rate_limit;
+#[cfg(feature = "transmission")]
+mod transmission;
 mod sse;
+mod visibility;
+mod ws;
 
 use shared::xmlrpc;
 
@@ -12,21 +26,23 @@ use axum::{
     routing::{get, post},
     Router,
     middleware::{self, Next},
-    response::Response,
+    response::{IntoResponse, Response},
     http::{StatusCode, Request},
     body::Body,
 };
 use axum_extra::extract::cookie::CookieJar;
 use clap::Parser;
 use dotenvy::dotenv;
-use shared::{AppEvent, Torrent};
+use shared::roles::role_allows;
+use shared::session;
+use shared::{ApiTokenScope, AppEvent, AuthUser, Torrent, UserRole};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{broadcast, watch};
+use tokio::sync::watch;
 use tower::ServiceBuilder;
 use tower_http::{
-    compression::{CompressionLayer, CompressionLevel},
+    compression::{predicate::{DefaultPredicate, Predicate}, CompressionLayer, CompressionLevel},
     cors::CorsLayer,
     trace::TraceLayer,
 };
@@ -38,18 +54,29 @@ use utoipa_swagger_ui::SwaggerUi;
 #[derive(Clone)]
 pub struct AppState {
     pub tx: Arc<watch::Sender<Vec<Torrent>>>,
-    pub event_bus: broadcast::Sender<AppEvent>,
+    pub event_bus: event_bus::EventBus,
     pub scgi_socket_path: String,
+    pub browse_roots: Vec<String>,
     pub db: shared::db::Db,
     #[cfg(feature = "push-notifications")]
     pub push_store: push::PushSubscriptionStore,
     pub notify_poll: Arc<tokio::sync::Notify>,
+    #[cfg(feature = "metrics")]
+    pub metrics: prometheus::Metrics,
+}
+
+/// Role of an enabled user; `None` if the account is gone or disabled.
+async fn active_role(state: &AppState, user_id: i64) -> Result<Option<UserRole>, StatusCode> {
+    state.db.get_active_user_role(user_id).await.map_err(|e| {
+        tracing::error!("User role lookup failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
 }
 
 async fn auth_middleware(
-    _state: axum::extract::State<AppState>,
+    state: axum::extract::State<AppState>,
     jar: CookieJar,
-    request: Request<Body>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
     // Skip auth for public server functions
@@ -60,33 +87,136 @@ async fn auth_middleware(
        || path.starts_with("/api/server_fns/get_setup_status")
        || path.starts_with("/api/server_fns/Setup")
        || path.starts_with("/api/server_fns/setup")
-       || path.starts_with("/api/internal/")
+       || path == "/api/v2/auth/login"
        || path.starts_with("/swagger-ui")
        || path.starts_with("/api-docs")
-       || !path.starts_with("/api/") 
+       || (!path.starts_with("/api/") && !path.starts_with("/transmission/"))
     {
         return Ok(next.run(request).await);
     }
 
 
-    // Check token
-    if let Some(token) = jar.get("auth_token") {
-        use jsonwebtoken::{decode, Validation, DecodingKey};
-        use shared::server_fns::auth::Claims;
-
-        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-        let validation = Validation::default();
-        
-        match decode::<Claims>(
-            token.value(),
-            &DecodingKey::from_secret(secret.as_bytes()),
-            &validation,
-        ) {
-            Ok(_) => return Ok(next.run(request).await),
-            Err(_) => {} // Invalid token
+    // Personal API tokens
+    if let Some(bearer) = request
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+    {
+        let now = history::unix_now();
+        let token = state
+            .db
+            .get_api_token_by_hash(&shared::api_token::hash(bearer.trim()))
+            .await
+            .map_err(|e| {
+                tracing::error!("API token lookup failed: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        let Some((id, user_id, scope, expires_at)) = token else {
+            return Err(StatusCode::UNAUTHORIZED);
+        };
+        if expires_at.is_some_and(|at| at <= now) {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        let Some(role) = active_role(&state, user_id).await? else {
+            return Err(StatusCode::UNAUTHORIZED);
+        };
+        if !shared::api_token::scope_allows(scope, request.method().as_str(), path)
+            || !role_allows(role, request.method().as_str(), path)
+        {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        if let Err(e) = state.db.touch_api_token(id, now).await {
+            tracing::warn!("Failed to update API token last use: {}", e);
+        }
+        // Lets long-lived connections (WebSocket commands) enforce the scope
+        // too; a viewer's token is read-only whatever its scope
+        let scope = if role == UserRole::Viewer { ApiTokenScope::ReadOnly } else { scope };
+        request.extensions_mut().insert(scope);
+        request.extensions_mut().insert(AuthUser { id: user_id, role });
+        return Ok(next.run(request).await);
+    }
+
+    // Transmission clients only know HTTP Basic auth
+    #[cfg(feature = "transmission")]
+    if path.starts_with("/transmission/") {
+        if let Some((username, password)) = transmission::basic_credentials(request.headers()) {
+            let uid = shared::server_fns::auth::check_credentials(&state.db, &username, &password)
+                .await
+                .map_err(|e| {
+                    tracing::error!("Credential check failed: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                })?;
+            if let Some(uid) = uid {
+                let Some(role) = active_role(&state, uid).await? else {
+                    return Err(StatusCode::UNAUTHORIZED);
+                };
+                // Every RPC call is a POST, so viewers are kept out entirely
+                if !role_allows(role, request.method().as_str(), path) {
+                    return Err(StatusCode::FORBIDDEN);
+                }
+                request.extensions_mut().insert(AuthUser { id: uid, role });
+                return Ok(next.run(request).await);
+            }
+        }
+    }
+
+    // Session cookie; qBittorrent API clients send theirs as `SID`
+    let is_qbittorrent = path.starts_with("/api/v2/");
+    let cookie = jar
+        .get(session::COOKIE_NAME)
+        .or_else(|| jar.get("SID").filter(|_| is_qbittorrent));
+    if let Some(token) = cookie.map(|c| c.value().to_string()) {
+        let now = history::unix_now();
+        let found = state.db.get_session(&session::hash(&token), now).await.map_err(|e| {
+            tracing::error!("Session lookup failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        // Sessions and roles are looked up on every request so logging out,
+        // disabling a user or changing their role takes effect immediately
+        if let Some((session_id, user_id, remember, last_seen_at)) = found {
+            if let Some(role) = active_role(&state, user_id).await? {
+                if !role_allows(role, request.method().as_str(), path) {
+                    return Err(StatusCode::FORBIDDEN);
+                }
+                if role == UserRole::Viewer {
+                    request.extensions_mut().insert(ApiTokenScope::ReadOnly);
+                }
+                request.extensions_mut().insert(AuthUser { id: user_id, role });
+                request.extensions_mut().insert(session::CurrentSession { id: session_id });
+
+                let touched = session::needs_touch(last_seen_at, now);
+                if touched {
+                    if let Err(e) = state.db.touch_session(session_id, remember, now).await {
+                        tracing::warn!("Failed to extend session: {}", e);
+                    }
+                }
+                let mut response = next.run(request).await;
+                // A remembered cookie lives as long as its session, unless
+                // the response replaces it (login, logout)
+                let replaced = response.headers().contains_key(axum::http::header::SET_COOKIE);
+                if touched && remember && !is_qbittorrent && !replaced {
+                    let cookie = session::cookie(session::COOKIE_NAME, &token, true);
+                    if let Ok(value) = axum::http::HeaderValue::from_str(&cookie.to_string()) {
+                        response.headers_mut().insert(axum::http::header::SET_COOKIE, value);
+                    }
+                }
+                return Ok(response);
+            }
         }
     }
 
+    // *arr clients only log in again after a 403, like real qBittorrent sends
+    if is_qbittorrent {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if path.starts_with("/transmission/") {
+        return Ok((
+            StatusCode::UNAUTHORIZED,
+            [(axum::http::header::WWW_AUTHENTICATE, "Basic realm=\"Transmission\"")],
+        )
+            .into_response());
+    }
     Err(StatusCode::UNAUTHORIZED)
 }
 #[derive(Parser, Debug)]
@@ -109,14 +239,50 @@ struct Args {
     #[arg(long, env = "DATABASE_URL", default_value = "sqlite:vibetorrent.db")]
     db_url: String,
 
+    /// Extra directories the file browser may expose (comma separated),
+    /// in addition to rTorrent's directory.default
+    #[arg(long, env = "BROWSE_ROOTS", value_delimiter = ',')]
+    browse_roots: Vec<String>,
+
+    /// Stop downloading torrents when free space on their filesystem drops
+    /// below this size (e.g. 5G, 500M); they are resumed once it recovers
+    #[arg(long, env = "MIN_FREE_SPACE", value_parser = disk_guard::parse_size)]
+    min_free_space: Option<u64>,
+
+    /// Bearer token required to scrape /metrics (open when unset)
+    #[cfg(feature = "metrics")]
+    #[arg(long, env = "METRICS_TOKEN")]
+    metrics_token: Option<String>,
+
     /// Reset password for the specified user
     #[arg(long)]
     reset_password: Option<String>,
+
+    /// Generate a new VAPID keypair for push notifications, replacing the
+    /// stored one and dropping existing subscriptions
+    #[cfg(feature = "push-notifications")]
+    #[arg(long)]
+    rotate_vapid_keys: bool,
 }
 
 #[cfg(feature = "swagger")]
 #[derive(OpenApi)]
 #[openapi(
+    paths(
+        handlers::api::list_torrents,
+        handlers::api::get_torrent,
+        handlers::api::add_torrent,
+        handlers::api::torrent_action,
+        handlers::api::delete_torrent,
+        handlers::api::get_files,
+        handlers::api::set_file_priority,
+        handlers::api::get_peers,
+        handlers::api::get_trackers,
+        handlers::api::list_labels,
+        handlers::api::set_label,
+        handlers::api::get_limits,
+        handlers::api::set_limits,
+    ),
     components(
         schemas(
             shared::AddTorrentRequest,
@@ -129,15 +295,35 @@ struct Args {
             shared::SetFilePriorityRequest,
             shared::SetLabelRequest,
             shared::GlobalLimitRequest,
+            handlers::api::ApiErrorBody,
+            handlers::api::LabelSummary,
         )
     ),
     tags(
-        (name = "vibetorrent", description = "VibeTorrent API")
+        (name = "vibetorrent", description = "VibeTorrent API"),
+        (name = "torrents", description = "Torrent list, actions and details"),
+        (name = "labels", description = "Torrent labels"),
+        (name = "settings", description = "Global rTorrent settings")
     )
 )]
 struct ApiDoc;
 
 
+fn api_v1_router() -> Router<AppState> {
+    use handlers::api;
+    Router::new()
+        .route("/torrents", get(api::list_torrents).post(api::add_torrent))
+        .route("/torrents/action", post(api::torrent_action))
+        .route("/torrents/files/priority", post(api::set_file_priority))
+        .route("/torrents/label", post(api::set_label))
+        .route("/torrents/{hash}", get(api::get_torrent).delete(api::delete_torrent))
+        .route("/torrents/{hash}/files", get(api::get_files))
+        .route("/torrents/{hash}/peers", get(api::get_peers))
+        .route("/torrents/{hash}/trackers", get(api::get_trackers))
+        .route("/labels", get(api::list_labels))
+        .route("/limits", get(api::get_limits).put(api::set_limits))
+}
+
 #[tokio::main]
 async fn main() {
     // Load .env file
@@ -222,6 +408,27 @@ async fn main() {
         }
     }
 
+    #[cfg(feature = "push-notifications")]
+    if args.rotate_vapid_keys {
+        if std::env::var("VAPID_PRIVATE_KEY").is_ok() && std::env::var("VAPID_PUBLIC_KEY").is_ok() {
+            tracing::error!("VAPID keys come from VAPID_* environment variables; unset them to use generated keys.");
+            std::process::exit(1);
+        }
+        match push::rotate_vapid_keys(&db).await {
+            Ok((public_key, removed)) => {
+                println!("--------------------------------------------------");
+                println!("VAPID keys rotated. New public key: {}", public_key);
+                println!("Removed {} push subscription(s); browsers will subscribe again.", removed);
+                println!("--------------------------------------------------");
+                std::process::exit(0);
+            }
+            Err(e) => {
+                tracing::error!("Failed to rotate VAPID keys: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     tracing::info!("Starting VibeTorrent Backend...");
     tracing::info!("Socket: {}", args.socket);
     tracing::info!("Port: {}", args.port);
@@ -267,15 +474,15 @@ async fn main() {
     let (tx, _rx) = watch::channel(vec![]);
     let tx = Arc::new(tx);
 
-    // Channel for Events (Diffs)
-    let (event_bus, _) = broadcast::channel::<AppEvent>(1024);
+    // Channel for Events (Diffs), sequenced so SSE clients can resume
+    let event_bus = event_bus::EventBus::new();
 
     #[cfg(feature = "push-notifications")]
-    let push_store = match push::PushSubscriptionStore::with_db(&db).await {
+    let push_store = match push::PushSubscriptionStore::new(&db).await {
         Ok(store) => store,
         Err(e) => {
-            tracing::error!("Failed to initialize push store: {}", e);
-            push::PushSubscriptionStore::new()
+            tracing::error!("Failed to load VAPID keys: {}", e);
+            std::process::exit(1);
         }
     };
 
@@ -284,29 +491,45 @@ async fn main() {
 
     let notify_poll = Arc::new(tokio::sync::Notify::new());
 
+    #[cfg(feature = "metrics")]
+    let metrics = match prometheus::install(args.metrics_token.clone()) {
+        Ok(metrics) => metrics,
+        Err(e) => {
+            tracing::error!("Failed to install metrics recorder: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     let app_state = AppState {
         tx: tx.clone(),
         event_bus: event_bus.clone(),
         scgi_socket_path: args.socket.clone(),
+        browse_roots: args.browse_roots.clone(),
         db: db.clone(),
         #[cfg(feature = "push-notifications")]
         push_store,
         notify_poll: notify_poll.clone(),
+        #[cfg(feature = "metrics")]
+        metrics,
     };
 
     // Spawn background task to poll rTorrent
     let tx_clone = tx.clone();
     let event_bus_tx = event_bus.clone();
     let socket_path = args.socket.clone(); // Clone for background task
-    #[cfg(feature = "push-notifications")]
-    let push_store_clone = app_state.push_store.clone();
     let notify_poll_clone = notify_poll.clone();
+    let db_for_poll = db.clone();
 
     tokio::spawn(async move {
         let client = xmlrpc::RtorrentClient::new(&socket_path);
-        let mut previous_torrents: Vec<Torrent> = Vec::new();
+        let mut previous_torrents: Option<Vec<Torrent>> = None;
         let mut consecutive_errors = 0;
         let mut backoff_duration = Duration::from_secs(1);
+        // rTorrent forgets its ipv4_filter on restart, so push the blocklist
+        // on startup and after every reconnect.
+        let mut blocklist_pending = true;
+        let mut history_sampler = history::Sampler::new(db_for_poll.clone());
+        let mut disk_usage = disk_guard::DiskUsageCache::default();
 
         loop {
             // Determine polling interval based on active clients
@@ -317,11 +540,17 @@ async fn main() {
                 Duration::from_secs(60)
             };
 
+            #[cfg(feature = "metrics")]
+            let poll_started = std::time::Instant::now();
+
             // 1. Fetch Torrents
             let torrents_result = sse::fetch_torrents(&client).await;
 
             // 2. Fetch Global Stats
-            let stats_result = sse::fetch_global_stats(&client).await;
+            let stats_result = sse::fetch_global_stats(&client, &mut disk_usage).await;
+
+            #[cfg(feature = "metrics")]
+            prometheus::record_poll_duration(poll_started.elapsed());
 
             // Handle Torrents
             match torrents_result {
@@ -341,6 +570,17 @@ async fn main() {
                         backoff_duration = Duration::from_secs(1);
                     }
 
+                    if blocklist_pending {
+                        blocklist_pending = false;
+                        let socket_path = socket_path.clone();
+                        let db = db_for_poll.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = shared::blocklist::reapply(&socket_path, &db).await {
+                                tracing::error!("Failed to apply IP blocklist: {}", e);
+                            }
+                        });
+                    }
+
                     // Update latest state
                     let _ = tx_clone.send(new_torrents.clone());
 
@@ -350,40 +590,27 @@ async fn main() {
                         .unwrap()
                         .as_secs();
 
-                    match diff::diff_torrents(&previous_torrents, &new_torrents) {
-                        diff::DiffResult::FullUpdate => {
+                    let result = match &previous_torrents {
+                        Some(old) => diff::diff_torrents(old, &new_torrents),
+                        // Nothing to patch against yet (first successful poll)
+                        None => diff::DiffResult::FullUpdate(Vec::new()),
+                    };
+                    match result {
+                        diff::DiffResult::FullUpdate(lifecycle) => {
                             let _ = event_bus_tx.send(AppEvent::FullList(new_torrents.clone(), now));
+                            for event in lifecycle {
+                                let _ = event_bus_tx.send(event);
+                            }
                         }
                         diff::DiffResult::Partial(updates) => {
                             for update in updates {
-                                // Check if this is a torrent completion notification
-                                #[cfg(feature = "push-notifications")]
-                                if let AppEvent::Notification(ref notif) = update {
-                                    if notif.message.contains("tamamlandı") {
-                                        // Send push notification in background
-                                        let push_store = push_store_clone.clone();
-                                        let title = "Torrent Tamamlandı".to_string();
-                                        let body = notif.message.clone();
-                                        tokio::spawn(async move {
-                                            if let Err(e) = push::send_push_notification(
-                                                &push_store,
-                                                &title,
-                                                &body,
-                                            )
-                                            .await
-                                            {
-                                                tracing::error!("Failed to send push notification: {}", e);
-                                            }
-                                        });
-                                    }
-                                }
                                 let _ = event_bus_tx.send(update);
                             }
                         }
                         diff::DiffResult::NoChange => {}
                     }
 
-                    previous_torrents = new_torrents;
+                    previous_torrents = Some(new_torrents);
 
                     // Success case: wait for the determined interval OR a wakeup notification
                     tokio::select! {
@@ -396,6 +623,7 @@ async fn main() {
                 Err(e) => {
                     tracing::error!("Error fetching torrents in background: {}", e);
                     consecutive_errors += 1;
+                    blocklist_pending = true;
 
                     // If this is the first error after success (or startup), notify clients
                     if consecutive_errors == 1 {
@@ -419,11 +647,21 @@ async fn main() {
 
             // Handle Stats
             if let Ok(stats) = stats_result {
+                history_sampler.record(&client, &stats).await;
+                #[cfg(feature = "metrics")]
+                prometheus::record_global_stats(&stats);
                 let _ = event_bus_tx.send(AppEvent::Stats(stats));
             }
         }
     });
 
+    if let Some(min_free_bytes) = args.min_free_space.filter(|b| *b > 0) {
+        tokio::spawn(disk_guard::run(app_state.clone(), min_free_bytes));
+    }
+
+    tokio::spawn(automation::run(app_state.clone()));
+    tokio::spawn(notify::run(app_state.clone()));
+
     let app = Router::new();
 
     #[cfg(feature = "swagger")]
@@ -432,20 +670,36 @@ async fn main() {
     // Setup & Auth Routes (cookie-based, stay as REST)
     // Setup & Auth Routes (cookie-based, stay as REST)
     let scgi_path_for_ctx = args.socket.clone();
+    let browse_roots_for_ctx = args.browse_roots.clone();
     let db_for_ctx = db.clone();
     let app = app
         .route("/api/events", get(sse::sse_handler))
-        .route("/api/internal/torrent-finished", post(handlers::notifications::torrent_finished_handler))
+        .route("/api/ws", get(ws::ws_handler))
+        .nest("/api/v1", api_v1_router())
+        .route("/api/download/{hash}", get(handlers::download::download_handler))
+        .route("/api/stream/{hash}/{file_index}", get(handlers::stream::stream_handler))
         .route("/api/server_fns/{*fn_name}", post({
             let scgi_path = scgi_path_for_ctx.clone();
             let db = db_for_ctx.clone();
+            let browse_roots = browse_roots_for_ctx.clone();
             move |req: Request<Body>| {
                 let scgi_path = scgi_path.clone();
                 let db = db.clone();
+                let browse_roots = browse_roots.clone();
+                // Absent only for the public login and setup functions
+                let user = req.extensions().get::<AuthUser>().copied();
+                let current_session = req.extensions().get::<session::CurrentSession>().copied();
                 leptos_axum::handle_server_fns_with_context(
                     move || {
+                        if let Some(user) = user {
+                            leptos::context::provide_context(user);
+                        }
+                        if let Some(current_session) = current_session {
+                            leptos::context::provide_context(current_session);
+                        }
                         leptos::context::provide_context(shared::ServerContext {
                             scgi_socket_path: scgi_path.clone(),
+                            browse_roots: browse_roots.clone(),
                         });
                         leptos::context::provide_context(shared::DbContext {
                             db: db.clone(),
@@ -457,6 +711,15 @@ async fn main() {
         }))
         .fallback(handlers::static_handler);
 
+    #[cfg(feature = "metrics")]
+    let app = app.route("/metrics", get(prometheus::metrics_handler));
+
+    #[cfg(feature = "qbittorrent")]
+    let app = app.nest("/api/v2", qbittorrent::router());
+
+    #[cfg(feature = "transmission")]
+    let app = app.route("/transmission/rpc", get(transmission::rpc_handler).post(transmission::rpc_handler));
+
     let app = app
         .layer(middleware::from_fn_with_state(app_state.clone(), auth_middleware))
         .layer(TraceLayer::new_for_http())
@@ -464,7 +727,13 @@ async fn main() {
             CompressionLayer::new()
                 .br(false)
                 .gzip(true)
-                .quality(CompressionLevel::Fastest),
+                .quality(CompressionLevel::Fastest)
+                // Downloads are served as-is so Content-Length and ranges stay intact
+                .compress_when(DefaultPredicate::new().and(
+                    |_: StatusCode, _: axum::http::Version, headers: &axum::http::HeaderMap, _: &axum::http::Extensions| {
+                        !headers.contains_key(axum::http::header::CONTENT_DISPOSITION)
+                    },
+                )),
         )
         .layer(
             ServiceBuilder::new()
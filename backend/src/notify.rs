@@ -0,0 +1,58 @@
+//! Fans lifecycle events out to the notification channels configured in
+//! settings, and to Web Push when it is built in. Every channel has its own
+//! event filter and is sent to in its own task, so a slow SMTP server does
+//! not hold up a Discord message.
+
+use crate::AppState;
+use shared::notifier::{self, Notification, Notifier};
+use shared::{AppEvent, LifecycleEvent};
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+async fn dispatch(state: &AppState, client: &reqwest::Client, event: &LifecycleEvent, owner: Option<i64>) {
+    let mut notifiers: Vec<(String, Box<dyn Notifier>)> = Vec::new();
+
+    // Each user's push preferences, and whether they may see the torrent,
+    // decide which events reach their devices
+    #[cfg(feature = "push-notifications")]
+    notifiers.push(("Web Push".to_string(), Box::new(state.push_store.clone())));
+
+    match state.db.list_notification_channels().await {
+        Ok(channels) => notifiers.extend(
+            channels
+                .into_iter()
+                .filter(|c| c.enabled && c.events.contains(&event.kind()))
+                .map(|c| (c.name, notifier::build(&c.config, client))),
+        ),
+        Err(e) => tracing::warn!("Failed to load notification channels: {}", e),
+    }
+
+    let mut notification = Notification::from_event(event);
+    notification.owner = owner;
+    let notification = Arc::new(notification);
+    for (name, notifier) in notifiers {
+        let notification = notification.clone();
+        tokio::spawn(async move {
+            if let Err(e) = notifier.send(&notification).await {
+                tracing::warn!("Notification via {} failed: {}", name, e);
+            }
+        });
+    }
+}
+
+/// Runs forever, delivering lifecycle events to notification channels.
+pub async fn run(state: AppState) {
+    let client = notifier::http_client();
+    let mut events = state.event_bus.subscribe_internal();
+
+    loop {
+        match events.recv().await {
+            Ok((_, AppEvent::Lifecycle(event, owner))) => dispatch(&state, &client, &event, owner).await,
+            Ok(_) => {}
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Notification dispatcher missed {} events", skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Prometheus exporter (`metrics` feature). Counters and histograms go
+//! through the `metrics` recorder; gauges derived from the current torrent
+//! list are rendered at scrape time so removed labels do not linger.
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use shared::{GlobalStats, Torrent, TorrentStatus};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+use crate::AppState;
+
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+const LATENCY_BUCKETS: [f64; 12] = [0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5];
+
+#[derive(Clone)]
+pub struct Metrics {
+    handle: PrometheusHandle,
+    /// Bearer token scrapers must send, `None` leaves the endpoint open
+    token: Option<String>,
+}
+
+/// Installs the global recorder. Must be called once, inside the runtime.
+pub fn install(token: Option<String>) -> Result<Metrics, String> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("duration_seconds".to_string()), &LATENCY_BUCKETS)
+        .map_err(|e| e.to_string())?
+        .install_recorder()
+        .map_err(|e| e.to_string())?;
+
+    metrics::describe_histogram!(
+        "vibetorrent_scgi_call_duration_seconds",
+        metrics::Unit::Seconds,
+        "Latency of XML-RPC calls to rTorrent by method"
+    );
+    metrics::describe_counter!("vibetorrent_scgi_errors_total", "Failed XML-RPC calls to rTorrent by method");
+    metrics::describe_histogram!(
+        "vibetorrent_poll_duration_seconds",
+        metrics::Unit::Seconds,
+        "Time spent fetching torrents and stats in one poll loop iteration"
+    );
+    metrics::describe_gauge!("vibetorrent_download_rate_bytes", metrics::Unit::Bytes, "Global download rate per second");
+    metrics::describe_gauge!("vibetorrent_upload_rate_bytes", metrics::Unit::Bytes, "Global upload rate per second");
+    metrics::describe_gauge!("vibetorrent_download_limit_bytes", metrics::Unit::Bytes, "Global download limit, 0 when unlimited");
+    metrics::describe_gauge!("vibetorrent_upload_limit_bytes", metrics::Unit::Bytes, "Global upload limit, 0 when unlimited");
+
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(UPKEEP_INTERVAL).await;
+            upkeep.run_upkeep();
+        }
+    });
+
+    Ok(Metrics { handle, token: token.filter(|t| !t.is_empty()) })
+}
+
+pub fn record_global_stats(stats: &GlobalStats) {
+    metrics::gauge!("vibetorrent_download_rate_bytes").set(stats.down_rate as f64);
+    metrics::gauge!("vibetorrent_upload_rate_bytes").set(stats.up_rate as f64);
+    metrics::gauge!("vibetorrent_download_limit_bytes").set(stats.down_limit.unwrap_or(0) as f64);
+    metrics::gauge!("vibetorrent_upload_limit_bytes").set(stats.up_limit.unwrap_or(0) as f64);
+}
+
+pub fn record_poll_duration(duration: Duration) {
+    metrics::histogram!("vibetorrent_poll_duration_seconds").record(duration.as_secs_f64());
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn write_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+}
+
+#[derive(Default)]
+struct LabelTotals {
+    torrents: u64,
+    size: i64,
+    down_rate: i64,
+    up_rate: i64,
+}
+
+type LabelGauge = (&'static str, &'static str, fn(&LabelTotals) -> i64);
+
+const LABEL_GAUGES: [LabelGauge; 4] = [
+    ("vibetorrent_label_torrents", "Number of torrents per label", |t| t.torrents as i64),
+    ("vibetorrent_label_size_bytes", "Total size of torrents per label", |t| t.size),
+    ("vibetorrent_label_download_rate_bytes", "Download rate per label", |t| t.down_rate),
+    ("vibetorrent_label_upload_rate_bytes", "Upload rate per label", |t| t.up_rate),
+];
+
+/// Gauges computed from the latest torrent list and the SSE bus.
+fn render_state(torrents: &[Torrent], subscribers: usize) -> String {
+    let mut out = String::new();
+
+    write_header(&mut out, "vibetorrent_torrents", "Number of torrents by status");
+    let statuses = [
+        (TorrentStatus::Downloading, "downloading"),
+        (TorrentStatus::Seeding, "seeding"),
+        (TorrentStatus::Paused, "paused"),
+        (TorrentStatus::Error, "error"),
+        (TorrentStatus::Checking, "checking"),
+        (TorrentStatus::Queued, "queued"),
+    ];
+    for (status, name) in statuses {
+        let count = torrents.iter().filter(|t| t.status == status).count();
+        let _ = writeln!(out, "vibetorrent_torrents{{status=\"{}\"}} {}", name, count);
+    }
+
+    let mut labels: BTreeMap<&str, LabelTotals> = BTreeMap::new();
+    for torrent in torrents {
+        let totals = labels.entry(torrent.label.as_deref().unwrap_or("")).or_default();
+        totals.torrents += 1;
+        totals.size += torrent.size;
+        totals.down_rate += torrent.down_rate;
+        totals.up_rate += torrent.up_rate;
+    }
+    for (name, help, value) in LABEL_GAUGES {
+        write_header(&mut out, name, help);
+        for (label, totals) in &labels {
+            let _ = writeln!(out, "{}{{label=\"{}\"}} {}", name, escape_label(label), value(totals));
+        }
+    }
+
+    write_header(&mut out, "vibetorrent_sse_subscribers", "Connected event stream clients");
+    let _ = writeln!(out, "vibetorrent_sse_subscribers {}", subscribers);
+    out
+}
+
+fn authorized(headers: &HeaderMap, token: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|given| given.trim() == token)
+}
+
+pub async fn metrics_handler(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let metrics = &state.metrics;
+    if let Some(token) = &metrics.token {
+        if !authorized(&headers, token) {
+            return (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")]).into_response();
+        }
+    }
+
+    let mut body = metrics.handle.render();
+    body.push_str(&render_state(&state.tx.borrow(), state.event_bus.receiver_count()));
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_state_escapes_labels() {
+        let torrent = Torrent {
+            hash: "A".into(),
+            name: "a".into(),
+            size: 10,
+            completed: 0,
+            down_rate: 5,
+            up_rate: 0,
+            eta: 0,
+            percent_complete: 0.0,
+            status: TorrentStatus::Downloading,
+            error_message: String::new(),
+            added_date: 0,
+            label: Some("tv \"hd\"".into()),
+            owner: None,
+        };
+        let out = render_state(&[torrent], 2);
+        assert!(out.contains("vibetorrent_torrents{status=\"downloading\"} 1"));
+        assert!(out.contains("vibetorrent_label_size_bytes{label=\"tv \\\"hd\\\"\"} 10"));
+        assert!(out.contains("vibetorrent_sse_subscribers 2"));
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
 use utoipa::ToSchema;
 use web_push::{
     HyperWebPushClient, SubscriptionInfo, VapidSignatureBuilder, WebPushClient, WebPushMessageBuilder,
@@ -8,6 +8,8 @@ use web_push::{
 use futures::StreamExt;
 
 use shared::db::Db;
+use shared::notifier::{Notification, Notifier, SendFuture};
+use shared::AuthUser;
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct PushSubscription {
@@ -28,95 +30,112 @@ pub struct VapidConfig {
     pub email: String,
 }
 
-#[derive(Clone)]
-pub struct PushSubscriptionStore {
-    db: Option<Db>,
-    subscriptions: Arc<RwLock<Vec<PushSubscription>>>,
-    vapid_config: VapidConfig,
-}
+/// Used when `VAPID_EMAIL` is unset. Push services only need a contact
+/// they can parse; Apple rejects `localhost` ones.
+const DEFAULT_SUBJECT: &str = "mailto:admin@vibetorrent.invalid";
 
-impl PushSubscriptionStore {
-    pub fn new() -> Self {
-        let private_key = std::env::var("VAPID_PRIVATE_KEY").expect("VAPID_PRIVATE_KEY must be set in .env");
-        let public_key = std::env::var("VAPID_PUBLIC_KEY").expect("VAPID_PUBLIC_KEY must be set in .env");
-        let email = std::env::var("VAPID_EMAIL").expect("VAPID_EMAIL must be set in .env");
-
-        Self {
-            db: None,
-            subscriptions: Arc::new(RwLock::new(Vec::new())),
-            vapid_config: VapidConfig {
-                private_key,
-                public_key,
-                email,
-            },
+impl VapidConfig {
+    /// Keys from `VAPID_PRIVATE_KEY`/`VAPID_PUBLIC_KEY` when both are set,
+    /// otherwise the pair stored in the database, generated on first run.
+    pub async fn load(db: &Db) -> anyhow::Result<Self> {
+        let email = std::env::var("VAPID_EMAIL").unwrap_or_else(|_| DEFAULT_SUBJECT.to_string());
+
+        if let (Ok(private_key), Ok(public_key)) =
+            (std::env::var("VAPID_PRIVATE_KEY"), std::env::var("VAPID_PUBLIC_KEY"))
+        {
+            return Ok(Self { private_key, public_key, email });
         }
+
+        let (private_key, public_key) = match db.get_vapid_keys().await? {
+            Some(keys) => keys,
+            None => {
+                let (private_key, public_key) = generate_vapid_keys()?;
+                db.replace_vapid_keys(&private_key, &public_key).await?;
+                tracing::info!("Generated VAPID keys for push notifications");
+                (private_key, public_key)
+            }
+        };
+        Ok(Self { private_key, public_key, email })
     }
+}
 
-    pub async fn with_db(db: &Db) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut subscriptions_vec: Vec<PushSubscription> = Vec::new();
+/// A new P-256 keypair as URL-safe base64: the raw 32-byte private scalar
+/// and the uncompressed public point browsers expect.
+pub fn generate_vapid_keys() -> Result<(String, String), openssl::error::ErrorStack> {
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use openssl::bn::BigNumContext;
+    use openssl::ec::{EcGroup, EcKey, PointConversionForm};
+    use openssl::nid::Nid;
 
-        // Load existing subscriptions from DB
-        let subs = db.get_all_push_subscriptions().await?;
-        for (endpoint, p256dh, auth) in subs {
-            subscriptions_vec.push(PushSubscription {
-                endpoint,
-                keys: PushKeys { p256dh, auth },
-            });
-        }
-        tracing::info!("Loaded {} push subscriptions from database", subscriptions_vec.len());
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let key = EcKey::generate(&group)?;
+    let mut ctx = BigNumContext::new()?;
+    let public_key = key.public_key().to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)?;
+    let private_key = key.private_key().to_vec_padded(32)?;
+    Ok((URL_SAFE_NO_PAD.encode(private_key), URL_SAFE_NO_PAD.encode(public_key)))
+}
+
+/// Replaces the stored keypair. Existing subscriptions cannot be used with
+/// the new key, so they are dropped; browsers subscribe again on their next
+/// visit. Returns the new public key and the number of dropped devices.
+pub async fn rotate_vapid_keys(db: &Db) -> anyhow::Result<(String, u64)> {
+    let (private_key, public_key) = generate_vapid_keys()?;
+    let removed = db.replace_vapid_keys(&private_key, &public_key).await?;
+    Ok((public_key, removed))
+}
 
-        let private_key = std::env::var("VAPID_PRIVATE_KEY").expect("VAPID_PRIVATE_KEY must be set in .env");
-        let public_key = std::env::var("VAPID_PUBLIC_KEY").expect("VAPID_PUBLIC_KEY must be set in .env");
-        let email = std::env::var("VAPID_EMAIL").expect("VAPID_EMAIL must be set in .env");
+/// Sends to the subscriptions stored in the database, so devices added
+/// through `SubscribePush` are reached without a restart.
+#[derive(Clone)]
+pub struct PushSubscriptionStore {
+    db: Db,
+    vapid_config: VapidConfig,
+}
 
+impl PushSubscriptionStore {
+    pub async fn new(db: &Db) -> anyhow::Result<Self> {
         Ok(Self {
-            db: Some(db.clone()),
-            subscriptions: Arc::new(RwLock::new(subscriptions_vec)),
-            vapid_config: VapidConfig {
-                private_key,
-                public_key,
-                email,
-            },
+            db: db.clone(),
+            vapid_config: VapidConfig::load(db).await?,
         })
     }
 
-    pub async fn add_subscription(&self, subscription: PushSubscription) {
-        // Add to memory
-        let mut subs = self.subscriptions.write().await;
-
-        // Remove duplicate endpoint if exists
-        subs.retain(|s| s.endpoint != subscription.endpoint);
-        subs.push(subscription.clone());
-        tracing::info!("Added push subscription. Total: {}", subs.len());
-
-        // Save to DB if available
-        if let Some(db) = &self.db {
-            if let Err(e) = db.save_push_subscription(
-                &subscription.endpoint,
-                &subscription.keys.p256dh,
-                &subscription.keys.auth,
-            ).await {
-                tracing::error!("Failed to save push subscription to DB: {}", e);
-            }
+    pub async fn remove_subscription(&self, endpoint: &str) {
+        if let Err(e) = self.db.remove_push_subscription(endpoint).await {
+            tracing::error!("Failed to remove push subscription from DB: {}", e);
         }
     }
 
-    pub async fn remove_subscription(&self, endpoint: &str) {
-        // Remove from memory
-        let mut subs = self.subscriptions.write().await;
-        subs.retain(|s| s.endpoint != endpoint);
-        tracing::info!("Removed push subscription. Total: {}", subs.len());
-
-        // Remove from DB if available
-        if let Some(db) = &self.db {
-            if let Err(e) = db.remove_push_subscription(endpoint).await {
-                tracing::error!("Failed to remove push subscription from DB: {}", e);
+    /// Subscriptions of the users who may see `notification` and whose
+    /// preferences accept its kind right now.
+    pub async fn subscriptions_for(&self, notification: &Notification) -> anyhow::Result<Vec<PushSubscription>> {
+        let now = crate::history::unix_now();
+        let mut wanted: HashMap<i64, bool> = HashMap::new();
+        let mut subscriptions = Vec::new();
+
+        for (user_id, endpoint, p256dh, auth) in self.db.get_all_push_subscriptions().await? {
+            let wants = match wanted.get(&user_id) {
+                Some(wants) => *wants,
+                None => {
+                    let visible = match self.db.get_active_user_role(user_id).await? {
+                        Some(role) => notification.visible_to(&AuthUser { id: user_id, role }),
+                        None => false,
+                    };
+                    let wants =
+                        visible && self.db.get_push_preferences(user_id).await?.wants(notification.kind, now);
+                    wanted.insert(user_id, wants);
+                    wants
+                }
+            };
+            if wants {
+                subscriptions.push(PushSubscription {
+                    endpoint,
+                    keys: PushKeys { p256dh, auth },
+                });
             }
         }
-    }
-
-    pub async fn get_all_subscriptions(&self) -> Vec<PushSubscription> {
-        self.subscriptions.read().await.clone()
+        Ok(subscriptions)
     }
 
     pub fn get_public_key(&self) -> &str {
@@ -124,13 +143,21 @@ impl PushSubscriptionStore {
     }
 }
 
-/// Send push notification to all subscribed clients
+impl Notifier for PushSubscriptionStore {
+    fn send<'a>(&'a self, notification: &'a Notification) -> SendFuture<'a> {
+        Box::pin(async move {
+            send_push_notification(self, notification).await
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// Send push notification to every device whose user wants it
 pub async fn send_push_notification(
     store: &PushSubscriptionStore,
-    title: &str,
-    body: &str,
+    notification: &Notification,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let subscriptions = store.get_all_subscriptions().await;
+    let subscriptions = store.subscriptions_for(notification).await?;
 
     if subscriptions.is_empty() {
         tracing::debug!("No push subscriptions to send to");
@@ -140,8 +167,8 @@ pub async fn send_push_notification(
     tracing::info!("Sending push notification to {} subscribers", subscriptions.len());
 
     let payload = serde_json::json!({
-        "title": title,
-        "body": body,
+        "title": notification.title,
+        "body": notification.body,
         "icon": "/icon-192.png",
         "badge": "/icon-192.png",
         "tag": "vibetorrent"
@@ -221,4 +248,18 @@ pub async fn send_push_notification(
 
     }
 
-    
\ No newline at end of file
+    
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_keys_sign() {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+
+        let (private_key, public_key) = generate_vapid_keys().unwrap();
+        let builder = VapidSignatureBuilder::from_base64_no_sub(&private_key, web_push::URL_SAFE_NO_PAD).unwrap();
+        assert_eq!(builder.get_public_key(), URL_SAFE_NO_PAD.decode(public_key).unwrap());
+    }
+}
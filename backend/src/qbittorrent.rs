@@ -0,0 +1,641 @@
+//! qBittorrent Web API v2 compatibility (`qbittorrent` feature): the subset
+//! Sonarr, Radarr, Prowlarr and autobrr use, on top of `RtorrentClient`.
+//! Labels are exposed as categories. `/api/v2/auth/login` starts a regular
+//! session and hands its token out as the `SID` cookie, which
+//! `auth_middleware` accepts on `/api/v2` paths.
+
+use axum::{
+    extract::{ConnectInfo, FromRequest, Multipart, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use shared::server_fns::auth;
+use shared::server_fns::torrent::{self, LoadOptions, TorrentExtra, TorrentSource};
+use shared::session;
+use shared::xmlrpc::RtorrentClient;
+use shared::{Torrent, TorrentStatus};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use crate::AppState;
+
+/// qBittorrent release we report; *arr apps gate features on it.
+const APP_VERSION: &str = "v4.6.7";
+const WEB_API_VERSION: &str = "2.9.3";
+/// qBittorrent's "infinite" ETA
+const MAX_ETA: i64 = 8_640_000;
+
+type QbError = (StatusCode, String);
+type QbResult<T> = Result<T, QbError>;
+
+fn internal_error(e: impl std::fmt::Display) -> QbError {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+fn bad_request(e: impl std::fmt::Display) -> QbError {
+    (StatusCode::BAD_REQUEST, e.to_string())
+}
+
+fn not_found() -> QbError {
+    (StatusCode::NOT_FOUND, "Torrent hash was not found".to_string())
+}
+
+fn client(state: &AppState) -> RtorrentClient {
+    RtorrentClient::new(&state.scgi_socket_path)
+}
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/auth/login", post(login))
+        .route("/auth/logout", post(logout))
+        .route("/app/version", get(app_version))
+        .route("/app/webapiVersion", get(web_api_version))
+        .route("/app/preferences", get(preferences))
+        .route("/app/defaultSavePath", get(default_save_path))
+        .route("/torrents/info", get(torrents_info).post(torrents_info))
+        .route("/torrents/properties", get(torrent_properties).post(torrent_properties))
+        .route("/torrents/files", get(torrent_files).post(torrent_files))
+        .route("/torrents/categories", get(categories).post(categories))
+        .route("/torrents/createCategory", post(create_category))
+        .route("/torrents/add", post(add))
+        .route("/torrents/delete", post(delete))
+        // qBittorrent 5 renamed pause/resume to stop/start
+        .route("/torrents/pause", post(pause))
+        .route("/torrents/stop", post(pause))
+        .route("/torrents/resume", post(resume))
+        .route("/torrents/start", post(resume))
+        .route("/torrents/setCategory", post(set_category))
+}
+
+// --- auth ---
+
+#[derive(Deserialize)]
+struct LoginForm {
+    username: String,
+    password: String,
+}
+
+async fn login(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Form(form): Form<LoginForm>,
+) -> QbResult<Response> {
+    let uid = auth::check_credentials(&state.db, &form.username, &form.password)
+        .await
+        .map_err(internal_error)?;
+    let Some(uid) = uid else {
+        return Ok("Fails.".into_response());
+    };
+    // *arr clients log in again when the session ends, so it is never remembered
+    let token = auth::create_session(&state.db, uid, false, &headers, Some(peer))
+        .await
+        .map_err(internal_error)?;
+    let cookie = session::cookie("SID", &token, false);
+    Ok(([(header::SET_COOKIE, cookie.to_string())], "Ok.").into_response())
+}
+
+async fn logout(State(state): State<AppState>, headers: HeaderMap) -> QbResult<Response> {
+    if let Some(token) = session::token_from_headers(&headers, "SID") {
+        state.db.delete_session(&session::hash(&token)).await.map_err(internal_error)?;
+    }
+    Ok([(header::SET_COOKIE, session::removal_cookie("SID").to_string())].into_response())
+}
+
+// --- app ---
+
+async fn app_version() -> &'static str {
+    APP_VERSION
+}
+
+async fn web_api_version() -> &'static str {
+    WEB_API_VERSION
+}
+
+async fn save_path(state: &AppState) -> String {
+    shared::paths::download_root(&client(state))
+        .await
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+async fn default_save_path(State(state): State<AppState>) -> String {
+    save_path(&state).await
+}
+
+/// Only the preferences *arr apps read; seeding goals are left to them.
+async fn preferences(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({
+        "save_path": save_path(&state).await,
+        "temp_path_enabled": false,
+        "max_ratio_enabled": false,
+        "max_ratio": -1,
+        "max_seeding_time_enabled": false,
+        "max_seeding_time": -1,
+        "max_inactive_seeding_time_enabled": false,
+        "max_inactive_seeding_time": -1,
+        "max_ratio_act": 0,
+        "queueing_enabled": false,
+        "dht": true,
+    }))
+}
+
+// --- torrent data ---
+
+async fn fetch_extra(client: &RtorrentClient) -> QbResult<HashMap<String, TorrentExtra>> {
+    torrent::get_torrent_extras_inner(client).await.map_err(internal_error)
+}
+
+fn is_complete(t: &Torrent) -> bool {
+    t.size > 0 && t.completed >= t.size
+}
+
+fn qb_state(t: &Torrent) -> &'static str {
+    let complete = is_complete(t);
+    match t.status {
+        TorrentStatus::Error => "error",
+        TorrentStatus::Checking => if complete { "checkingUP" } else { "checkingDL" },
+        TorrentStatus::Paused => if complete { "pausedUP" } else { "pausedDL" },
+        TorrentStatus::Queued => if complete { "queuedUP" } else { "queuedDL" },
+        TorrentStatus::Seeding => if t.up_rate > 0 { "uploading" } else { "stalledUP" },
+        // Magnets report no size until the metadata arrives
+        TorrentStatus::Downloading if t.size == 0 => "metaDL",
+        TorrentStatus::Downloading => if t.down_rate > 0 { "downloading" } else { "stalledDL" },
+    }
+}
+
+fn matches_filter(filter: &str, t: &Torrent) -> bool {
+    let paused = t.status == TorrentStatus::Paused;
+    let active = t.down_rate > 0 || t.up_rate > 0;
+    match filter {
+        "downloading" => !is_complete(t) && !paused,
+        "seeding" => is_complete(t) && !paused && t.status != TorrentStatus::Error,
+        "completed" => is_complete(t),
+        "paused" | "stopped" => paused,
+        "resumed" | "running" => !paused,
+        "active" => active,
+        "inactive" => !active,
+        "stalled" => !paused && !active,
+        "stalled_uploading" => is_complete(t) && !paused && t.up_rate == 0,
+        "stalled_downloading" => !is_complete(t) && !paused && t.down_rate == 0,
+        "checking" => t.status == TorrentStatus::Checking,
+        "errored" => t.status == TorrentStatus::Error,
+        _ => true,
+    }
+}
+
+/// `a|b|c` or `all`; `None` selects every torrent.
+fn parse_hashes(hashes: &str) -> Option<Vec<String>> {
+    if hashes.eq_ignore_ascii_case("all") {
+        return None;
+    }
+    Some(hashes.split('|').map(str::trim).filter(|h| !h.is_empty()).map(str::to_uppercase).collect())
+}
+
+/// Hashes from the latest poll matching `hashes`; unknown ones are dropped
+/// like qBittorrent does.
+fn select_hashes(state: &AppState, hashes: &str) -> Vec<String> {
+    let wanted = parse_hashes(hashes);
+    state
+        .tx
+        .borrow()
+        .iter()
+        .filter(|t| wanted.as_ref().is_none_or(|w| w.contains(&t.hash.to_uppercase())))
+        .map(|t| t.hash.clone())
+        .collect()
+}
+
+fn find_torrent(state: &AppState, hash: &str) -> QbResult<Torrent> {
+    state
+        .tx
+        .borrow()
+        .iter()
+        .find(|t| t.hash.eq_ignore_ascii_case(hash))
+        .cloned()
+        .ok_or_else(not_found)
+}
+
+fn seeding_time(extra: &TorrentExtra, now: i64) -> i64 {
+    if extra.finished_at > 0 { (now - extra.finished_at).max(0) } else { 0 }
+}
+
+#[derive(Serialize)]
+struct QbTorrent {
+    hash: String,
+    name: String,
+    size: i64,
+    total_size: i64,
+    progress: f64,
+    dlspeed: i64,
+    upspeed: i64,
+    eta: i64,
+    state: &'static str,
+    category: String,
+    tags: String,
+    save_path: String,
+    content_path: String,
+    added_on: i64,
+    completion_on: i64,
+    amount_left: i64,
+    completed: i64,
+    downloaded: i64,
+    uploaded: i64,
+    ratio: f64,
+    ratio_limit: f64,
+    seeding_time: i64,
+    seeding_time_limit: i64,
+    inactive_seeding_time_limit: i64,
+    last_activity: i64,
+    num_seeds: i64,
+    num_leechs: i64,
+    priority: i64,
+    auto_tmm: bool,
+}
+
+fn to_qb_torrent(t: &Torrent, extra: &TorrentExtra, now: i64) -> QbTorrent {
+    let eta = if is_complete(t) { 0 } else if t.eta > 0 { t.eta } else { MAX_ETA };
+    QbTorrent {
+        hash: t.hash.to_lowercase(),
+        name: t.name.clone(),
+        size: t.size,
+        total_size: t.size,
+        progress: (t.percent_complete / 100.0).clamp(0.0, 1.0),
+        dlspeed: t.down_rate,
+        upspeed: t.up_rate,
+        eta,
+        state: qb_state(t),
+        category: t.label.clone().unwrap_or_default(),
+        tags: String::new(),
+        save_path: extra.save_path(),
+        content_path: extra.content_path(&t.name),
+        added_on: t.added_date,
+        completion_on: if extra.finished_at > 0 { extra.finished_at } else { -1 },
+        amount_left: (t.size - t.completed).max(0),
+        completed: t.completed,
+        downloaded: t.completed,
+        uploaded: extra.uploaded,
+        ratio: extra.ratio as f64 / 1000.0,
+        // -2: use the global limit, which is disabled
+        ratio_limit: -2.0,
+        seeding_time: seeding_time(extra, now),
+        seeding_time_limit: -2,
+        inactive_seeding_time_limit: -2,
+        last_activity: if t.down_rate > 0 || t.up_rate > 0 { now } else { 0 },
+        num_seeds: extra.seeds,
+        num_leechs: extra.leechs,
+        priority: 0,
+        auto_tmm: false,
+    }
+}
+
+fn compare_json(a: &Value, b: &Value) -> Ordering {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        _ => a.as_str().unwrap_or_default().cmp(b.as_str().unwrap_or_default()),
+    }
+}
+
+#[derive(Deserialize)]
+struct InfoQuery {
+    #[serde(default)]
+    filter: String,
+    /// Absent: any category; empty: uncategorized only
+    category: Option<String>,
+    hashes: Option<String>,
+    sort: Option<String>,
+    #[serde(default)]
+    reverse: bool,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+async fn torrents_info(State(state): State<AppState>, Form(query): Form<InfoQuery>) -> QbResult<Json<Vec<Value>>> {
+    let extra = fetch_extra(&client(&state)).await?;
+    let wanted = query.hashes.as_deref().and_then(parse_hashes);
+    let now = crate::history::unix_now();
+
+    let mut torrents: Vec<Value> = state
+        .tx
+        .borrow()
+        .iter()
+        .filter(|t| matches_filter(&query.filter, t))
+        .filter(|t| query.category.as_deref().is_none_or(|c| t.label.as_deref().unwrap_or_default() == c))
+        .filter(|t| wanted.as_ref().is_none_or(|w| w.contains(&t.hash.to_uppercase())))
+        .map(|t| {
+            let extra = extra.get(&t.hash.to_uppercase()).cloned().unwrap_or_default();
+            serde_json::to_value(to_qb_torrent(t, &extra, now)).unwrap_or_default()
+        })
+        .collect();
+
+    if let Some(key) = query.sort.as_deref() {
+        torrents.sort_by(|a, b| compare_json(&a[key], &b[key]));
+    }
+    if query.reverse {
+        torrents.reverse();
+    }
+    let torrents = torrents
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.filter(|l| *l > 0).unwrap_or(usize::MAX))
+        .collect();
+    Ok(Json(torrents))
+}
+
+#[derive(Deserialize)]
+struct HashQuery {
+    hash: String,
+}
+
+async fn torrent_properties(State(state): State<AppState>, Form(query): Form<HashQuery>) -> QbResult<Json<Value>> {
+    let t = find_torrent(&state, &query.hash)?;
+    let extra = fetch_extra(&client(&state)).await?.remove(&t.hash.to_uppercase()).ok_or_else(not_found)?;
+    let now = crate::history::unix_now();
+    let qb = to_qb_torrent(&t, &extra, now);
+
+    Ok(Json(json!({
+        "save_path": qb.save_path,
+        "creation_date": t.added_date,
+        "addition_date": t.added_date,
+        "completion_date": qb.completion_on,
+        "piece_size": extra.chunk_size,
+        "pieces_num": extra.chunks,
+        "pieces_have": extra.completed_chunks,
+        "comment": "",
+        "total_size": t.size,
+        "total_downloaded": t.completed,
+        "total_uploaded": extra.uploaded,
+        "total_wasted": 0,
+        "share_ratio": qb.ratio,
+        "dl_speed": t.down_rate,
+        "up_speed": t.up_rate,
+        "dl_limit": -1,
+        "up_limit": -1,
+        "eta": qb.eta,
+        "seeding_time": qb.seeding_time,
+        "time_elapsed": (now - t.added_date).max(0),
+        "nb_connections": extra.seeds + extra.leechs,
+        "seeds": extra.seeds,
+        "peers": extra.leechs,
+    })))
+}
+
+#[derive(Serialize)]
+struct QbFile {
+    index: u32,
+    name: String,
+    size: i64,
+    progress: f64,
+    priority: u8,
+    is_seed: bool,
+    piece_range: [i64; 2],
+    availability: f64,
+}
+
+async fn torrent_files(State(state): State<AppState>, Form(query): Form<HashQuery>) -> QbResult<Json<Vec<QbFile>>> {
+    let t = find_torrent(&state, &query.hash)?;
+    let client = client(&state);
+    let extra = fetch_extra(&client).await?.remove(&t.hash.to_uppercase()).unwrap_or_default();
+    let files = torrent::get_files_inner(&client, &t.hash).await.map_err(internal_error)?;
+    let chunk_size = extra.chunk_size.max(1);
+
+    let mut offset = 0;
+    let files = files
+        .into_iter()
+        .map(|f| {
+            let first = offset / chunk_size;
+            let last = ((offset + f.size - 1) / chunk_size).max(first);
+            offset += f.size;
+            let progress = (f.completed_chunks as f64 / (last - first + 1) as f64).clamp(0.0, 1.0);
+            QbFile {
+                index: f.index,
+                // qBittorrent paths include the torrent folder
+                name: if extra.multi_file { format!("{}/{}", t.name, f.path) } else { f.path },
+                size: f.size,
+                progress,
+                priority: match f.priority {
+                    0 => 0,
+                    2 => 6,
+                    _ => 1,
+                },
+                is_seed: progress >= 1.0,
+                piece_range: [first, last],
+                availability: -1.0,
+            }
+        })
+        .collect();
+    Ok(Json(files))
+}
+
+#[derive(Serialize)]
+struct QbCategory {
+    name: String,
+    #[serde(rename = "savePath")]
+    save_path: String,
+}
+
+async fn categories(State(state): State<AppState>) -> Json<BTreeMap<String, QbCategory>> {
+    let categories = state
+        .tx
+        .borrow()
+        .iter()
+        .filter_map(|t| t.label.clone())
+        .filter(|l| !l.is_empty())
+        .map(|name| (name.clone(), QbCategory { name, save_path: String::new() }))
+        .collect();
+    Json(categories)
+}
+
+#[derive(Deserialize)]
+struct CreateCategoryForm {
+    category: String,
+}
+
+/// Labels only exist on torrents, so there is nothing to create; accepting
+/// the call keeps *arr apps from failing their setup check.
+async fn create_category(Form(form): Form<CreateCategoryForm>) -> QbResult<StatusCode> {
+    if form.category.trim().is_empty() {
+        return Err(bad_request("Category name is empty"));
+    }
+    Ok(StatusCode::OK)
+}
+
+// --- actions ---
+
+#[derive(Deserialize, Default)]
+struct AddForm {
+    #[serde(default)]
+    urls: String,
+    savepath: Option<String>,
+    category: Option<String>,
+    paused: Option<String>,
+    stopped: Option<String>,
+}
+
+/// Accepts both encodings: *arr apps send URLs as a plain form and files
+/// as multipart.
+async fn add(State(state): State<AppState>, request: Request) -> QbResult<&'static str> {
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("multipart/form-data"));
+
+    let mut form = AddForm::default();
+    let mut files = Vec::new();
+    if is_multipart {
+        let mut multipart = Multipart::from_request(request, &state).await.map_err(bad_request)?;
+        while let Some(field) = multipart.next_field().await.map_err(bad_request)? {
+            let name = field.name().unwrap_or_default().to_string();
+            if name == "torrents" {
+                files.push(field.bytes().await.map_err(bad_request)?);
+                continue;
+            }
+            let value = field.text().await.map_err(bad_request)?;
+            match name.as_str() {
+                "urls" => form.urls = value,
+                "savepath" => form.savepath = Some(value),
+                "category" => form.category = Some(value),
+                "paused" => form.paused = Some(value),
+                "stopped" => form.stopped = Some(value),
+                _ => {}
+            }
+        }
+    } else {
+        let Form(parsed) = Form::<AddForm>::from_request(request, &state).await.map_err(bad_request)?;
+        form = parsed;
+    }
+
+    let options = LoadOptions {
+        save_path: form.savepath.as_deref(),
+        label: form.category.as_deref(),
+        paused: form.paused.as_deref() == Some("true") || form.stopped.as_deref() == Some("true"),
+        owner: None,
+    };
+    let client = client(&state);
+    let mut added = 0;
+    let urls = form.urls.lines().map(str::trim).filter(|u| !u.is_empty());
+    for source in urls.map(TorrentSource::Uri).chain(files.iter().map(|f| TorrentSource::File(f))) {
+        match torrent::load_torrent_inner(&client, &state.browse_roots, source, &options).await {
+            Ok(()) => added += 1,
+            Err(e) => tracing::warn!("qBittorrent API add failed: {}", e),
+        }
+    }
+
+    if added == 0 {
+        return Ok("Fails.");
+    }
+    state.notify_poll.notify_one();
+    Ok("Ok.")
+}
+
+async fn run_action(state: &AppState, hashes: &str, action: &str) -> QbResult<StatusCode> {
+    let client = client(state);
+    for hash in select_hashes(state, hashes) {
+        torrent::torrent_action_inner(&client, &hash, action).await.map_err(internal_error)?;
+    }
+    state.notify_poll.notify_one();
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+struct HashesForm {
+    hashes: String,
+}
+
+#[derive(Deserialize)]
+struct DeleteForm {
+    hashes: String,
+    #[serde(default, rename = "deleteFiles")]
+    delete_files: bool,
+}
+
+async fn delete(State(state): State<AppState>, Form(form): Form<DeleteForm>) -> QbResult<StatusCode> {
+    let action = if form.delete_files { "delete_with_data" } else { "delete" };
+    run_action(&state, &form.hashes, action).await
+}
+
+async fn pause(State(state): State<AppState>, Form(form): Form<HashesForm>) -> QbResult<StatusCode> {
+    run_action(&state, &form.hashes, "stop").await
+}
+
+async fn resume(State(state): State<AppState>, Form(form): Form<HashesForm>) -> QbResult<StatusCode> {
+    run_action(&state, &form.hashes, "start").await
+}
+
+#[derive(Deserialize)]
+struct SetCategoryForm {
+    hashes: String,
+    #[serde(default)]
+    category: String,
+}
+
+async fn set_category(State(state): State<AppState>, Form(form): Form<SetCategoryForm>) -> QbResult<StatusCode> {
+    let client = client(&state);
+    for hash in select_hashes(&state, &form.hashes) {
+        torrent::set_label_inner(&client, &hash, form.category.trim()).await.map_err(internal_error)?;
+    }
+    state.notify_poll.notify_one();
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn torrent(status: TorrentStatus, completed: i64) -> Torrent {
+        Torrent {
+            hash: "ABCDEF".to_string(),
+            name: "ubuntu.iso".to_string(),
+            size: 100,
+            completed,
+            down_rate: 0,
+            up_rate: 0,
+            eta: 0,
+            percent_complete: completed as f64,
+            status,
+            error_message: String::new(),
+            added_date: 0,
+            label: None,
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn test_qb_state() {
+        assert_eq!(qb_state(&torrent(TorrentStatus::Paused, 100)), "pausedUP");
+        assert_eq!(qb_state(&torrent(TorrentStatus::Paused, 50)), "pausedDL");
+        assert_eq!(qb_state(&torrent(TorrentStatus::Seeding, 100)), "stalledUP");
+        assert_eq!(qb_state(&torrent(TorrentStatus::Downloading, 50)), "stalledDL");
+        let mut magnet = torrent(TorrentStatus::Downloading, 0);
+        magnet.size = 0;
+        assert_eq!(qb_state(&magnet), "metaDL");
+    }
+
+    #[test]
+    fn test_filters_and_hashes() {
+        let seeding = torrent(TorrentStatus::Seeding, 100);
+        assert!(matches_filter("completed", &seeding));
+        assert!(matches_filter("seeding", &seeding));
+        assert!(!matches_filter("downloading", &seeding));
+        assert!(matches_filter("all", &seeding));
+
+        assert_eq!(parse_hashes("all"), None);
+        assert_eq!(parse_hashes("abc|DEF|"), Some(vec!["ABC".to_string(), "DEF".to_string()]));
+    }
+
+    #[test]
+    fn test_paths() {
+        let t = torrent(TorrentStatus::Seeding, 100);
+        let single = TorrentExtra { directory: "/data/downloads".to_string(), ..TorrentExtra::default() };
+        let qb = to_qb_torrent(&t, &single, 0);
+        assert_eq!((qb.save_path.as_str(), qb.content_path.as_str()), ("/data/downloads", "/data/downloads/ubuntu.iso"));
+        let multi = TorrentExtra { directory: "/data/downloads/Show S01".to_string(), multi_file: true, ..TorrentExtra::default() };
+        let qb = to_qb_torrent(&t, &multi, 0);
+        assert_eq!((qb.save_path.as_str(), qb.content_path.as_str()), ("/data/downloads", "/data/downloads/Show S01"));
+    }
+}
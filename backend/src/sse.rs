@@ -1,14 +1,20 @@
 use shared::xmlrpc::{
     parse_i64_response, parse_multicall_response, RpcParam, RtorrentClient, XmlRpcError,
 };
+use crate::event_bus::{EventBus, Sequenced};
+use crate::visibility::{visible_event, visible_torrents};
 use crate::AppState;
-use axum::extract::State;
+use axum::extract::{Query, State};
+use axum::http::HeaderMap;
 use axum::response::sse::{Event, Sse};
 use futures::stream::{self};
-use shared::{AppEvent, GlobalStats, Torrent, TorrentStatus};
+use serde::Deserialize;
+use shared::{AppEvent, AuthUser, GlobalStats, Torrent, TorrentStatus};
+use std::collections::VecDeque;
 use std::convert::Infallible;
-use tokio_stream::StreamExt;
+use tokio::sync::broadcast::{self, error::RecvError};
 use axum::response::IntoResponse;
+use axum::Extension;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
 // Field definitions to keep query and parser in sync
@@ -51,6 +57,9 @@ mod fields {
 
     pub const IDX_LABEL: usize = 12;
     pub const CMD_LABEL: &str = "d.custom1=";
+
+    pub const IDX_OWNER: usize = 13;
+    pub const CMD_OWNER: &str = "d.custom=vt_owner";
 }
 
 use fields::*;
@@ -72,6 +81,7 @@ const RTORRENT_FIELDS: &[&str] = &[
     CMD_CREATION_DATE,
     CMD_HASHING,
     CMD_LABEL,
+    CMD_OWNER,
 ];
 
 fn parse_long(s: Option<&String>) -> i64 {
@@ -98,6 +108,7 @@ fn from_rtorrent_row(row: Vec<String>) -> Torrent {
     let added_date = parse_long(row.get(IDX_CREATION_DATE));
     let is_hashing = parse_long(row.get(IDX_HASHING));
     let label_raw = parse_string(row.get(IDX_LABEL));
+    let owner = row.get(IDX_OWNER).and_then(|v| v.parse().ok());
 
     let label = if label_raw.is_empty() {
         None
@@ -144,6 +155,7 @@ fn from_rtorrent_row(row: Vec<String>) -> Torrent {
         error_message: message,
         added_date,
         label,
+        owner,
     }
 }
 
@@ -162,7 +174,10 @@ pub async fn fetch_torrents(client: &RtorrentClient) -> Result<Vec<Torrent>, Xml
     Ok(torrents)
 }
 
-pub async fn fetch_global_stats(client: &RtorrentClient) -> Result<GlobalStats, XmlRpcError> {
+pub async fn fetch_global_stats(
+    client: &RtorrentClient,
+    disk_usage: &mut crate::disk_guard::DiskUsageCache,
+) -> Result<GlobalStats, XmlRpcError> {
     let empty_params: Vec<RpcParam> = vec![];
 
     let down_rate_xml = client
@@ -185,69 +200,115 @@ pub async fn fetch_global_stats(client: &RtorrentClient) -> Result<GlobalStats,
         .await?;
     let up_limit = parse_i64_response(&up_limit_xml).ok();
 
+    let (free_space, disks) = disk_usage.get(client).await;
+
     Ok(GlobalStats {
         down_rate,
         up_rate,
         down_limit,
         up_limit,
-        free_space: None,
+        free_space,
+        disks,
     })
 }
 
+/// Resume point for clients that cannot set the `Last-Event-ID` header,
+/// e.g. a browser `EventSource` opened anew after an error.
+#[derive(Deserialize)]
+pub struct ResumeQuery {
+    last_event_id: Option<String>,
+}
+
+fn to_sse_event(event_bus: &EventBus, seq: u64, event: &AppEvent) -> Event {
+    match rmp_serde::to_vec(event) {
+        Ok(bytes) => Event::default().id(event_bus.event_id(seq)).data(BASE64.encode(bytes)),
+        Err(e) => {
+            tracing::warn!("Failed to serialize SSE event (MessagePack): {}", e);
+            Event::default().comment("error")
+        }
+    }
+}
+
+fn full_list_event(state: &AppState, user: &AuthUser, seq: u64) -> Event {
+    let torrents = visible_torrents(user, &state.tx.borrow()).into_owned();
+    let event = AppEvent::FullList(torrents, crate::history::unix_now() as u64);
+    to_sse_event(&state.event_bus, seq, &event)
+}
+
+/// Replayed or live events `user` may see, ready to send.
+fn visible_events(state: &AppState, user: &AuthUser, events: impl IntoIterator<Item = Sequenced>) -> Vec<Event> {
+    let torrents = state.tx.borrow();
+    events
+        .into_iter()
+        .filter_map(|(seq, event)| {
+            visible_event(user, event, &torrents).map(|event| to_sse_event(&state.event_bus, seq, &event))
+        })
+        .collect()
+}
+
+struct LiveStream {
+    state: AppState,
+    user: AuthUser,
+    receiver: broadcast::Receiver<Sequenced>,
+    last_seq: u64,
+    pending: VecDeque<Event>,
+}
+
 pub async fn sse_handler(
     State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    headers: HeaderMap,
+    Query(query): Query<ResumeQuery>,
 ) -> impl IntoResponse {
     // Notify background worker to wake up and poll immediately
     state.notify_poll.notify_one();
 
-    // Get initial value synchronously (from the watch channel's current state)
-    let initial_rx = state.tx.subscribe();
-    let initial_torrents = initial_rx.borrow().clone();
-
-    let initial_event = {
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+    let last_seen = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .or(query.last_event_id.as_deref())
+        .and_then(|id| state.event_bus.parse_event_id(id));
 
-        let event_data = AppEvent::FullList(initial_torrents, timestamp);
+    let resume = state.event_bus.resume(last_seen);
 
-        match rmp_serde::to_vec(&event_data) {
-            Ok(bytes) => Event::default().data(BASE64.encode(bytes)),
-            Err(_) => Event::default().comment("init_error"),
-        }
+    // Only the missed patches if they are all still buffered, else a full list
+    let initial: VecDeque<Event> = match resume.missed {
+        Some(missed) => visible_events(&state, &user, missed).into(),
+        None => VecDeque::from([full_list_event(&state, &user, resume.last_seq)]),
     };
 
-    // Stream that yields the initial event once
-    let initial_stream = stream::once(async { Ok::<Event, Infallible>(initial_event) });
-
-    // Stream that waits for subsequent changes via Broadcast channel
-    let rx = state.event_bus.subscribe();
-    let update_stream = stream::unfold(rx, |mut rx| async move {
-        match rx.recv().await {
-            Ok(event) => match rmp_serde::to_vec(&event) {
-                Ok(bytes) => Some((Ok::<Event, Infallible>(Event::default().data(BASE64.encode(bytes))), rx)),
-                Err(e) => {
-                    tracing::warn!("Failed to serialize SSE event (MessagePack): {}", e);
-                    Some((
-                        Ok::<Event, Infallible>(Event::default().comment("error")),
-                        rx,
-                    ))
+    let live = LiveStream { state, user, receiver: resume.receiver, last_seq: resume.last_seq, pending: initial };
+    let stream = stream::unfold(live, |mut live| async move {
+        loop {
+            if let Some(event) = live.pending.pop_front() {
+                return Some((Ok::<Event, Infallible>(event), live));
+            }
+            match live.receiver.recv().await {
+                // Already sent as part of a replay
+                Ok((seq, _)) if seq <= live.last_seq => {}
+                Ok((seq, event)) => {
+                    live.last_seq = seq;
+                    live.pending.extend(visible_events(&live.state, &live.user, [(seq, event)]));
                 }
-            },
-            Err(e) => {
-                // If channel closed or lagged, close stream so client reconnects and gets fresh state
-                tracing::warn!("SSE Broadcast channel error (lagged/closed): {}", e);
-                None
+                Err(RecvError::Lagged(skipped)) => {
+                    // Catch up from the replay buffer instead of dropping the client
+                    tracing::debug!("SSE client lagged by {} events", skipped);
+                    let (last_seq, missed) = live.state.event_bus.since(live.last_seq);
+                    match missed {
+                        Some(missed) => live.pending.extend(visible_events(&live.state, &live.user, missed)),
+                        None => live.pending.push_back(full_list_event(&live.state, &live.user, last_seq)),
+                    }
+                    live.last_seq = last_seq;
+                }
+                Err(RecvError::Closed) => return None,
             }
         }
     });
 
-    let sse = Sse::new(initial_stream.chain(update_stream))
-        .keep_alive(axum::response::sse::KeepAlive::default());
+    let sse = Sse::new(stream).keep_alive(axum::response::sse::KeepAlive::default());
 
     (
         [("content-type", "text/event-stream")],
         sse
     )
-}
\ No newline at end of file
+}
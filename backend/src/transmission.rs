@@ -0,0 +1,574 @@
+//! Transmission RPC compatibility (`transmission` feature) at
+//! `/transmission/rpc`, for mobile apps and Flexget. Implements the session
+//! id handshake and the torrent/session methods those clients use, on top
+//! of the `*_inner` helpers behind the server functions. Clients log in with
+//! HTTP Basic auth, checked in `auth_middleware`.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use leptos::prelude::ServerFnError;
+use rand::RngCore;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use sha1::{Digest, Sha1};
+use shared::server_fns::torrent::{self, LoadOptions, TorrentExtra, TorrentSource};
+use shared::server_fns::settings;
+use shared::xmlrpc::{self, RtorrentClient};
+use shared::{Torrent, TorrentFile, TorrentStatus};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use crate::AppState;
+
+const SESSION_HEADER: &str = "X-Transmission-Session-Id";
+const RPC_VERSION: i64 = 17;
+/// Transmission reports speeds and limits in KiB/s with these units.
+const SPEED_BYTES: i64 = 1024;
+
+/// Anti-CSRF token clients must echo back; a new one per process is enough.
+static SESSION_ID: LazyLock<String> = LazyLock::new(|| {
+    let mut bytes = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE64.encode(bytes).replace(['+', '/', '='], "")
+});
+
+/// `(username, password)` from an `Authorization: Basic` header.
+pub fn basic_credentials(headers: &HeaderMap) -> Option<(String, String)> {
+    let encoded = headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(BASE64.decode(encoded.trim()).ok()?).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    Some((username.to_string(), password.to_string()))
+}
+
+fn client(state: &AppState) -> RtorrentClient {
+    RtorrentClient::new(&state.scgi_socket_path)
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    method: String,
+    #[serde(default)]
+    arguments: Value,
+    tag: Option<Value>,
+}
+
+pub async fn rpc_handler(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> Response {
+    let session_id = HeaderValue::from_str(&SESSION_ID).expect("session id is ASCII");
+    if headers.get(SESSION_HEADER) != Some(&session_id) {
+        return (
+            StatusCode::CONFLICT,
+            [(SESSION_HEADER, session_id)],
+            format!("<h1>409: Conflict</h1><p><code>{}: {}</code></p>", SESSION_HEADER, *SESSION_ID),
+        )
+            .into_response();
+    }
+
+    let request: RpcRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+
+    let (result, arguments) = match dispatch(&state, &request.method, &request.arguments).await {
+        Ok(arguments) => ("success".to_string(), arguments),
+        Err(e) => (e, json!({})),
+    };
+    let mut response = json!({ "result": result, "arguments": arguments });
+    if let Some(tag) = request.tag {
+        response["tag"] = tag;
+    }
+    Json(response).into_response()
+}
+
+type RpcResult = Result<Value, String>;
+
+fn rpc_error(e: ServerFnError) -> String {
+    match e {
+        ServerFnError::ServerError(message) => message,
+        other => other.to_string(),
+    }
+}
+
+async fn dispatch(state: &AppState, method: &str, args: &Value) -> RpcResult {
+    match method {
+        "session-get" => session_get(state, args).await,
+        "session-set" => session_set(state, args).await,
+        "session-stats" => session_stats(state).await,
+        "torrent-get" => torrent_get(state, args).await,
+        "torrent-add" => torrent_add(state, args).await,
+        "torrent-start" | "torrent-start-now" => torrent_action(state, args, "start").await,
+        "torrent-stop" => torrent_action(state, args, "stop").await,
+        "torrent-remove" => {
+            let delete_data = args["delete-local-data"].as_bool().unwrap_or(false);
+            torrent_action(state, args, if delete_data { "delete_with_data" } else { "delete" }).await
+        }
+        _ => Err("method name not recognized".to_string()),
+    }
+}
+
+// --- ids ---
+
+/// Transmission identifies torrents by small integers. Deriving them from
+/// the hash keeps them stable across polls and restarts without a table.
+fn torrent_id(hash: &str) -> i64 {
+    hash.get(..7).and_then(|prefix| i64::from_str_radix(prefix, 16).ok()).unwrap_or(0)
+}
+
+/// Torrents selected by `ids`: absent means all, otherwise a number, a hash,
+/// `"recently-active"` or an array of numbers and hashes.
+fn select(torrents: &[Torrent], ids: &Value) -> Vec<Torrent> {
+    let matches = |t: &Torrent, id: &Value| match id {
+        Value::Number(n) => n.as_i64() == Some(torrent_id(&t.hash)),
+        Value::String(s) => t.hash.eq_ignore_ascii_case(s),
+        _ => false,
+    };
+    torrents
+        .iter()
+        .filter(|t| match ids {
+            Value::Null => true,
+            Value::String(s) if s == "recently-active" => t.down_rate > 0 || t.up_rate > 0,
+            Value::Array(ids) => ids.iter().any(|id| matches(t, id)),
+            id => matches(t, id),
+        })
+        .cloned()
+        .collect()
+}
+
+// --- torrent-get ---
+
+fn status(t: &Torrent) -> i64 {
+    match t.status {
+        TorrentStatus::Paused | TorrentStatus::Error => 0,
+        TorrentStatus::Checking => 2,
+        TorrentStatus::Queued => 3,
+        TorrentStatus::Downloading => 4,
+        TorrentStatus::Seeding => 6,
+    }
+}
+
+fn is_complete(t: &Torrent) -> bool {
+    t.size > 0 && t.completed >= t.size
+}
+
+/// Bytes done of one file, estimated from its completed chunks.
+fn file_bytes_completed(file: &TorrentFile, chunk_size: i64) -> i64 {
+    (file.completed_chunks * chunk_size).min(file.size)
+}
+
+fn file_priority(file: &TorrentFile) -> i64 {
+    if file.priority == 2 { 1 } else { 0 }
+}
+
+fn torrent_field(field: &str, t: &Torrent, extra: &TorrentExtra, files: Option<&[TorrentFile]>, now: i64) -> Option<Value> {
+    let files = || files.unwrap_or_default().iter();
+    let value = match field {
+        "id" => json!(torrent_id(&t.hash)),
+        "hashString" => json!(t.hash.to_lowercase()),
+        "name" => json!(t.name),
+        "totalSize" | "sizeWhenDone" => json!(t.size),
+        "leftUntilDone" => json!((t.size - t.completed).max(0)),
+        "haveValid" | "downloadedEver" => json!(t.completed),
+        "percentDone" => json!((t.percent_complete / 100.0).clamp(0.0, 1.0)),
+        "metadataPercentComplete" => json!(if t.size > 0 { 1.0 } else { 0.0 }),
+        "rateDownload" => json!(t.down_rate),
+        "rateUpload" => json!(t.up_rate),
+        "status" => json!(status(t)),
+        // -1: not available
+        "eta" => json!(if t.eta > 0 { t.eta } else { -1 }),
+        // 3: local error
+        "error" => json!(if t.status == TorrentStatus::Error { 3 } else { 0 }),
+        "errorString" => json!(t.error_message),
+        "addedDate" | "startDate" => json!(t.added_date),
+        "doneDate" => json!(extra.finished_at),
+        "activityDate" => json!(if t.down_rate > 0 || t.up_rate > 0 { now } else { 0 }),
+        "downloadDir" => json!(extra.save_path()),
+        "uploadedEver" => json!(extra.uploaded),
+        "uploadRatio" => json!(extra.ratio as f64 / 1000.0),
+        "labels" => json!(t.label.iter().filter(|l| !l.is_empty()).collect::<Vec<_>>()),
+        "isFinished" => json!(false),
+        "isStalled" => json!(t.status != TorrentStatus::Paused && t.down_rate == 0 && t.up_rate == 0),
+        "isPrivate" => json!(false),
+        "queuePosition" => json!(0),
+        "secondsSeeding" => json!(if is_complete(t) && extra.finished_at > 0 { (now - extra.finished_at).max(0) } else { 0 }),
+        "peersConnected" => json!(extra.seeds + extra.leechs),
+        "peersSendingToUs" => json!(extra.seeds),
+        "peersGettingFromUs" => json!(extra.leechs),
+        "pieceCount" => json!(extra.chunks),
+        "pieceSize" => json!(extra.chunk_size),
+        "seedRatioMode" => json!(0),
+        "seedRatioLimit" => json!(0),
+        "trackers" | "trackerStats" | "peers" => json!([]),
+        "files" => json!(files()
+            .map(|f| json!({
+                // Transmission paths include the torrent folder
+                "name": if extra.multi_file { format!("{}/{}", t.name, f.path) } else { f.path.clone() },
+                "length": f.size,
+                "bytesCompleted": file_bytes_completed(f, extra.chunk_size),
+            }))
+            .collect::<Vec<_>>()),
+        "fileStats" => json!(files()
+            .map(|f| json!({
+                "bytesCompleted": file_bytes_completed(f, extra.chunk_size),
+                "wanted": f.priority > 0,
+                "priority": file_priority(f),
+            }))
+            .collect::<Vec<_>>()),
+        "wanted" => json!(files().map(|f| i64::from(f.priority > 0)).collect::<Vec<_>>()),
+        "priorities" => json!(files().map(file_priority).collect::<Vec<_>>()),
+        _ => return None,
+    };
+    Some(value)
+}
+
+const FILE_FIELDS: [&str; 4] = ["files", "fileStats", "wanted", "priorities"];
+
+async fn torrent_get(state: &AppState, args: &Value) -> RpcResult {
+    let fields: Vec<&str> = args["fields"]
+        .as_array()
+        .map(|fields| fields.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if fields.is_empty() {
+        return Err("no fields specified".to_string());
+    }
+
+    let torrents = select(&state.tx.borrow(), &args["ids"]);
+    let client = client(state);
+    let extras = torrent::get_torrent_extras_inner(&client).await.map_err(rpc_error)?;
+    let wants_files = fields.iter().any(|f| FILE_FIELDS.contains(f));
+    let now = crate::history::unix_now();
+
+    let mut rows = Vec::with_capacity(torrents.len());
+    for t in &torrents {
+        let extra = extras.get(&t.hash.to_uppercase()).cloned().unwrap_or_default();
+        let files = if wants_files {
+            Some(torrent::get_files_inner(&client, &t.hash).await.map_err(rpc_error)?)
+        } else {
+            None
+        };
+        let row: Vec<(String, Value)> = fields
+            .iter()
+            .filter_map(|f| torrent_field(f, t, &extra, files.as_deref(), now).map(|v| (f.to_string(), v)))
+            .collect();
+        rows.push(row);
+    }
+
+    // Tables keep the requested field order, which a JSON object would not
+    let torrents = if args["format"].as_str() == Some("table") {
+        let header: Vec<String> = match rows.first() {
+            Some(row) => row.iter().map(|(f, _)| f.clone()).collect(),
+            None => fields.iter().map(|f| f.to_string()).collect(),
+        };
+        let mut table = vec![json!(header)];
+        table.extend(rows.into_iter().map(|row| Value::Array(row.into_iter().map(|(_, v)| v).collect())));
+        Value::Array(table)
+    } else {
+        Value::Array(rows.into_iter().map(|row| Value::Object(row.into_iter().collect::<Map<_, _>>())).collect())
+    };
+    let mut result = json!({ "torrents": torrents });
+    if args["ids"].as_str() == Some("recently-active") {
+        result["removed"] = json!([]);
+    }
+    Ok(result)
+}
+
+// --- torrent-add ---
+
+/// Returns the end of the bencoded value starting at `pos`.
+fn bencode_end(data: &[u8], pos: usize) -> Option<usize> {
+    match *data.get(pos)? {
+        b'i' => Some(pos + data[pos..].iter().position(|&b| b == b'e')? + 1),
+        b'l' | b'd' => {
+            let mut pos = pos + 1;
+            while *data.get(pos)? != b'e' {
+                pos = bencode_end(data, pos)?;
+            }
+            Some(pos + 1)
+        }
+        b'0'..=b'9' => {
+            let colon = pos + data[pos..].iter().position(|&b| b == b':')?;
+            let len: usize = std::str::from_utf8(&data[pos..colon]).ok()?.parse().ok()?;
+            let end = colon + 1 + len;
+            (end <= data.len()).then_some(end)
+        }
+        _ => None,
+    }
+}
+
+/// Byte span of the value stored under `key` in the dictionary at `pos`.
+fn bencode_dict_get(data: &[u8], pos: usize, key: &[u8]) -> Option<(usize, usize)> {
+    if *data.get(pos)? != b'd' {
+        return None;
+    }
+    let mut pos = pos + 1;
+    while *data.get(pos)? != b'e' {
+        let key_end = bencode_end(data, pos)?;
+        let colon = pos + data[pos..key_end].iter().position(|&b| b == b':')?;
+        let value_end = bencode_end(data, key_end)?;
+        if &data[colon + 1..key_end] == key {
+            return Some((key_end, value_end));
+        }
+        pos = value_end;
+    }
+    None
+}
+
+/// `(info hash, name)` of a `.torrent` file.
+fn metainfo_identity(data: &[u8]) -> Option<(String, String)> {
+    let (start, end) = bencode_dict_get(data, 0, b"info")?;
+    let hash = Sha1::digest(&data[start..end]).iter().map(|b| format!("{:02x}", b)).collect();
+    let name = bencode_dict_get(data, start, b"name")
+        .and_then(|(s, e)| {
+            let colon = s + data[s..e].iter().position(|&b| b == b':')?;
+            Some(String::from_utf8_lossy(&data[colon + 1..e]).into_owned())
+        })
+        .unwrap_or_default();
+    Some((hash, name))
+}
+
+fn base32_to_hex(s: &str) -> Option<String> {
+    let mut bits = 0u64;
+    let mut count = 0;
+    let mut hex = String::new();
+    for c in s.chars() {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return None,
+        };
+        bits = (bits << 5) | value;
+        count += 5;
+        while count >= 4 {
+            count -= 4;
+            hex.push(char::from_digit(((bits >> count) & 0xf) as u32, 16)?);
+        }
+    }
+    Some(hex)
+}
+
+/// `(info hash, display name)` of a magnet link.
+fn magnet_identity(uri: &str) -> Option<(String, String)> {
+    let query = uri.strip_prefix("magnet:?")?;
+    let mut hash = None;
+    let mut name = String::new();
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=')?;
+        match key {
+            "xt" => {
+                let btih = value.strip_prefix("urn:btih:")?;
+                hash = match btih.len() {
+                    40 => Some(btih.to_lowercase()),
+                    32 => base32_to_hex(btih),
+                    _ => None,
+                };
+            }
+            "dn" => name = value.replace('+', " "),
+            _ => {}
+        }
+    }
+    Some((hash?, name))
+}
+
+async fn torrent_add(state: &AppState, args: &Value) -> RpcResult {
+    let metainfo = match args["metainfo"].as_str() {
+        Some(encoded) => Some(BASE64.decode(encoded.trim()).map_err(|_| "invalid or corrupt torrent file".to_string())?),
+        None => None,
+    };
+    let filename = args["filename"].as_str().map(str::trim).filter(|f| !f.is_empty());
+
+    let (source, identity) = match (&metainfo, filename) {
+        (Some(data), _) => (TorrentSource::File(data), metainfo_identity(data)),
+        (None, Some(uri)) => (TorrentSource::Uri(uri), magnet_identity(uri)),
+        (None, None) => return Err("no filename or metainfo specified".to_string()),
+    };
+
+    if let Some((hash, name)) = &identity {
+        if state.tx.borrow().iter().any(|t| t.hash.eq_ignore_ascii_case(hash)) {
+            return Ok(json!({ "torrent-duplicate": { "id": torrent_id(hash), "name": name, "hashString": hash } }));
+        }
+    }
+
+    let label = args["labels"].as_array().and_then(|l| l.first()).and_then(Value::as_str);
+    let options = LoadOptions {
+        save_path: args["download-dir"].as_str(),
+        label,
+        paused: args["paused"].as_bool().unwrap_or(false),
+        owner: None,
+    };
+    torrent::load_torrent_inner(&client(state), &state.browse_roots, source, &options)
+        .await
+        .map_err(rpc_error)?;
+    state.notify_poll.notify_one();
+
+    // URLs to .torrent files are fetched by rTorrent, so their hash is not known yet
+    let (hash, name) = identity.unwrap_or_else(|| (String::new(), filename.unwrap_or_default().to_string()));
+    Ok(json!({ "torrent-added": { "id": torrent_id(&hash), "name": name, "hashString": hash } }))
+}
+
+async fn torrent_action(state: &AppState, args: &Value, action: &str) -> RpcResult {
+    let client = client(state);
+    let torrents = select(&state.tx.borrow(), &args["ids"]);
+    for t in torrents {
+        torrent::torrent_action_inner(&client, &t.hash, action).await.map_err(rpc_error)?;
+    }
+    state.notify_poll.notify_one();
+    Ok(json!({}))
+}
+
+// --- session ---
+
+async fn session_get(state: &AppState, args: &Value) -> RpcResult {
+    let client = client(state);
+    let limits = settings::get_global_limits_inner(&client).await.map_err(rpc_error)?;
+    let down = limits.max_download_rate.unwrap_or(0).max(0);
+    let up = limits.max_upload_rate.unwrap_or(0).max(0);
+    let download_dir = shared::paths::download_root(&client)
+        .await
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let session = json!({
+        "version": format!("4.0.6 (VibeTorrent {})", env!("CARGO_PKG_VERSION")),
+        "rpc-version": RPC_VERSION,
+        "rpc-version-minimum": 14,
+        "rpc-version-semver": "5.3.0",
+        "session-id": *SESSION_ID,
+        "download-dir": download_dir,
+        "speed-limit-down": down / SPEED_BYTES,
+        "speed-limit-down-enabled": down > 0,
+        "speed-limit-up": up / SPEED_BYTES,
+        "speed-limit-up-enabled": up > 0,
+        "alt-speed-enabled": false,
+        "seedRatioLimited": false,
+        "queue-download-enabled": false,
+        "units": {
+            "speed-bytes": SPEED_BYTES,
+            "speed-units": ["KiB/s", "MiB/s", "GiB/s", "TiB/s"],
+            "size-bytes": 1024,
+            "size-units": ["KiB", "MiB", "GiB", "TiB"],
+            "memory-bytes": 1024,
+            "memory-units": ["KiB", "MiB", "GiB", "TiB"],
+        },
+    });
+
+    let Some(fields) = args["fields"].as_array() else {
+        return Ok(session);
+    };
+    let session = session.as_object().cloned().unwrap_or_default();
+    Ok(Value::Object(
+        fields
+            .iter()
+            .filter_map(Value::as_str)
+            .filter_map(|f| session.get(f).map(|v| (f.to_string(), v.clone())))
+            .collect(),
+    ))
+}
+
+/// New limit in bytes/s for one direction, or `None` to leave it alone.
+/// rTorrent has a single value where 0 means unlimited, so a limit only
+/// applies while enabled.
+fn new_limit(args: &Value, direction: &str, current: i64) -> Option<i64> {
+    let enabled = args[format!("speed-limit-{}-enabled", direction)].as_bool();
+    let limit = args[format!("speed-limit-{}", direction)].as_i64();
+    match (enabled, limit) {
+        (Some(false), _) => Some(0),
+        (Some(true), Some(kib)) => Some(kib.max(0) * SPEED_BYTES),
+        (None, Some(kib)) if current > 0 => Some(kib.max(0) * SPEED_BYTES),
+        _ => None,
+    }
+}
+
+async fn session_set(state: &AppState, args: &Value) -> RpcResult {
+    let client = client(state);
+    let current = settings::get_global_limits_inner(&client).await.map_err(rpc_error)?;
+    let down = new_limit(args, "down", current.max_download_rate.unwrap_or(0));
+    let up = new_limit(args, "up", current.max_upload_rate.unwrap_or(0));
+    if down.is_some() || up.is_some() {
+        settings::set_global_limits_inner(&client, down, up).await.map_err(rpc_error)?;
+    }
+    Ok(json!({}))
+}
+
+async fn session_stats(state: &AppState) -> RpcResult {
+    let client = client(state);
+    // rTorrent's own counters, so these cover its session rather than ours
+    let total = |method: &'static str| {
+        let client = &client;
+        async move {
+            client
+                .call(method, &[])
+                .await
+                .ok()
+                .and_then(|xml| xmlrpc::parse_i64_response(&xml).ok())
+                .unwrap_or(0)
+        }
+    };
+    let downloaded = total("throttle.global_down.total").await;
+    let uploaded = total("throttle.global_up.total").await;
+    let started_at = total("system.startup_time").await;
+
+    let torrents = state.tx.borrow().clone();
+    let counts: HashMap<bool, usize> = torrents.iter().fold(HashMap::new(), |mut counts, t| {
+        *counts.entry(t.status == TorrentStatus::Paused).or_default() += 1;
+        counts
+    });
+    let stats = json!({
+        "uploadedBytes": uploaded,
+        "downloadedBytes": downloaded,
+        "filesAdded": 0,
+        "sessionCount": 1,
+        "secondsActive": if started_at > 0 { (crate::history::unix_now() - started_at).max(0) } else { 0 },
+    });
+
+    Ok(json!({
+        "torrentCount": torrents.len(),
+        "activeTorrentCount": counts.get(&false).copied().unwrap_or(0),
+        "pausedTorrentCount": counts.get(&true).copied().unwrap_or(0),
+        "downloadSpeed": torrents.iter().map(|t| t.down_rate).sum::<i64>(),
+        "uploadSpeed": torrents.iter().map(|t| t.up_rate).sum::<i64>(),
+        "cumulative-stats": stats,
+        "current-stats": stats,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metainfo_identity() {
+        let data = b"d8:announce3:url4:infod6:lengthi5e4:name8:test.iso12:piece lengthi16384eee";
+        let (hash, name) = metainfo_identity(data).unwrap();
+        let info = b"d6:lengthi5e4:name8:test.iso12:piece lengthi16384ee";
+        let expected: String = Sha1::digest(info).iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hash, expected);
+        assert_eq!(name, "test.iso");
+        assert_eq!(metainfo_identity(b"d4:info"), None);
+    }
+
+    #[test]
+    fn test_magnet_identity() {
+        let hex = "magnet:?xt=urn:btih:C12FE1C06BBA254A9DC9F519B335AA7C1367A88A&dn=Some+Name";
+        assert_eq!(
+            magnet_identity(hex),
+            Some(("c12fe1c06bba254a9dc9f519b335aa7c1367a88a".to_string(), "Some Name".to_string()))
+        );
+        let base32 = "magnet:?xt=urn:btih:YEX6DQDLXISUVHOJ6UM3GNNKPQJWPKEK";
+        assert_eq!(magnet_identity(base32).unwrap().0, "c12fe1c06bba254a9dc9f519b335aa7c1367a88a");
+    }
+
+    #[test]
+    fn test_new_limit() {
+        let args = json!({ "speed-limit-down": 100, "speed-limit-down-enabled": true, "speed-limit-up-enabled": false });
+        assert_eq!(new_limit(&args, "down", 0), Some(100 * 1024));
+        assert_eq!(new_limit(&args, "up", 5000), Some(0));
+        assert_eq!(new_limit(&json!({ "speed-limit-down": 10 }), "down", 0), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! Per-user torrent visibility. Torrents record the user who added them in
+//! rTorrent (`d.custom=vt_owner`); admins see every torrent, everyone else
+//! only their own. The event streams and the REST API filter through here.
+
+use shared::{AppEvent, AuthUser, Torrent};
+use std::borrow::Cow;
+
+/// The part of `torrents` that `user` may see.
+pub fn visible_torrents<'a>(user: &AuthUser, torrents: &'a [Torrent]) -> Cow<'a, [Torrent]> {
+    if user.sees_all() {
+        Cow::Borrowed(torrents)
+    } else {
+        Cow::Owned(torrents.iter().filter(|t| user.sees(t)).cloned().collect())
+    }
+}
+
+/// Whether `user` may see the torrent with `hash` in `torrents`.
+pub fn sees_hash(user: &AuthUser, torrents: &[Torrent], hash: &str) -> bool {
+    user.sees_all() || torrents.iter().any(|t| t.hash.eq_ignore_ascii_case(hash) && user.sees(t))
+}
+
+/// What, if anything, of a broadcast event `user` should get. `torrents`
+/// is the full list after the event.
+pub fn visible_event(user: &AuthUser, event: AppEvent, torrents: &[Torrent]) -> Option<AppEvent> {
+    if user.sees_all() {
+        return Some(event);
+    }
+    match event {
+        AppEvent::FullList(list, at) => {
+            Some(AppEvent::FullList(list.into_iter().filter(|t| user.sees(t)).collect(), at))
+        }
+        AppEvent::Added(ref torrent) => user.sees(torrent).then_some(event),
+        AppEvent::Update(ref patch) => {
+            let hash = patch.hash.as_deref()?;
+            sees_hash(user, torrents, hash).then_some(event)
+        }
+        // The torrent is gone from the list; the event says who owned it
+        AppEvent::Removed(_, owner) => user.owns(owner).then_some(event),
+        AppEvent::Lifecycle(ref lifecycle, owner) => {
+            (lifecycle.hash().is_none() || user.owns(owner)).then_some(event)
+        }
+        AppEvent::Stats(_) | AppEvent::Notification(_) => Some(event),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::{GlobalStats, LifecycleEvent, TorrentStatus, TorrentUpdate, UserRole};
+
+    fn torrent(hash: &str, owner: Option<i64>) -> Torrent {
+        Torrent {
+            hash: hash.to_string(),
+            name: hash.to_lowercase(),
+            size: 100,
+            completed: 0,
+            down_rate: 0,
+            up_rate: 0,
+            eta: 0,
+            percent_complete: 0.0,
+            status: TorrentStatus::Downloading,
+            error_message: String::new(),
+            added_date: 0,
+            label: None,
+            owner,
+        }
+    }
+
+    fn update(hash: &str) -> AppEvent {
+        AppEvent::Update(TorrentUpdate { hash: Some(hash.to_string()), ..Default::default() })
+    }
+
+    #[test]
+    fn test_visible_event() {
+        let admin = AuthUser { id: 1, role: UserRole::Admin };
+        let user = AuthUser { id: 2, role: UserRole::Operator };
+        let list = vec![torrent("AAA", Some(2)), torrent("BBB", Some(3)), torrent("CCC", None)];
+
+        assert_eq!(visible_torrents(&admin, &list).len(), 3);
+        assert_eq!(visible_torrents(&user, &list).len(), 1);
+
+        match visible_event(&user, AppEvent::FullList(list.clone(), 0), &list) {
+            Some(AppEvent::FullList(visible, _)) => assert_eq!(visible.len(), 1),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(visible_event(&user, update("AAA"), &list).is_some());
+        assert!(visible_event(&user, update("BBB"), &list).is_none());
+        assert!(visible_event(&admin, update("BBB"), &list).is_some());
+        assert!(visible_event(&user, AppEvent::Added(torrent("DDD", Some(3))), &list).is_none());
+        assert!(visible_event(&user, AppEvent::Stats(GlobalStats::default()), &list).is_some());
+
+        let removed = |hash: &str, owner| {
+            AppEvent::Lifecycle(LifecycleEvent::TorrentRemoved { hash: hash.to_string(), name: String::new() }, owner)
+        };
+        assert!(visible_event(&user, removed("DDD", Some(2)), &list).is_some());
+        assert!(visible_event(&user, removed("EEE", Some(3)), &list).is_none());
+        assert!(visible_event(&user, removed("CCC", None), &list).is_none());
+        assert!(visible_event(&user, AppEvent::Removed("DDD".to_string(), Some(2)), &list).is_some());
+        assert!(visible_event(&user, AppEvent::Removed("EEE".to_string(), Some(3)), &list).is_none());
+        assert!(visible_event(&admin, AppEvent::Removed("EEE".to_string(), Some(3)), &list).is_some());
+    }
+}
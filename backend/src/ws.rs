@@ -0,0 +1,359 @@
+//! WebSocket event channel on `/api/ws`. Unlike the SSE stream, frames are
+//! raw binary msgpack and each socket only receives the topics it
+//! subscribed to, so clients that show little pay for little. Commands sent
+//! over the socket go through the same `*_inner` helpers as the server
+//! functions, limited by the API token scope the socket was opened with,
+//! and see only the torrents the user may see.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::response::Response;
+use axum::Extension;
+use leptos::prelude::ServerFnError;
+use shared::server_fns::{settings, torrent};
+use shared::xmlrpc::RtorrentClient;
+use shared::{
+    ApiTokenScope, AppEvent, AuthUser, Torrent, TorrentDetails, UserRole, WsClientMessage, WsCommand, WsServerMessage,
+    WsTopic,
+};
+use std::collections::HashSet;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use crate::visibility::{sees_hash, visible_event, visible_torrents};
+use crate::AppState;
+
+/// How often subscribed torrents' files, peers and trackers are resent.
+const DETAILS_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+struct Subscriptions {
+    topics: HashSet<WsTopic>,
+}
+
+impl Subscriptions {
+    fn update(&mut self, topics: Vec<WsTopic>, subscribe: bool) {
+        for topic in topics {
+            let topic = match topic {
+                WsTopic::Torrent(hash) => WsTopic::Torrent(hash.to_uppercase()),
+                other => other,
+            };
+            if subscribe {
+                self.topics.insert(topic);
+            } else {
+                self.topics.remove(&topic);
+            }
+        }
+    }
+
+    fn wants_torrent(&self, t: &Torrent) -> bool {
+        self.topics.contains(&WsTopic::Torrents)
+            || self.topics.contains(&WsTopic::Torrent(t.hash.to_uppercase()))
+            || t.label.as_ref().is_some_and(|l| self.topics.contains(&WsTopic::Label(l.clone())))
+    }
+
+    fn wants_any_torrent(&self) -> bool {
+        self.topics
+            .iter()
+            .any(|t| matches!(t, WsTopic::Torrents | WsTopic::Label(_) | WsTopic::Torrent(_)))
+    }
+
+    fn has_labels(&self) -> bool {
+        self.topics.iter().any(|t| matches!(t, WsTopic::Label(_)))
+    }
+
+    fn detail_hashes(&self) -> Vec<String> {
+        self.topics
+            .iter()
+            .filter_map(|t| match t {
+                WsTopic::Torrent(hash) => Some(hash.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The subscribed part of the current torrent list.
+    fn snapshot(&self, torrents: &[Torrent]) -> Option<AppEvent> {
+        if !self.wants_any_torrent() {
+            return None;
+        }
+        let list = torrents.iter().filter(|t| self.wants_torrent(t)).cloned().collect();
+        Some(AppEvent::FullList(list, crate::history::unix_now() as u64))
+    }
+
+    /// What, if anything, of a broadcast event this socket should get.
+    /// `torrents` is the list after the event.
+    fn filter(&self, event: AppEvent, torrents: &[Torrent]) -> Option<AppEvent> {
+        match event {
+            AppEvent::Stats(_) => self.topics.contains(&WsTopic::Stats).then_some(event),
+            AppEvent::Notification(_) | AppEvent::Lifecycle(..) => {
+                self.topics.contains(&WsTopic::Notifications).then_some(event)
+            }
+            AppEvent::FullList(..) if self.topics.contains(&WsTopic::Torrents) => Some(event),
+            AppEvent::FullList(..) => self.snapshot(torrents),
+            AppEvent::Added(ref torrent) => self.wants_torrent(torrent).then_some(event),
+            // The label is gone with the torrent; an unknown hash is harmless
+            AppEvent::Removed(..) => self.wants_any_torrent().then_some(event),
+            AppEvent::Update(ref patch) => {
+                // A torrent may have left a subscribed label; resend the list
+                if patch.label.is_some() && self.has_labels() {
+                    return self.snapshot(torrents);
+                }
+                let hash = patch.hash.as_deref()?;
+                let torrent = torrents.iter().find(|t| t.hash == hash)?;
+                self.wants_torrent(torrent).then_some(event)
+            }
+        }
+    }
+}
+
+/// Whether a socket opened with `scope` may run `command`.
+fn command_allowed(scope: ApiTokenScope, command: &WsCommand) -> bool {
+    match scope {
+        ApiTokenScope::Full => true,
+        ApiTokenScope::AddOnly => matches!(command, WsCommand::AddTorrent { .. }),
+        ApiTokenScope::ReadOnly => false,
+    }
+}
+
+fn error_message(e: ServerFnError) -> String {
+    match e {
+        ServerFnError::ServerError(message) => message,
+        other => other.to_string(),
+    }
+}
+
+/// Someone else's torrent is reported like an unknown one.
+fn require_visible(state: &AppState, user: &AuthUser, hash: &str) -> Result<(), ServerFnError> {
+    if sees_hash(user, &state.tx.borrow(), hash) {
+        Ok(())
+    } else {
+        Err(ServerFnError::new("Torrent not found"))
+    }
+}
+
+async fn run_command(state: &AppState, user: &AuthUser, command: WsCommand) -> Result<(), ServerFnError> {
+    let client = RtorrentClient::new(&state.scgi_socket_path);
+    match command {
+        WsCommand::Action { hash, action } => {
+            require_visible(state, user, &hash)?;
+            torrent::torrent_action_inner(&client, &hash, &action).await?;
+        }
+        WsCommand::SetLabel { hash, label } => {
+            require_visible(state, user, &hash)?;
+            torrent::set_label_inner(&client, &hash, label.trim()).await?;
+        }
+        WsCommand::AddTorrent { uri, save_path } => {
+            torrent::add_torrent_inner(&client, &state.browse_roots, uri.trim(), save_path.as_deref(), Some(user.id))
+                .await?;
+        }
+        WsCommand::SetGlobalLimits { max_download_rate, max_upload_rate } => {
+            if user.role != UserRole::Admin {
+                return Err(ServerFnError::new("Admin role required"));
+            }
+            settings::set_global_limits_inner(&client, max_download_rate, max_upload_rate).await?;
+        }
+    }
+    state.notify_poll.notify_one();
+    Ok(())
+}
+
+async fn fetch_details(state: &AppState, hash: &str) -> Result<TorrentDetails, ServerFnError> {
+    let client = RtorrentClient::new(&state.scgi_socket_path);
+    let (files, peers, trackers) = tokio::try_join!(
+        torrent::get_files_inner(&client, hash),
+        torrent::get_peers_inner(&client, hash),
+        torrent::get_trackers_inner(&client, hash),
+    )?;
+    Ok(TorrentDetails { hash: hash.to_string(), files, peers, trackers })
+}
+
+async fn send(socket: &mut WebSocket, message: &WsServerMessage) -> bool {
+    match rmp_serde::to_vec(message) {
+        Ok(bytes) => socket.send(Message::Binary(bytes.into())).await.is_ok(),
+        Err(e) => {
+            tracing::warn!("Failed to serialize WebSocket message: {}", e);
+            true
+        }
+    }
+}
+
+async fn send_details(
+    socket: &mut WebSocket,
+    state: &AppState,
+    user: &AuthUser,
+    subscriptions: &Subscriptions,
+) -> bool {
+    let hashes: Vec<String> = {
+        let torrents = state.tx.borrow();
+        subscriptions.detail_hashes().into_iter().filter(|hash| sees_hash(user, &torrents, hash)).collect()
+    };
+    for hash in hashes {
+        // Unknown or removed torrents are skipped rather than reported each tick
+        if let Ok(details) = fetch_details(state, &hash).await {
+            if !send(socket, &WsServerMessage::Details(details)).await {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Extension(user): Extension<AuthUser>,
+    scope: Option<Extension<ApiTokenScope>>,
+) -> Response {
+    // Cookie sessions carry no scope unless the user is a viewer
+    let scope = scope.map(|Extension(scope)| scope).unwrap_or(ApiTokenScope::Full);
+    ws.on_upgrade(move |socket| handle_socket(socket, state, user, scope))
+}
+
+async fn handle_socket(mut socket: WebSocket, state: AppState, user: AuthUser, scope: ApiTokenScope) {
+    let mut events = state.event_bus.subscribe();
+    let mut subscriptions = Subscriptions::default();
+    let mut details_tick = tokio::time::interval(DETAILS_INTERVAL);
+
+    loop {
+        tokio::select! {
+            message = socket.recv() => {
+                let bytes = match message {
+                    Some(Ok(Message::Binary(bytes))) => bytes,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Pings are answered by axum; text frames are not part of the protocol
+                    Some(Ok(_)) => continue,
+                };
+                let message = match rmp_serde::from_slice::<WsClientMessage>(&bytes) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        if !send(&mut socket, &WsServerMessage::Error(format!("Invalid message: {}", e))).await {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+
+                let ok = match message {
+                    WsClientMessage::Subscribe(topics) => {
+                        subscriptions.update(topics, true);
+                        state.notify_poll.notify_one();
+                        let snapshot = subscriptions.snapshot(&visible_torrents(&user, &state.tx.borrow()));
+                        let sent = match snapshot {
+                            Some(event) => send(&mut socket, &WsServerMessage::Event(event)).await,
+                            None => true,
+                        };
+                        sent && send_details(&mut socket, &state, &user, &subscriptions).await
+                    }
+                    WsClientMessage::Unsubscribe(topics) => {
+                        subscriptions.update(topics, false);
+                        true
+                    }
+                    WsClientMessage::Refresh => {
+                        state.notify_poll.notify_one();
+                        let snapshot = subscriptions.snapshot(&visible_torrents(&user, &state.tx.borrow()));
+                        let sent = match snapshot {
+                            Some(event) => send(&mut socket, &WsServerMessage::Event(event)).await,
+                            None => true,
+                        };
+                        sent && send_details(&mut socket, &state, &user, &subscriptions).await
+                    }
+                    WsClientMessage::Command { id, command } => {
+                        let error = if command_allowed(scope, &command) {
+                            run_command(&state, &user, command).await.err().map(error_message)
+                        } else {
+                            Some("Not allowed for this API token".to_string())
+                        };
+                        send(&mut socket, &WsServerMessage::CommandResult { id, error }).await
+                    }
+                };
+                if !ok {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                let event = match event {
+                    Ok((_, event)) => {
+                        let torrents = state.tx.borrow();
+                        visible_event(&user, event, &torrents)
+                            .and_then(|event| subscriptions.filter(event, &visible_torrents(&user, &torrents)))
+                    }
+                    // Missed events are covered by a fresh list instead of closing the socket
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::debug!("WebSocket client lagged by {} events", skipped);
+                        subscriptions.snapshot(&visible_torrents(&user, &state.tx.borrow()))
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if let Some(event) = event {
+                    if !send(&mut socket, &WsServerMessage::Event(event)).await {
+                        break;
+                    }
+                }
+            }
+            _ = details_tick.tick() => {
+                if !send_details(&mut socket, &state, &user, &subscriptions).await {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::{GlobalStats, TorrentStatus, TorrentUpdate};
+
+    fn torrent(hash: &str, label: Option<&str>) -> Torrent {
+        Torrent {
+            hash: hash.to_string(),
+            name: hash.to_lowercase(),
+            size: 100,
+            completed: 0,
+            down_rate: 0,
+            up_rate: 0,
+            eta: 0,
+            percent_complete: 0.0,
+            status: TorrentStatus::Downloading,
+            error_message: String::new(),
+            added_date: 0,
+            label: label.map(str::to_string),
+            owner: None,
+        }
+    }
+
+    fn update(hash: &str) -> AppEvent {
+        AppEvent::Update(TorrentUpdate { hash: Some(hash.to_string()), down_rate: Some(10), ..Default::default() })
+    }
+
+    #[test]
+    fn test_filter_by_topic() {
+        let torrents = vec![torrent("AAA", Some("tv")), torrent("BBB", None)];
+        let mut subs = Subscriptions::default();
+        assert!(subs.filter(AppEvent::Stats(GlobalStats::default()), &torrents).is_none());
+        assert!(subs.filter(update("AAA"), &torrents).is_none());
+
+        subs.update(vec![WsTopic::Stats, WsTopic::Label("tv".to_string()), WsTopic::Torrent("bbb".to_string())], true);
+        assert!(subs.filter(AppEvent::Stats(GlobalStats::default()), &torrents).is_some());
+        assert!(subs.filter(update("AAA"), &torrents).is_some());
+        assert!(subs.filter(update("BBB"), &torrents).is_some());
+
+        subs.update(vec![WsTopic::Torrent("BBB".to_string())], false);
+        assert!(subs.filter(update("BBB"), &torrents).is_none());
+
+        match subs.filter(AppEvent::FullList(torrents.clone(), 0), &torrents) {
+            Some(AppEvent::FullList(list, _)) => assert_eq!(list.len(), 1),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_command_scope() {
+        let add = WsCommand::AddTorrent { uri: "magnet:?".to_string(), save_path: None };
+        let stop = WsCommand::Action { hash: "AAA".to_string(), action: "stop".to_string() };
+        assert!(command_allowed(ApiTokenScope::AddOnly, &add));
+        assert!(!command_allowed(ApiTokenScope::AddOnly, &stop));
+        assert!(!command_allowed(ApiTokenScope::ReadOnly, &add));
+        assert!(command_allowed(ApiTokenScope::Full, &stop));
+    }
+}
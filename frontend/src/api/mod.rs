@@ -163,8 +163,8 @@ pub mod push {
 pub mod torrent {
     use super::*;
 
-    pub async fn add(uri: &str) -> Result<(), ApiError> {
-        shared::server_fns::torrent::add_torrent(uri.to_string())
+    pub async fn add(uri: &str, save_path: Option<String>) -> Result<(), ApiError> {
+        shared::server_fns::torrent::add_torrent(uri.to_string(), save_path)
             .await
             .map_err(|e| ApiError::ServerFn(e.to_string()))
     }
@@ -1,8 +1,11 @@
 use crate::components::layout::protected::Protected;
 use crate::components::ui::skeleton::Skeleton;
 use crate::components::torrent::table::TorrentTable;
+use crate::components::torrent::details::TorrentDetails;
 use crate::components::auth::login::Login;
 use crate::components::auth::setup::Setup;
+use crate::components::settings::SettingsPage;
+use crate::components::stats::StatsPage;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
 use leptos_router::components::{Router, Routes, Route};
@@ -71,6 +74,7 @@ fn InnerApp() -> impl IntoView {
                     log::info!("Authenticated as {}", user_info.username);
                     if let Some(s) = store {
                         s.user.set(Some(user_info.username));
+                        s.role.set(Some(user_info.role));
                     }
                     is_authenticated.1.set(true);
                 }
@@ -189,6 +193,7 @@ fn InnerApp() -> impl IntoView {
                                         <div class="flex-1 overflow-hidden">
                                             <TorrentTable />
                                         </div>
+                                        <TorrentDetails />
                                     </div>
                                 </Protected>
                             </Show>
@@ -209,7 +214,27 @@ fn InnerApp() -> impl IntoView {
                         <Show when=move || !is_loading.0.get() fallback=|| ()>
                             <Show when=move || authenticated fallback=|| ()>
                                 <Protected>
-                                    <div class="p-4">"Settings Page (Coming Soon)"</div>
+                                    <SettingsPage />
+                                </Protected>
+                            </Show>
+                        </Show>
+                    }
+                }/>
+
+                <Route path=leptos_router::path!("/stats") view=move || {
+                    let authenticated = is_authenticated.0.get();
+                    Effect::new(move |_| {
+                        if !authenticated {
+                            let navigate = use_navigate();
+                            navigate("/login", Default::default());
+                        }
+                    });
+                    
+                    view! {
+                        <Show when=move || !is_loading.0.get() fallback=|| ()>
+                            <Show when=move || authenticated fallback=|| ()>
+                                <Protected>
+                                    <StatsPage />
                                 </Protected>
                             </Show>
                         </Show>
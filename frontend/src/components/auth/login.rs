@@ -1,6 +1,7 @@
 use leptos::prelude::*;
 use leptos::task::spawn_local;
 use crate::components::ui::card::{Card, CardHeader, CardContent};
+use crate::components::ui::checkbox::Checkbox;
 use crate::components::ui::input::{Input, InputType};
 
 use crate::components::ui::button::Button;
@@ -9,6 +10,7 @@ use crate::components::ui::button::Button;
 pub fn Login() -> impl IntoView {
     let username = RwSignal::new(String::new());
     let password = RwSignal::new(String::new());
+    let remember = RwSignal::new(false);
     let error = signal(Option::<String>::None);
     let loading = signal(false);
 
@@ -19,9 +21,10 @@ pub fn Login() -> impl IntoView {
 
         let user = username.get();
         let pass = password.get();
+        let remember_me = remember.get_untracked();
 
         spawn_local(async move {
-            match shared::server_fns::auth::login(user, pass).await {
+            match shared::server_fns::auth::login(user, pass, remember_me).await {
                 Ok(_) => {
                     let window = web_sys::window().expect("window should exist");
                     let _ = window.location().set_href("/");
@@ -68,6 +71,15 @@ pub fn Login() -> impl IntoView {
                                 disabled=loading.0.get()
                             />
                         </div>
+                        <label class="flex items-center gap-2 text-sm">
+                            <Checkbox
+                                checked=remember
+                                disabled=Signal::derive(move || loading.0.get())
+                                on_checked_change=Callback::new(move |checked: bool| remember.set(checked))
+                                aria_label="Beni hatırla".to_string()
+                            />
+                            "Beni hatırla"
+                        </label>
 
                         <Show when=move || error.0.get().is_some()>
                             <div class="rounded-lg border border-destructive/50 bg-destructive/10 p-3 text-sm text-destructive">
@@ -1,13 +1,32 @@
 use leptos::prelude::*;
 use crate::components::ui::separator::Separator;
+use crate::components::ui::sparkline::Sparkline;
+use crate::store::SPEED_HISTORY_LEN;
+use crate::utils::format::format_speed;
 
 #[component]
 pub fn Footer() -> impl IntoView {
+    let store = use_context::<crate::store::TorrentStore>().expect("store not provided");
     let year = chrono::Local::now().format("%Y").to_string();
-    
+
+    let down = Signal::derive(move || store.speed_history.with(|h| h.down()));
+    let up = Signal::derive(move || store.speed_history.with(|h| h.up()));
+
     view! {
         <footer class="mt-auto pb-6 px-4">
             <Separator class="mb-4 opacity-30" />
+            <Show when=move || store.speed_history.with(|h| !h.is_empty()) fallback=|| ()>
+                <div class="mb-3 flex flex-wrap items-center justify-center gap-x-6 gap-y-2 text-xs font-mono">
+                    <div class="flex items-center gap-2 text-blue-600 dark:text-blue-400" title="Son 5 dakika">
+                        <span>{move || format!("↓ {}", format_speed(store.global_stats.with(|s| s.down_rate)))}</span>
+                        <Sparkline values=down slots=SPEED_HISTORY_LEN />
+                    </div>
+                    <div class="flex items-center gap-2 text-green-600 dark:text-green-400" title="Son 5 dakika">
+                        <span>{move || format!("↑ {}", format_speed(store.global_stats.with(|s| s.up_rate)))}</span>
+                        <Sparkline values=up slots=SPEED_HISTORY_LEN />
+                    </div>
+                </div>
+            </Show>
             <div class="flex items-center justify-center gap-2 text-[10px] uppercase tracking-widest text-muted-foreground/60 font-medium">
                 <span>{format!("© {} VibeTorrent", year)}</span>
                 <span class="size-1 rounded-full bg-muted-foreground/30" />
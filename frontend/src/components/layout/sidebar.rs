@@ -4,6 +4,7 @@ use crate::components::ui::sidenav::*;
 use crate::components::ui::button::{Button, ButtonVariant, ButtonSize};
 use crate::components::ui::theme_toggle::ThemeToggle;
 use crate::components::ui::switch::Switch;
+use crate::utils::format::format_bytes;
 
 #[component]
 pub fn Sidebar() -> impl IntoView {
@@ -58,6 +59,8 @@ pub fn Sidebar() -> impl IntoView {
 
     let is_active = move |f: crate::store::FilterStatus| store.filter.get() == f;
 
+    let disks = move || store.global_stats.with(|s| s.disks.clone());
+
     let username = move || {
         store.user.get().unwrap_or_else(|| "User".to_string())
     };
@@ -144,10 +147,67 @@ pub fn Sidebar() -> impl IntoView {
                     </SidenavMenu>
                 </SidenavGroupContent>
             </SidenavGroup>
+
+            <SidenavGroup>
+                <SidenavGroupLabel>"Genel"</SidenavGroupLabel>
+                <SidenavGroupContent>
+                    <SidenavMenu>
+                        <SidenavMenuItem>
+                            <SidenavLink href="/" class="text-muted-foreground hover:text-foreground">
+                                <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="size-4 shrink-0">
+                                    <path stroke-linecap="round" stroke-linejoin="round" d="M3.75 12h16.5m-16.5 3.75h16.5M3.75 19.5h16.5M5.625 4.5h12.75a1.875 1.875 0 010 3.75H5.625a1.875 1.875 0 010-3.75z" />
+                                </svg>
+                                <span>"Torrentler"</span>
+                            </SidenavLink>
+                        </SidenavMenuItem>
+                        <SidenavMenuItem>
+                            <SidenavLink href="/stats" class="text-muted-foreground hover:text-foreground">
+                                <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="size-4 shrink-0">
+                                    <path stroke-linecap="round" stroke-linejoin="round" d="M3 13.125C3 12.504 3.504 12 4.125 12h2.25c.621 0 1.125.504 1.125 1.125v6.75C7.5 20.496 6.996 21 6.375 21h-2.25A1.125 1.125 0 013 19.875v-6.75zM9.75 8.625c0-.621.504-1.125 1.125-1.125h2.25c.621 0 1.125.504 1.125 1.125v11.25c0 .621-.504 1.125-1.125 1.125h-2.25a1.125 1.125 0 01-1.125-1.125V8.625zM16.5 4.125c0-.621.504-1.125 1.125-1.125h2.25C20.496 3 21 3.504 21 4.125v15.75c0 .621-.504 1.125-1.125 1.125h-2.25a1.125 1.125 0 01-1.125-1.125V4.125z" />
+                                </svg>
+                                <span>"İstatistikler"</span>
+                            </SidenavLink>
+                        </SidenavMenuItem>
+                        <SidenavMenuItem>
+                            <SidenavLink href="/settings" class="text-muted-foreground hover:text-foreground">
+                                <svg xmlns="http://www.w3.org/2000/svg" fill="none" viewBox="0 0 24 24" stroke-width="1.5" stroke="currentColor" class="size-4 shrink-0">
+                                    <path stroke-linecap="round" stroke-linejoin="round" d="M10.343 3.94c.09-.542.56-.94 1.11-.94h1.093c.55 0 1.02.398 1.11.94l.149.894c.07.424.384.764.78.93.398.164.855.142 1.205-.108l.737-.527a1.125 1.125 0 011.45.12l.773.774c.39.389.44 1.002.12 1.45l-.527.737c-.25.35-.272.806-.107 1.204.165.397.505.71.93.78l.893.15c.543.09.94.56.94 1.109v1.094c0 .55-.397 1.02-.94 1.11l-.893.149c-.425.07-.765.383-.93.78-.165.398-.143.854.107 1.204l.527.738c.32.447.269 1.06-.12 1.45l-.774.773a1.125 1.125 0 01-1.449.12l-.738-.527c-.35-.25-.806-.272-1.203-.107-.397.165-.71.505-.781.929l-.149.894c-.09.542-.56.94-1.11.94h-1.094c-.55 0-1.019-.398-1.11-.94l-.148-.894c-.071-.424-.384-.764-.781-.93-.398-.164-.854-.142-1.204.108l-.738.527c-.447.32-1.06.269-1.45-.12l-.773-.774a1.125 1.125 0 01-.12-1.45l.527-.737c.25-.35.273-.806.108-1.204-.165-.397-.505-.71-.93-.78l-.894-.15c-.542-.09-.94-.56-.94-1.109v-1.094c0-.55.398-1.02.94-1.11l.894-.149c.424-.07.765-.383.93-.78.165-.398.143-.854-.107-1.204l-.527-.738a1.125 1.125 0 01.12-1.45l.773-.773a1.125 1.125 0 011.45-.12l.737.527c.35.25.807.272 1.204.107.397-.165.71-.505.78-.929l.15-.894z" />
+                                    <path stroke-linecap="round" stroke-linejoin="round" d="M15 12a3 3 0 11-6 0 3 3 0 016 0z" />
+                                </svg>
+                                <span>"Ayarlar"</span>
+                            </SidenavLink>
+                        </SidenavMenuItem>
+                    </SidenavMenu>
+                </SidenavGroupContent>
+            </SidenavGroup>
         </SidenavContent>
 
         <SidenavFooter>
             <div class="flex flex-col gap-4 p-4">
+                // Disk usage per mount
+                <Show when=move || store.global_stats.with(|s| !s.disks.is_empty()) fallback=|| ()>
+                    <div class="flex flex-col gap-2 px-2">
+                        <For each=disks key=|d| (d.mount_point.clone(), d.available_bytes, d.total_bytes) let:disk>
+                            {
+                                let used = disk.total_bytes.saturating_sub(disk.available_bytes);
+                                let percent = if disk.total_bytes > 0 { used as f64 / disk.total_bytes as f64 * 100.0 } else { 0.0 };
+                                let bar_class = if percent >= 95.0 { "h-full bg-destructive" } else if percent >= 85.0 { "h-full bg-amber-500" } else { "h-full bg-primary" };
+                                view! {
+                                    <div class="flex flex-col gap-1" title=format!("{:.0}% dolu", percent)>
+                                        <div class="flex items-center justify-between gap-2 text-[10px]">
+                                            <span class="truncate font-mono text-foreground/70">{disk.mount_point.clone()}</span>
+                                            <span class="shrink-0 text-muted-foreground">{format!("{} boş", format_bytes(disk.available_bytes as i64))}</span>
+                                        </div>
+                                        <div class="h-1 overflow-hidden rounded-full bg-muted">
+                                            <div class=bar_class style=format!("width: {:.1}%", percent) />
+                                        </div>
+                                    </div>
+                                }
+                            }
+                        </For>
+                    </div>
+                </Show>
+
                 // Push Notification Toggle
                 <div class="flex items-center justify-between px-2 py-1 bg-muted/20 rounded-md border border-border/50">
                     <div class="flex flex-col gap-0.5">
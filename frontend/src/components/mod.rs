@@ -3,5 +3,7 @@ pub mod context_menu;
 pub mod layout;
 pub mod torrent;
 pub mod auth;
+pub mod settings;
+pub mod stats;
 // pub mod toast; (Removed)
 pub mod ui;
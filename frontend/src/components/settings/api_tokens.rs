@@ -0,0 +1,201 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::{ApiToken, ApiTokenScope};
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::card::{Card, CardContent, CardDescription, CardHeader, CardTitle};
+use crate::components::ui::input::{Input, InputType};
+use crate::store::{toast_error, toast_success};
+
+const SCOPES: [ApiTokenScope; 3] = [ApiTokenScope::ReadOnly, ApiTokenScope::AddOnly, ApiTokenScope::Full];
+const EXPIRY_DAYS: [u32; 4] = [0, 30, 90, 365];
+
+fn scope_label(scope: ApiTokenScope) -> &'static str {
+    match scope {
+        ApiTokenScope::ReadOnly => "Salt okunur",
+        ApiTokenScope::AddOnly => "Yalnızca ekleme",
+        ApiTokenScope::Full => "Tam erişim",
+    }
+}
+
+fn expiry_label(days: u32) -> String {
+    if days == 0 { "Süresiz".to_string() } else { format!("{} gün", days) }
+}
+
+fn format_date(timestamp: Option<i64>, fallback: &str) -> String {
+    timestamp
+        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%d/%m/%Y %H:%M").to_string())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+#[component]
+pub fn ApiTokenSettings() -> impl IntoView {
+    let tokens = RwSignal::new(Vec::<ApiToken>::new());
+    let name = RwSignal::new(String::new());
+    let scope = RwSignal::new(ApiTokenScope::ReadOnly);
+    let expiry = RwSignal::new(90u32);
+    let created_secret = RwSignal::new(Option::<String>::None);
+    let busy = RwSignal::new(false);
+
+    let refresh = move || {
+        spawn_local(async move {
+            match shared::server_fns::api_tokens::list_api_tokens().await {
+                Ok(list) => tokens.set(list),
+                Err(e) => toast_error(format!("API anahtarları alınamadı: {}", e)),
+            }
+        });
+    };
+    refresh();
+
+    let create = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        let value = name.get().trim().to_string();
+        if value.is_empty() {
+            return;
+        }
+        let days = expiry.get();
+        busy.set(true);
+        spawn_local(async move {
+            let result = shared::server_fns::api_tokens::create_api_token(
+                value,
+                scope.get_untracked(),
+                (days > 0).then_some(days),
+            )
+            .await;
+            match result {
+                Ok(created) => {
+                    name.set(String::new());
+                    created_secret.set(Some(created.secret));
+                    tokens.update(|list| list.insert(0, created.token));
+                    toast_success("API anahtarı oluşturuldu");
+                }
+                Err(e) => toast_error(format!("API anahtarı oluşturulamadı: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let revoke = move |id: i64| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::api_tokens::revoke_api_token(id).await {
+                Ok(()) => {
+                    tokens.update(|list| list.retain(|t| t.id != id));
+                    toast_success("API anahtarı iptal edildi");
+                }
+                Err(e) => toast_error(format!("API anahtarı iptal edilemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let copy_secret = move |_| {
+        let Some(secret) = created_secret.get_untracked() else { return };
+        let clipboard = web_sys::window().map(|w| w.navigator().clipboard());
+        spawn_local(async move {
+            let Some(clipboard) = clipboard else { return };
+            match wasm_bindgen_futures::JsFuture::from(clipboard.write_text(&secret)).await {
+                Ok(_) => toast_success("Panoya kopyalandı"),
+                Err(_) => toast_error("Panoya kopyalanamadı"),
+            }
+        });
+    };
+
+    view! {
+        <Card>
+            <CardHeader>
+                <CardTitle>"API Anahtarları"</CardTitle>
+                <CardDescription>
+                    "Betikler ve harici araçlar için kişisel anahtarlar. İsteklerde "
+                    <code class="font-mono text-xs">"Authorization: Bearer <anahtar>"</code>
+                    " başlığıyla gönderilir."
+                </CardDescription>
+            </CardHeader>
+            <CardContent class="space-y-4">
+                {move || created_secret.get().map(|secret| view! {
+                    <div class="space-y-2 rounded-lg border border-primary/40 bg-primary/5 p-3">
+                        <p class="text-xs text-muted-foreground">
+                            "Anahtar yalnızca şimdi gösteriliyor. Kopyalayıp güvenli bir yerde saklayın."
+                        </p>
+                        <div class="flex items-center gap-2">
+                            <code class="min-w-0 flex-1 truncate rounded bg-muted px-2 py-1 font-mono text-xs">{secret}</code>
+                            <Button variant=ButtonVariant::Outline size=ButtonSize::Sm on:click=copy_secret>"Kopyala"</Button>
+                            <Button variant=ButtonVariant::Ghost size=ButtonSize::Sm on:click=move |_| created_secret.set(None)>
+                                "Kapat"
+                            </Button>
+                        </div>
+                    </div>
+                })}
+
+                <div class="space-y-2">
+                    {move || {
+                        let list = tokens.get();
+                        if list.is_empty() {
+                            return view! {
+                                <p class="text-sm text-muted-foreground">"Henüz API anahtarı yok."</p>
+                            }.into_any();
+                        }
+                        list.into_iter().map(|token| {
+                            let id = token.id;
+                            let expired = token.expires_at.is_some_and(|at| at <= chrono::Utc::now().timestamp());
+                            let details = format!(
+                                "{} · Oluşturma: {} · Bitiş: {} · Son kullanım: {}",
+                                scope_label(token.scope),
+                                format_date(Some(token.created_at), "-"),
+                                format_date(token.expires_at, "Süresiz"),
+                                format_date(token.last_used_at, "Hiç"),
+                            );
+                            view! {
+                                <div class="flex items-center justify-between gap-3 rounded-md border px-3 py-2 text-sm">
+                                    <div class="flex min-w-0 flex-col">
+                                        <span class="truncate font-medium">
+                                            {token.name}
+                                            {expired.then(|| view! {
+                                                <span class="ml-2 text-[10px] font-normal text-destructive">"Süresi doldu"</span>
+                                            })}
+                                        </span>
+                                        <span class="truncate text-[11px] text-muted-foreground">{details}</span>
+                                    </div>
+                                    <Button
+                                        variant=ButtonVariant::Ghost
+                                        size=ButtonSize::Sm
+                                        class="text-destructive hover:bg-destructive/10"
+                                        attr:disabled=move || busy.get()
+                                        on:click=move |_| revoke(id)
+                                    >
+                                        "İptal Et"
+                                    </Button>
+                                </div>
+                            }
+                        }).collect_view().into_any()
+                    }}
+                </div>
+
+                <form on:submit=create class="flex flex-wrap gap-2">
+                    <div class="min-w-40 flex-1">
+                        <Input r#type=InputType::Text placeholder="Anahtar adı" bind_value=name />
+                    </div>
+                    <select
+                        class="h-9 rounded-md border border-input bg-background px-2 text-sm"
+                        prop:value=move || scope.get().as_str()
+                        on:change=move |ev| {
+                            if let Some(s) = ApiTokenScope::parse(&event_target_value(&ev)) {
+                                scope.set(s);
+                            }
+                        }
+                    >
+                        {SCOPES.into_iter().map(|s| view! { <option value=s.as_str()>{scope_label(s)}</option> }).collect_view()}
+                    </select>
+                    <select
+                        class="h-9 rounded-md border border-input bg-background px-2 text-sm"
+                        prop:value=move || expiry.get().to_string()
+                        on:change=move |ev| expiry.set(event_target_value(&ev).parse().unwrap_or(0))
+                    >
+                        {EXPIRY_DAYS.into_iter().map(|d| view! { <option value=d.to_string()>{expiry_label(d)}</option> }).collect_view()}
+                    </select>
+                    <Button attr:r#type="submit" attr:disabled=move || busy.get()>"Oluştur"</Button>
+                </form>
+            </CardContent>
+        </Card>
+    }
+}
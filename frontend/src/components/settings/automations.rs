@@ -0,0 +1,294 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::{Automation, AutomationAction, AutomationDelivery, AutomationTrigger};
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::card::{Card, CardContent, CardDescription, CardHeader, CardTitle};
+use crate::components::ui::checkbox::Checkbox;
+use crate::components::ui::input::{Input, InputType};
+use crate::components::ui::switch::Switch;
+use crate::store::{toast_error, toast_success};
+
+const METHODS: [&str; 4] = ["POST", "PUT", "PATCH", "GET"];
+
+fn trigger_label(trigger: AutomationTrigger) -> &'static str {
+    match trigger {
+        AutomationTrigger::Added => "Eklendi",
+        AutomationTrigger::Completed => "Tamamlandı",
+        AutomationTrigger::Errored => "Hata",
+        AutomationTrigger::Removed => "Kaldırıldı",
+    }
+}
+
+fn action_summary(action: &AutomationAction) -> String {
+    match action {
+        AutomationAction::Webhook { url, method, secret, .. } => {
+            let signed = if secret.is_some() { " · İmzalı" } else { "" };
+            format!("{} {}{}", method, url, signed)
+        }
+        AutomationAction::Script { command } => format!("$ {}", command),
+    }
+}
+
+fn format_date(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%d/%m/%Y %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+#[component]
+pub fn AutomationSettings() -> impl IntoView {
+    let automations = RwSignal::new(Vec::<Automation>::new());
+    let deliveries = RwSignal::new(Vec::<AutomationDelivery>::new());
+    let busy = RwSignal::new(false);
+
+    let name = RwSignal::new(String::new());
+    let is_script = RwSignal::new(false);
+    let triggers = RwSignal::new(vec![AutomationTrigger::Completed]);
+    let method = RwSignal::new("POST".to_string());
+    let url = RwSignal::new(String::new());
+    let body_template = RwSignal::new(String::new());
+    let secret = RwSignal::new(String::new());
+    let command = RwSignal::new(String::new());
+
+    let refresh = move || {
+        spawn_local(async move {
+            match shared::server_fns::automations::list_automations().await {
+                Ok(list) => automations.set(list),
+                Err(e) => toast_error(format!("Otomasyonlar alınamadı: {}", e)),
+            }
+            match shared::server_fns::automations::list_automation_deliveries().await {
+                Ok(list) => deliveries.set(list),
+                Err(e) => toast_error(format!("Teslim kaydı alınamadı: {}", e)),
+            }
+        });
+    };
+    refresh();
+
+    let create = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        let value = name.get().trim().to_string();
+        if value.is_empty() {
+            return;
+        }
+        let action = if is_script.get() {
+            AutomationAction::Script { command: command.get() }
+        } else {
+            let secret = secret.get();
+            AutomationAction::Webhook {
+                url: url.get(),
+                method: method.get(),
+                body_template: body_template.get(),
+                secret: (!secret.trim().is_empty()).then_some(secret),
+            }
+        };
+        let selected = triggers.get();
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::automations::create_automation(value, selected, action).await {
+                Ok(created) => {
+                    automations.update(|list| list.insert(0, created));
+                    name.set(String::new());
+                    url.set(String::new());
+                    body_template.set(String::new());
+                    secret.set(String::new());
+                    command.set(String::new());
+                    toast_success("Otomasyon eklendi");
+                }
+                Err(e) => toast_error(format!("Otomasyon eklenemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let set_enabled = move |id: i64, enabled: bool| {
+        spawn_local(async move {
+            match shared::server_fns::automations::set_automation_enabled(id, enabled).await {
+                Ok(()) => automations.update(|list| {
+                    if let Some(a) = list.iter_mut().find(|a| a.id == id) {
+                        a.enabled = enabled;
+                    }
+                }),
+                Err(e) => toast_error(format!("Otomasyon güncellenemedi: {}", e)),
+            }
+        });
+    };
+
+    let remove = move |id: i64| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::automations::delete_automation(id).await {
+                Ok(()) => {
+                    automations.update(|list| list.retain(|a| a.id != id));
+                    deliveries.update(|list| list.retain(|d| d.automation_id != id));
+                    toast_success("Otomasyon silindi");
+                }
+                Err(e) => toast_error(format!("Otomasyon silinemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    view! {
+        <Card>
+            <CardHeader>
+                <CardTitle>"Otomasyonlar"</CardTitle>
+                <CardDescription>
+                    "Torrent olaylarında webhook çağırın veya komut çalıştırın. Başarısız denemeler üç kez tekrarlanır."
+                </CardDescription>
+            </CardHeader>
+            <CardContent class="space-y-4">
+                <div class="space-y-2">
+                    {move || {
+                        let list = automations.get();
+                        if list.is_empty() {
+                            return view! {
+                                <p class="text-sm text-muted-foreground">"Henüz otomasyon yok."</p>
+                            }.into_any();
+                        }
+                        list.into_iter().map(|automation| {
+                            let id = automation.id;
+                            let enabled = automation.enabled;
+                            let events = automation.triggers.iter().map(|t| trigger_label(*t)).collect::<Vec<_>>().join(", ");
+                            view! {
+                                <div class="flex items-center justify-between gap-3 rounded-md border px-3 py-2 text-sm">
+                                    <div class="flex min-w-0 flex-col">
+                                        <span class="truncate font-medium">{automation.name}</span>
+                                        <span class="truncate font-mono text-[11px] text-muted-foreground">
+                                            {action_summary(&automation.action)}
+                                        </span>
+                                        <span class="truncate text-[11px] text-muted-foreground">{events}</span>
+                                    </div>
+                                    <div class="flex shrink-0 items-center gap-2">
+                                        <Switch
+                                            checked=Signal::derive(move || enabled)
+                                            on_checked_change=Callback::new(move |value| set_enabled(id, value))
+                                        />
+                                        <Button
+                                            variant=ButtonVariant::Ghost
+                                            size=ButtonSize::Sm
+                                            class="text-destructive hover:bg-destructive/10"
+                                            attr:disabled=move || busy.get()
+                                            on:click=move |_| remove(id)
+                                        >
+                                            "Sil"
+                                        </Button>
+                                    </div>
+                                </div>
+                            }
+                        }).collect_view().into_any()
+                    }}
+                </div>
+
+                <form on:submit=create class="space-y-3 rounded-md border p-3">
+                    <div class="flex flex-wrap gap-2">
+                        <div class="min-w-40 flex-1">
+                            <Input r#type=InputType::Text placeholder="Otomasyon adı" bind_value=name />
+                        </div>
+                        <select
+                            class="h-9 rounded-md border border-input bg-background px-2 text-sm"
+                            prop:value=move || if is_script.get() { "script" } else { "webhook" }
+                            on:change=move |ev| is_script.set(event_target_value(&ev) == "script")
+                        >
+                            <option value="webhook">"Webhook"</option>
+                            <option value="script">"Komut"</option>
+                        </select>
+                    </div>
+
+                    <div class="flex flex-wrap gap-4 text-sm">
+                        {AutomationTrigger::ALL.into_iter().map(|trigger| view! {
+                            <label class="flex items-center gap-2">
+                                <Checkbox
+                                    checked=Signal::derive(move || triggers.get().contains(&trigger))
+                                    on_checked_change=Callback::new(move |checked: bool| triggers.update(|list| {
+                                        list.retain(|t| *t != trigger);
+                                        if checked {
+                                            list.push(trigger);
+                                        }
+                                    }))
+                                    aria_label=trigger_label(trigger).to_string()
+                                />
+                                {trigger_label(trigger)}
+                            </label>
+                        }).collect_view()}
+                    </div>
+
+                    {move || if is_script.get() {
+                        view! {
+                            <div class="space-y-1">
+                                <Input r#type=InputType::Text placeholder="/usr/local/bin/tamamlandi.sh" bind_value=command />
+                                <p class="text-[11px] text-muted-foreground">
+                                    "sh -c ile çalışır. Olay bilgisi VT_EVENT, VT_HASH, VT_NAME ve VT_MESSAGE ortam değişkenlerindedir."
+                                </p>
+                            </div>
+                        }.into_any()
+                    } else {
+                        view! {
+                            <div class="space-y-2">
+                                <div class="flex gap-2">
+                                    <select
+                                        class="h-9 rounded-md border border-input bg-background px-2 text-sm"
+                                        prop:value=move || method.get()
+                                        on:change=move |ev| method.set(event_target_value(&ev))
+                                    >
+                                        {METHODS.into_iter().map(|m| view! { <option value=m>{m}</option> }).collect_view()}
+                                    </select>
+                                    <div class="flex-1">
+                                        <Input r#type=InputType::Text placeholder="https://ornek.com/webhook" bind_value=url />
+                                    </div>
+                                </div>
+                                <textarea
+                                    class="min-h-20 w-full rounded-md border border-input bg-transparent px-3 py-2 font-mono text-xs"
+                                    placeholder=r#"{"event":"{{event}}","hash":"{{hash}}","name":"{{name}}","message":"{{message}}"}"#
+                                    prop:value=move || body_template.get()
+                                    on:input=move |ev| body_template.set(event_target_value(&ev))
+                                />
+                                <Input r#type=InputType::Password placeholder="İmza anahtarı (isteğe bağlı)" bind_value=secret />
+                                <p class="text-[11px] text-muted-foreground">
+                                    "Anahtar verilirse gövde HMAC-SHA256 ile imzalanıp X-VibeTorrent-Signature başlığında gönderilir."
+                                </p>
+                            </div>
+                        }.into_any()
+                    }}
+
+                    <Button attr:r#type="submit" attr:disabled=move || busy.get() || triggers.get().is_empty()>
+                        "Ekle"
+                    </Button>
+                </form>
+
+                <div class="space-y-2">
+                    <div class="flex items-center justify-between">
+                        <h4 class="text-sm font-medium">"Teslim Kaydı"</h4>
+                        <Button variant=ButtonVariant::Ghost size=ButtonSize::Sm on:click=move |_| refresh()>"Yenile"</Button>
+                    </div>
+                    {move || {
+                        let list = deliveries.get();
+                        if list.is_empty() {
+                            return view! {
+                                <p class="text-sm text-muted-foreground">"Henüz teslim yok."</p>
+                            }.into_any();
+                        }
+                        list.into_iter().map(|delivery| {
+                            let status_class = if delivery.success { "text-green-500" } else { "text-destructive" };
+                            let summary = format!(
+                                "{} · {} · {} · {} deneme",
+                                format_date(delivery.created_at),
+                                trigger_label(delivery.trigger),
+                                delivery.torrent_name,
+                                delivery.attempts,
+                            );
+                            view! {
+                                <div class="flex items-center justify-between gap-3 rounded-md border px-3 py-1.5 text-xs">
+                                    <div class="flex min-w-0 flex-col">
+                                        <span class="truncate font-medium">{delivery.automation_name}</span>
+                                        <span class="truncate text-[11px] text-muted-foreground">{summary}</span>
+                                    </div>
+                                    <span class=format!("shrink-0 font-mono {}", status_class)>{delivery.detail}</span>
+                                </div>
+                            }
+                        }).collect_view().into_any()
+                    }}
+                </div>
+            </CardContent>
+        </Card>
+    }
+}
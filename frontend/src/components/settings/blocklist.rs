@@ -0,0 +1,181 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::BlocklistStatus;
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::card::{Card, CardContent, CardDescription, CardHeader, CardTitle};
+use crate::components::ui::input::{Input, InputType};
+use crate::store::{toast_error, toast_success};
+
+fn format_applied_at(timestamp: Option<i64>) -> String {
+    timestamp
+        .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%d/%m/%Y %H:%M").to_string())
+        .unwrap_or_else(|| "Henüz uygulanmadı".to_string())
+}
+
+#[component]
+pub fn BlocklistSettings() -> impl IntoView {
+    let status = RwSignal::new(Option::<BlocklistStatus>::None);
+    let path = RwSignal::new(String::new());
+    let busy = RwSignal::new(false);
+    let file_input = NodeRef::<leptos::html::Input>::new();
+
+    let refresh = move || {
+        spawn_local(async move {
+            match shared::server_fns::blocklist::get_blocklist_status().await {
+                Ok(s) => status.set(Some(s)),
+                Err(e) => toast_error(format!("Engel listesi alınamadı: {}", e)),
+            }
+        });
+    };
+    refresh();
+
+    // Shared completion handler for every mutating call
+    let finish = move |result: Result<BlocklistStatus, ServerFnError>, success: &'static str| {
+        match result {
+            Ok(s) => {
+                status.set(Some(s));
+                toast_success(success);
+            }
+            Err(e) => toast_error(format!("Engel listesi hatası: {}", e)),
+        }
+        busy.set(false);
+    };
+
+    let import_path = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        let value = path.get().trim().to_string();
+        if value.is_empty() {
+            return;
+        }
+        busy.set(true);
+        spawn_local(async move {
+            let result = shared::server_fns::blocklist::import_blocklist_path(value).await;
+            if result.is_ok() {
+                path.set(String::new());
+            }
+            finish(result, "Engel listesi içe aktarıldı");
+        });
+    };
+
+    let import_upload = move |_| {
+        let Some(input) = file_input.get() else { return };
+        let Some(file) = input.files().and_then(|files| files.get(0)) else { return };
+        busy.set(true);
+        spawn_local(async move {
+            let name = file.name();
+            let buffer = match wasm_bindgen_futures::JsFuture::from(file.array_buffer()).await {
+                Ok(buffer) => buffer,
+                Err(e) => {
+                    toast_error(format!("Dosya okunamadı: {:?}", e));
+                    busy.set(false);
+                    return;
+                }
+            };
+            let data = js_sys::Uint8Array::new(&buffer).to_vec();
+            let result = shared::server_fns::blocklist::import_blocklist_upload(name, data).await;
+            if let Some(input) = file_input.get_untracked() {
+                input.set_value("");
+            }
+            finish(result, "Engel listesi yüklendi");
+        });
+    };
+
+    let remove_source = move |id: i64| {
+        busy.set(true);
+        spawn_local(async move {
+            let result = shared::server_fns::blocklist::remove_blocklist_source(id).await;
+            finish(result, "Kaynak kaldırıldı");
+        });
+    };
+
+    let reapply = move |_| {
+        busy.set(true);
+        spawn_local(async move {
+            let result = shared::server_fns::blocklist::apply_blocklist().await;
+            finish(result, "Engel listesi yeniden uygulandı");
+        });
+    };
+
+    view! {
+        <Card>
+            <CardHeader>
+                <CardTitle>"IP Engel Listesi"</CardTitle>
+                <CardDescription>
+                    "eMule DAT veya PeerGuardian P2P formatındaki listeleri (gzip dahil) içe aktarın. Liste, rTorrent'e her yeniden bağlanıldığında otomatik olarak uygulanır."
+                </CardDescription>
+            </CardHeader>
+            <CardContent class="space-y-4">
+                <div class="flex items-center justify-between rounded-lg border bg-muted/30 p-3">
+                    <div class="flex flex-col">
+                        <span class="text-2xl font-semibold font-mono">
+                            {move || status.get().map(|s| s.blocked_ranges).unwrap_or(0)}
+                        </span>
+                        <span class="text-xs text-muted-foreground">"engellenen IP aralığı"</span>
+                    </div>
+                    <div class="flex flex-col items-end gap-2">
+                        <span class="text-[10px] text-muted-foreground">
+                            {move || format_applied_at(status.get().and_then(|s| s.applied_at))}
+                        </span>
+                        <Button
+                            variant=ButtonVariant::Outline
+                            size=ButtonSize::Sm
+                            attr:disabled=move || busy.get()
+                            on:click=reapply
+                        >
+                            "Yeniden Uygula"
+                        </Button>
+                    </div>
+                </div>
+
+                <div class="space-y-2">
+                    {move || status.get().map(|s| {
+                        s.sources.into_iter().map(|source| {
+                            let id = source.id;
+                            let origin = source.path.clone().unwrap_or_else(|| "Yüklenen dosya".to_string());
+                            view! {
+                                <div class="flex items-center justify-between gap-3 rounded-md border px-3 py-2 text-sm">
+                                    <div class="flex min-w-0 flex-col">
+                                        <span class="truncate font-medium">{source.name}</span>
+                                        <span class="truncate text-[11px] text-muted-foreground">
+                                            {format!("{} · {} aralık", origin, source.range_count)}
+                                        </span>
+                                    </div>
+                                    <Button
+                                        variant=ButtonVariant::Ghost
+                                        size=ButtonSize::Sm
+                                        class="text-destructive hover:bg-destructive/10"
+                                        attr:disabled=move || busy.get()
+                                        on:click=move |_| remove_source(id)
+                                    >
+                                        "Kaldır"
+                                    </Button>
+                                </div>
+                            }
+                        }).collect_view()
+                    })}
+                </div>
+
+                <form on:submit=import_path class="flex gap-2">
+                    <Input
+                        r#type=InputType::Text
+                        placeholder="/srv/blocklists/level1.p2p.gz"
+                        bind_value=path
+                    />
+                    <Button attr:r#type="submit" attr:disabled=move || busy.get()>"Yoldan Ekle"</Button>
+                </form>
+
+                <div class="flex gap-2">
+                    <Input r#type=InputType::File node_ref=file_input />
+                    <Button
+                        variant=ButtonVariant::Secondary
+                        attr:disabled=move || busy.get()
+                        on:click=import_upload
+                    >
+                        "Yükle"
+                    </Button>
+                </div>
+            </CardContent>
+        </Card>
+    }
+}
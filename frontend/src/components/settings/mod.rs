@@ -0,0 +1,46 @@
+pub mod api_tokens;
+pub mod automations;
+pub mod blocklist;
+pub mod notifications;
+pub mod push;
+pub mod sessions;
+pub mod users;
+
+use leptos::prelude::*;
+use api_tokens::ApiTokenSettings;
+use automations::AutomationSettings;
+use blocklist::BlocklistSettings;
+use notifications::NotificationChannelSettings;
+use push::PushSettings;
+use sessions::SessionSettings;
+use shared::UserRole;
+use users::UserSettings;
+
+#[component]
+pub fn SettingsPage() -> impl IntoView {
+    let store = use_context::<crate::store::TorrentStore>().expect("store not provided");
+    // The server enforces roles; this only hides what would be refused
+    let is_admin = move || store.role.get() == Some(UserRole::Admin);
+
+    view! {
+        <div class="h-full overflow-y-auto px-4 py-6">
+            <div class="mx-auto max-w-3xl space-y-6">
+                <div class="space-y-1">
+                    <h1 class="text-2xl font-semibold tracking-tight">"Ayarlar"</h1>
+                    <p class="text-sm text-muted-foreground">"Sunucu ve uygulama tercihlerini yönetin."</p>
+                </div>
+                <Show when=is_admin>
+                    <UserSettings />
+                    <BlocklistSettings />
+                </Show>
+                <SessionSettings />
+                <ApiTokenSettings />
+                <PushSettings />
+                <Show when=is_admin>
+                    <NotificationChannelSettings />
+                    <AutomationSettings />
+                </Show>
+            </div>
+        </div>
+    }
+}
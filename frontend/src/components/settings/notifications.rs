@@ -0,0 +1,293 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::{ChannelConfig, LifecycleKind, NotificationChannel, SmtpSecurity};
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::card::{Card, CardContent, CardDescription, CardHeader, CardTitle};
+use crate::components::ui::checkbox::Checkbox;
+use crate::components::ui::input::{Input, InputType};
+use crate::components::ui::switch::Switch;
+use crate::store::{toast_error, toast_success};
+
+const KINDS: [(&str, &str); 5] = [
+    ("webhook", "JSON Webhook"),
+    ("ntfy", "ntfy"),
+    ("gotify", "Gotify"),
+    ("discord", "Discord / Slack"),
+    ("email", "E-posta (SMTP)"),
+];
+
+pub(super) fn event_label(kind: LifecycleKind) -> &'static str {
+    match kind {
+        LifecycleKind::Completed => "Tamamlandı",
+        LifecycleKind::Errored => "Torrent hatası",
+        LifecycleKind::Added => "Eklendi",
+        LifecycleKind::Removed => "Kaldırıldı",
+        LifecycleKind::TrackerError => "İzleyici hatası",
+        LifecycleKind::DiskLow => "Disk alanı azaldı",
+    }
+}
+
+fn channel_summary(config: &ChannelConfig) -> String {
+    match config {
+        ChannelConfig::Webhook { url } => format!("JSON Webhook · {}", url),
+        ChannelConfig::Ntfy { url, .. } => format!("ntfy · {}", url),
+        ChannelConfig::Gotify { url, .. } => format!("Gotify · {}", url),
+        ChannelConfig::Discord { url } => format!("Discord / Slack · {}", url),
+        ChannelConfig::Email { host, port, to, .. } => format!("E-posta · {}:{} → {}", host, port, to),
+    }
+}
+
+fn optional(value: String) -> Option<String> {
+    (!value.trim().is_empty()).then_some(value)
+}
+
+#[component]
+pub fn NotificationChannelSettings() -> impl IntoView {
+    let channels = RwSignal::new(Vec::<NotificationChannel>::new());
+    let busy = RwSignal::new(false);
+
+    let name = RwSignal::new(String::new());
+    let kind = RwSignal::new("ntfy".to_string());
+    let events = RwSignal::new(vec![LifecycleKind::Completed, LifecycleKind::Errored, LifecycleKind::DiskLow]);
+    let url = RwSignal::new(String::new());
+    let token = RwSignal::new(String::new());
+    let host = RwSignal::new(String::new());
+    let port = RwSignal::new("587".to_string());
+    let security = RwSignal::new(SmtpSecurity::StartTls);
+    let username = RwSignal::new(String::new());
+    let password = RwSignal::new(String::new());
+    let from = RwSignal::new(String::new());
+    let to = RwSignal::new(String::new());
+
+    spawn_local(async move {
+        match shared::server_fns::notifications::list_notification_channels().await {
+            Ok(list) => channels.set(list),
+            Err(e) => toast_error(format!("Bildirim kanalları alınamadı: {}", e)),
+        }
+    });
+
+    let create = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        let value = name.get().trim().to_string();
+        if value.is_empty() {
+            return;
+        }
+        let config = match kind.get().as_str() {
+            "webhook" => ChannelConfig::Webhook { url: url.get() },
+            "gotify" => ChannelConfig::Gotify { url: url.get(), token: token.get() },
+            "discord" => ChannelConfig::Discord { url: url.get() },
+            "email" => ChannelConfig::Email {
+                host: host.get(),
+                port: port.get().trim().parse().unwrap_or(0),
+                security: security.get(),
+                username: optional(username.get()),
+                password: optional(password.get()),
+                from: from.get(),
+                to: to.get(),
+            },
+            _ => ChannelConfig::Ntfy { url: url.get(), token: optional(token.get()) },
+        };
+        let selected = events.get();
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::notifications::create_notification_channel(value, config, selected).await {
+                Ok(created) => {
+                    channels.update(|list| list.insert(0, created));
+                    name.set(String::new());
+                    url.set(String::new());
+                    token.set(String::new());
+                    password.set(String::new());
+                    toast_success("Bildirim kanalı eklendi");
+                }
+                Err(e) => toast_error(format!("Bildirim kanalı eklenemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let set_enabled = move |id: i64, enabled: bool| {
+        spawn_local(async move {
+            match shared::server_fns::notifications::set_notification_channel_enabled(id, enabled).await {
+                Ok(()) => channels.update(|list| {
+                    if let Some(c) = list.iter_mut().find(|c| c.id == id) {
+                        c.enabled = enabled;
+                    }
+                }),
+                Err(e) => toast_error(format!("Bildirim kanalı güncellenemedi: {}", e)),
+            }
+        });
+    };
+
+    let send_test = move |id: i64| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::notifications::test_notification_channel(id).await {
+                Ok(()) => toast_success("Test bildirimi gönderildi"),
+                Err(e) => toast_error(format!("Test bildirimi gönderilemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let remove = move |id: i64| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::notifications::delete_notification_channel(id).await {
+                Ok(()) => {
+                    channels.update(|list| list.retain(|c| c.id != id));
+                    toast_success("Bildirim kanalı silindi");
+                }
+                Err(e) => toast_error(format!("Bildirim kanalı silinemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let select_class = "h-9 rounded-md border border-input bg-background px-2 text-sm";
+
+    view! {
+        <Card>
+            <CardHeader>
+                <CardTitle>"Bildirim Kanalları"</CardTitle>
+                <CardDescription>
+                    "Uygulama kapalıyken de haberdar olun: ntfy, Gotify, Discord/Slack, e-posta veya kendi webhook'unuz."
+                </CardDescription>
+            </CardHeader>
+            <CardContent class="space-y-4">
+                <div class="space-y-2">
+                    {move || {
+                        let list = channels.get();
+                        if list.is_empty() {
+                            return view! {
+                                <p class="text-sm text-muted-foreground">"Henüz bildirim kanalı yok."</p>
+                            }.into_any();
+                        }
+                        list.into_iter().map(|channel| {
+                            let id = channel.id;
+                            let enabled = channel.enabled;
+                            let labels = channel.events.iter().map(|e| event_label(*e)).collect::<Vec<_>>().join(", ");
+                            view! {
+                                <div class="flex items-center justify-between gap-3 rounded-md border px-3 py-2 text-sm">
+                                    <div class="flex min-w-0 flex-col">
+                                        <span class="truncate font-medium">{channel.name}</span>
+                                        <span class="truncate text-[11px] text-muted-foreground">{channel_summary(&channel.config)}</span>
+                                        <span class="truncate text-[11px] text-muted-foreground">{labels}</span>
+                                    </div>
+                                    <div class="flex shrink-0 items-center gap-2">
+                                        <Switch
+                                            checked=Signal::derive(move || enabled)
+                                            on_checked_change=Callback::new(move |value| set_enabled(id, value))
+                                        />
+                                        <Button
+                                            variant=ButtonVariant::Outline
+                                            size=ButtonSize::Sm
+                                            attr:disabled=move || busy.get()
+                                            on:click=move |_| send_test(id)
+                                        >
+                                            "Test"
+                                        </Button>
+                                        <Button
+                                            variant=ButtonVariant::Ghost
+                                            size=ButtonSize::Sm
+                                            class="text-destructive hover:bg-destructive/10"
+                                            attr:disabled=move || busy.get()
+                                            on:click=move |_| remove(id)
+                                        >
+                                            "Sil"
+                                        </Button>
+                                    </div>
+                                </div>
+                            }
+                        }).collect_view().into_any()
+                    }}
+                </div>
+
+                <form on:submit=create class="space-y-3 rounded-md border p-3">
+                    <div class="flex flex-wrap gap-2">
+                        <div class="min-w-40 flex-1">
+                            <Input r#type=InputType::Text placeholder="Kanal adı" bind_value=name />
+                        </div>
+                        <select
+                            class=select_class
+                            prop:value=move || kind.get()
+                            on:change=move |ev| kind.set(event_target_value(&ev))
+                        >
+                            {KINDS.into_iter().map(|(value, label)| view! { <option value=value>{label}</option> }).collect_view()}
+                        </select>
+                    </div>
+
+                    {move || match kind.get().as_str() {
+                        "email" => view! {
+                            <div class="grid gap-2 sm:grid-cols-2">
+                                <Input r#type=InputType::Text placeholder="SMTP sunucusu" bind_value=host />
+                                <div class="flex gap-2">
+                                    <Input r#type=InputType::Number placeholder="Port" bind_value=port />
+                                    <select
+                                        class=select_class
+                                        prop:value=move || format!("{:?}", security.get())
+                                        on:change=move |ev| security.set(match event_target_value(&ev).as_str() {
+                                            "Tls" => SmtpSecurity::Tls,
+                                            "None" => SmtpSecurity::None,
+                                            _ => SmtpSecurity::StartTls,
+                                        })
+                                    >
+                                        <option value="StartTls">"STARTTLS"</option>
+                                        <option value="Tls">"TLS"</option>
+                                        <option value="None">"Şifresiz"</option>
+                                    </select>
+                                </div>
+                                <Input r#type=InputType::Text placeholder="Kullanıcı adı (isteğe bağlı)" bind_value=username />
+                                <Input r#type=InputType::Password placeholder="Parola" bind_value=password />
+                                <Input r#type=InputType::Text placeholder="Gönderen adresi" bind_value=from />
+                                <Input r#type=InputType::Text placeholder="Alıcı adresi" bind_value=to />
+                            </div>
+                        }.into_any(),
+                        current => {
+                            let placeholder = match current {
+                                "ntfy" => "https://ntfy.sh/konu-adi",
+                                "gotify" => "https://gotify.ornek.com",
+                                "discord" => "https://discord.com/api/webhooks/...",
+                                _ => "https://ornek.com/bildirim",
+                            };
+                            let token_placeholder = match current {
+                                "ntfy" => Some("Erişim anahtarı (isteğe bağlı)"),
+                                "gotify" => Some("Uygulama anahtarı"),
+                                _ => None,
+                            };
+                            view! {
+                                <div class="space-y-2">
+                                    <Input r#type=InputType::Text placeholder=placeholder bind_value=url />
+                                    {token_placeholder.map(|p| view! {
+                                        <Input r#type=InputType::Password placeholder=p bind_value=token />
+                                    })}
+                                </div>
+                            }.into_any()
+                        }
+                    }}
+
+                    <div class="flex flex-wrap gap-4 text-sm">
+                        {LifecycleKind::ALL.into_iter().map(|event| view! {
+                            <label class="flex items-center gap-2">
+                                <Checkbox
+                                    checked=Signal::derive(move || events.get().contains(&event))
+                                    on_checked_change=Callback::new(move |checked: bool| events.update(|list| {
+                                        list.retain(|e| *e != event);
+                                        if checked {
+                                            list.push(event);
+                                        }
+                                    }))
+                                    aria_label=event_label(event).to_string()
+                                />
+                                {event_label(event)}
+                            </label>
+                        }).collect_view()}
+                    </div>
+
+                    <Button attr:r#type="submit" attr:disabled=move || busy.get() || events.get().is_empty()>
+                        "Ekle"
+                    </Button>
+                </form>
+            </CardContent>
+        </Card>
+    }
+}
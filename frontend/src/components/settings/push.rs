@@ -0,0 +1,199 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::{LifecycleKind, PushDevice, PushPreferences, QuietHours};
+use super::notifications::event_label;
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::card::{Card, CardContent, CardDescription, CardHeader, CardTitle};
+use crate::components::ui::checkbox::Checkbox;
+use crate::components::ui::input::{Input, InputType};
+use crate::components::ui::switch::Switch;
+use crate::store::{toast_error, toast_success};
+
+/// Short browser/OS label from a user agent string.
+pub(super) fn device_label(user_agent: Option<&str>) -> String {
+    let Some(ua) = user_agent else {
+        return "Bilinmeyen cihaz".to_string();
+    };
+    let browser = ["Edg/", "OPR/", "Firefox/", "Chrome/", "Safari/"]
+        .into_iter()
+        .find(|b| ua.contains(b))
+        .map(|b| match b {
+            "Edg/" => "Edge",
+            "OPR/" => "Opera",
+            other => other.trim_end_matches('/'),
+        })
+        .unwrap_or("Tarayıcı");
+    let os = ["Android", "iPhone", "iPad", "Windows", "Mac OS", "Linux"]
+        .into_iter()
+        .find(|os| ua.contains(os))
+        .map(|os| if os == "Mac OS" { "macOS" } else { os })
+        .unwrap_or("bilinmeyen sistem");
+    format!("{} · {}", browser, os)
+}
+
+fn format_minute(minute: u16) -> String {
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+fn parse_minute(value: &str) -> Option<u16> {
+    let (h, m) = value.split_once(':')?;
+    let (h, m) = (h.parse::<u16>().ok()?, m.parse::<u16>().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+#[component]
+pub fn PushSettings() -> impl IntoView {
+    let devices = RwSignal::new(Vec::<PushDevice>::new());
+    let busy = RwSignal::new(false);
+
+    let events = RwSignal::new(PushPreferences::default().events);
+    let quiet_enabled = RwSignal::new(false);
+    let quiet_start = RwSignal::new("23:00".to_string());
+    let quiet_end = RwSignal::new("07:00".to_string());
+
+    spawn_local(async move {
+        match shared::server_fns::push::list_push_devices().await {
+            Ok(list) => devices.set(list),
+            Err(e) => toast_error(format!("Cihazlar alınamadı: {}", e)),
+        }
+        match shared::server_fns::push::get_push_preferences().await {
+            Ok(prefs) => {
+                events.set(prefs.events);
+                if let Some(quiet) = prefs.quiet_hours {
+                    quiet_enabled.set(true);
+                    quiet_start.set(format_minute(quiet.start_minute));
+                    quiet_end.set(format_minute(quiet.end_minute));
+                }
+            }
+            Err(e) => toast_error(format!("Bildirim tercihleri alınamadı: {}", e)),
+        }
+    });
+
+    let save = move |_| {
+        let quiet_hours = if quiet_enabled.get() {
+            match (parse_minute(&quiet_start.get()), parse_minute(&quiet_end.get())) {
+                (Some(start_minute), Some(end_minute)) => Some(QuietHours {
+                    start_minute,
+                    end_minute,
+                    // Saved with today's offset; the window follows the browser's time zone
+                    utc_offset_minutes: chrono::Local::now().offset().local_minus_utc() / 60,
+                }),
+                _ => {
+                    toast_error("Sessiz saatler geçersiz");
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        let preferences = PushPreferences { events: events.get(), quiet_hours };
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::push::set_push_preferences(preferences).await {
+                Ok(()) => toast_success("Bildirim tercihleri kaydedildi"),
+                Err(e) => toast_error(format!("Bildirim tercihleri kaydedilemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let revoke = move |id: i64| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::push::revoke_push_device(id).await {
+                Ok(()) => {
+                    devices.update(|list| list.retain(|d| d.id != id));
+                    toast_success("Cihazın bildirim izni kaldırıldı");
+                }
+                Err(e) => toast_error(format!("Cihaz kaldırılamadı: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    view! {
+        <Card>
+            <CardHeader>
+                <CardTitle>"Anlık Bildirimler"</CardTitle>
+                <CardDescription>
+                    "Tarayıcı bildirimi alan cihazlarınız ve hangi olayların, ne zaman gönderileceği."
+                </CardDescription>
+            </CardHeader>
+            <CardContent class="space-y-4">
+                <div class="space-y-2">
+                    {move || {
+                        let list = devices.get();
+                        if list.is_empty() {
+                            return view! {
+                                <p class="text-sm text-muted-foreground">"Bildirim alan cihaz yok."</p>
+                            }.into_any();
+                        }
+                        list.into_iter().map(|device| {
+                            let id = device.id;
+                            view! {
+                                <div class="flex items-center justify-between gap-3 rounded-md border px-3 py-2 text-sm">
+                                    <div class="flex min-w-0 flex-col">
+                                        <span class="truncate font-medium">{device_label(device.user_agent.as_deref())}</span>
+                                        <span class="truncate text-[11px] text-muted-foreground">
+                                            {format!("Eklenme: {}", device.created_at)}
+                                        </span>
+                                    </div>
+                                    <Button
+                                        variant=ButtonVariant::Ghost
+                                        size=ButtonSize::Sm
+                                        class="text-destructive hover:bg-destructive/10"
+                                        attr:disabled=move || busy.get()
+                                        on:click=move |_| revoke(id)
+                                    >
+                                        "Kaldır"
+                                    </Button>
+                                </div>
+                            }
+                        }).collect_view().into_any()
+                    }}
+                </div>
+
+                <div class="space-y-3 rounded-md border p-3">
+                    <div class="flex flex-wrap gap-4 text-sm">
+                        {LifecycleKind::ALL.into_iter().map(|event| view! {
+                            <label class="flex items-center gap-2">
+                                <Checkbox
+                                    checked=Signal::derive(move || events.get().contains(&event))
+                                    on_checked_change=Callback::new(move |checked: bool| events.update(|list| {
+                                        list.retain(|e| *e != event);
+                                        if checked {
+                                            list.push(event);
+                                        }
+                                    }))
+                                    aria_label=event_label(event).to_string()
+                                />
+                                {event_label(event)}
+                            </label>
+                        }).collect_view()}
+                    </div>
+
+                    <div class="flex flex-wrap items-center gap-3 text-sm">
+                        <label class="flex items-center gap-2">
+                            <Switch
+                                checked=Signal::derive(move || quiet_enabled.get())
+                                on_checked_change=Callback::new(move |value| quiet_enabled.set(value))
+                            />
+                            "Sessiz saatler"
+                        </label>
+                        <Show when=move || quiet_enabled.get()>
+                            <div class="flex items-center gap-2">
+                                <Input r#type=InputType::Time bind_value=quiet_start />
+                                <span class="text-muted-foreground">"–"</span>
+                                <Input r#type=InputType::Time bind_value=quiet_end />
+                            </div>
+                        </Show>
+                    </div>
+
+                    <Button attr:disabled=move || busy.get() on:click=save>
+                        "Kaydet"
+                    </Button>
+                </div>
+            </CardContent>
+        </Card>
+    }
+}
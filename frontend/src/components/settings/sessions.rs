@@ -0,0 +1,110 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::LoginSession;
+use super::push::device_label;
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::card::{Card, CardContent, CardDescription, CardHeader, CardTitle};
+use crate::store::{toast_error, toast_success};
+
+fn format_date(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%d/%m/%Y %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[component]
+pub fn SessionSettings() -> impl IntoView {
+    let sessions = RwSignal::new(Vec::<LoginSession>::new());
+    let busy = RwSignal::new(false);
+
+    spawn_local(async move {
+        match shared::server_fns::sessions::list_sessions().await {
+            Ok(list) => sessions.set(list),
+            Err(e) => toast_error(format!("Oturumlar alınamadı: {}", e)),
+        }
+    });
+
+    let revoke = move |id: i64| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::sessions::revoke_session(id).await {
+                Ok(()) => {
+                    sessions.update(|list| list.retain(|s| s.id != id));
+                    toast_success("Oturum kapatıldı");
+                }
+                Err(e) => toast_error(format!("Oturum kapatılamadı: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let revoke_others = move |_| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::sessions::revoke_other_sessions().await {
+                Ok(count) => {
+                    sessions.update(|list| list.retain(|s| s.current));
+                    toast_success(format!("{} oturum kapatıldı", count));
+                }
+                Err(e) => toast_error(format!("Oturumlar kapatılamadı: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    view! {
+        <Card>
+            <CardHeader>
+                <CardTitle>"Oturumlar"</CardTitle>
+                <CardDescription>
+                    "Hesabınıza giriş yapılmış cihazlar. Tanımadığınız bir oturumu buradan kapatabilirsiniz."
+                </CardDescription>
+            </CardHeader>
+            <CardContent class="space-y-4">
+                <div class="space-y-2">
+                    {move || sessions.get().into_iter().map(|session| {
+                        let id = session.id;
+                        let current = session.current;
+                        let details = format!(
+                            "{} · Giriş: {} · Son etkinlik: {}{}",
+                            session.ip.as_deref().unwrap_or("Bilinmeyen adres"),
+                            format_date(session.created_at),
+                            format_date(session.last_seen_at),
+                            if session.remember { " · Hatırlanıyor" } else { "" },
+                        );
+                        view! {
+                            <div class="flex items-center justify-between gap-3 rounded-md border px-3 py-2 text-sm">
+                                <div class="flex min-w-0 flex-col">
+                                    <span class="truncate font-medium">
+                                        {device_label(session.user_agent.as_deref())}
+                                        {current.then(|| view! {
+                                            <span class="ml-2 text-[10px] font-normal text-muted-foreground">"(bu cihaz)"</span>
+                                        })}
+                                    </span>
+                                    <span class="truncate text-[11px] text-muted-foreground">{details}</span>
+                                </div>
+                                <Show when=move || !current>
+                                    <Button
+                                        variant=ButtonVariant::Ghost
+                                        size=ButtonSize::Sm
+                                        class="text-destructive hover:bg-destructive/10"
+                                        attr:disabled=move || busy.get()
+                                        on:click=move |_| revoke(id)
+                                    >
+                                        "Kapat"
+                                    </Button>
+                                </Show>
+                            </div>
+                        }
+                    }).collect_view()}
+                </div>
+
+                <Show when=move || sessions.get().iter().any(|s| !s.current)>
+                    <Button variant=ButtonVariant::Outline attr:disabled=move || busy.get() on:click=revoke_others>
+                        "Diğer Oturumları Kapat"
+                    </Button>
+                </Show>
+            </CardContent>
+        </Card>
+    }
+}
@@ -0,0 +1,227 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::{User, UserRole};
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::card::{Card, CardContent, CardDescription, CardHeader, CardTitle};
+use crate::components::ui::input::{Input, InputType};
+use crate::components::ui::switch::Switch;
+use crate::store::{toast_error, toast_success};
+
+fn role_label(role: UserRole) -> &'static str {
+    match role {
+        UserRole::Admin => "Yönetici",
+        UserRole::Operator => "Operatör",
+        UserRole::Viewer => "İzleyici",
+    }
+}
+
+#[component]
+pub fn UserSettings() -> impl IntoView {
+    let store = use_context::<crate::store::TorrentStore>().expect("store not provided");
+    let users = RwSignal::new(Vec::<User>::new());
+    let busy = RwSignal::new(false);
+    let reset_password = RwSignal::new(Option::<(String, String)>::None);
+
+    let username = RwSignal::new(String::new());
+    let password = RwSignal::new(String::new());
+    let role = RwSignal::new(UserRole::Viewer);
+
+    spawn_local(async move {
+        match shared::server_fns::users::list_users().await {
+            Ok(list) => users.set(list),
+            Err(e) => toast_error(format!("Kullanıcılar alınamadı: {}", e)),
+        }
+    });
+
+    let create = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        let name = username.get().trim().to_string();
+        let pass = password.get();
+        if name.is_empty() || pass.is_empty() {
+            return;
+        }
+        let selected = role.get();
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::users::create_user(name, pass, selected).await {
+                Ok(created) => {
+                    users.update(|list| list.push(created));
+                    username.set(String::new());
+                    password.set(String::new());
+                    toast_success("Kullanıcı eklendi");
+                }
+                Err(e) => toast_error(format!("Kullanıcı eklenemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let set_role = move |id: i64, new_role: UserRole| {
+        spawn_local(async move {
+            match shared::server_fns::users::set_user_role(id, new_role).await {
+                Ok(()) => users.update(|list| {
+                    if let Some(u) = list.iter_mut().find(|u| u.id == id) {
+                        u.role = new_role;
+                    }
+                }),
+                Err(e) => toast_error(format!("Rol değiştirilemedi: {}", e)),
+            }
+        });
+    };
+
+    let set_enabled = move |id: i64, enabled: bool| {
+        spawn_local(async move {
+            match shared::server_fns::users::set_user_disabled(id, !enabled).await {
+                Ok(()) => users.update(|list| {
+                    if let Some(u) = list.iter_mut().find(|u| u.id == id) {
+                        u.disabled = !enabled;
+                    }
+                }),
+                Err(e) => toast_error(format!("Kullanıcı güncellenemedi: {}", e)),
+            }
+        });
+    };
+
+    let reset = move |id: i64, name: String| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::users::reset_user_password(id).await {
+                Ok(new_password) => reset_password.set(Some((name, new_password))),
+                Err(e) => toast_error(format!("Parola sıfırlanamadı: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let remove = move |id: i64| {
+        busy.set(true);
+        spawn_local(async move {
+            match shared::server_fns::users::delete_user(id).await {
+                Ok(()) => {
+                    users.update(|list| list.retain(|u| u.id != id));
+                    toast_success("Kullanıcı silindi");
+                }
+                Err(e) => toast_error(format!("Kullanıcı silinemedi: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    let select_class = "h-9 rounded-md border border-input bg-background px-2 text-sm";
+
+    view! {
+        <Card>
+            <CardHeader>
+                <CardTitle>"Kullanıcılar"</CardTitle>
+                <CardDescription>
+                    "Yöneticiler her şeyi, operatörler torrentleri ve hız sınırlarını yönetebilir; izleyiciler yalnızca durumu görür."
+                </CardDescription>
+            </CardHeader>
+            <CardContent class="space-y-4">
+                {move || reset_password.get().map(|(name, new_password)| view! {
+                    <div class="space-y-2 rounded-lg border border-primary/40 bg-primary/5 p-3">
+                        <p class="text-xs text-muted-foreground">
+                            {format!("{} için yeni parola yalnızca şimdi gösteriliyor.", name)}
+                        </p>
+                        <div class="flex items-center gap-2">
+                            <code class="min-w-0 flex-1 truncate rounded bg-muted px-2 py-1 font-mono text-xs">{new_password}</code>
+                            <Button variant=ButtonVariant::Ghost size=ButtonSize::Sm on:click=move |_| reset_password.set(None)>
+                                "Kapat"
+                            </Button>
+                        </div>
+                    </div>
+                })}
+
+                <div class="space-y-2">
+                    {move || users.get().into_iter().map(|user| {
+                        let id = user.id;
+                        let is_self = store.user.get().as_deref() == Some(user.username.as_str());
+                        let enabled = !user.disabled;
+                        let current_role = user.role;
+                        let name = user.username.clone();
+                        view! {
+                            <div class="flex items-center justify-between gap-3 rounded-md border px-3 py-2 text-sm">
+                                <div class="flex min-w-0 flex-col">
+                                    <span class="truncate font-medium">
+                                        {user.username}
+                                        {is_self.then(|| view! {
+                                            <span class="ml-2 text-[10px] font-normal text-muted-foreground">"(siz)"</span>
+                                        })}
+                                    </span>
+                                    <span class="truncate text-[11px] text-muted-foreground">
+                                        {format!("Oluşturma: {}", user.created_at)}
+                                    </span>
+                                </div>
+                                <div class="flex shrink-0 items-center gap-2">
+                                    <select
+                                        class=select_class
+                                        disabled=is_self
+                                        prop:value=current_role.as_str()
+                                        on:change=move |ev| {
+                                            if let Some(r) = UserRole::parse(&event_target_value(&ev)) {
+                                                set_role(id, r);
+                                            }
+                                        }
+                                    >
+                                        {UserRole::ALL.into_iter().map(|r| view! {
+                                            <option value=r.as_str()>{role_label(r)}</option>
+                                        }).collect_view()}
+                                    </select>
+                                    <Show when=move || !is_self>
+                                        <Switch
+                                            checked=Signal::derive(move || enabled)
+                                            on_checked_change=Callback::new(move |value| set_enabled(id, value))
+                                        />
+                                    </Show>
+                                    <Button
+                                        variant=ButtonVariant::Outline
+                                        size=ButtonSize::Sm
+                                        attr:disabled=move || busy.get()
+                                        on:click={
+                                            let name = name.clone();
+                                            move |_| reset(id, name.clone())
+                                        }
+                                    >
+                                        "Parolayı Sıfırla"
+                                    </Button>
+                                    <Show when=move || !is_self>
+                                        <Button
+                                            variant=ButtonVariant::Ghost
+                                            size=ButtonSize::Sm
+                                            class="text-destructive hover:bg-destructive/10"
+                                            attr:disabled=move || busy.get()
+                                            on:click=move |_| remove(id)
+                                        >
+                                            "Sil"
+                                        </Button>
+                                    </Show>
+                                </div>
+                            </div>
+                        }
+                    }).collect_view()}
+                </div>
+
+                <form on:submit=create class="flex flex-wrap gap-2">
+                    <div class="min-w-32 flex-1">
+                        <Input r#type=InputType::Text placeholder="Kullanıcı adı" bind_value=username />
+                    </div>
+                    <div class="min-w-32 flex-1">
+                        <Input r#type=InputType::Password placeholder="Parola" bind_value=password />
+                    </div>
+                    <select
+                        class=select_class
+                        prop:value=move || role.get().as_str()
+                        on:change=move |ev| {
+                            if let Some(r) = UserRole::parse(&event_target_value(&ev)) {
+                                role.set(r);
+                            }
+                        }
+                    >
+                        {UserRole::ALL.into_iter().map(|r| view! { <option value=r.as_str()>{role_label(r)}</option> }).collect_view()}
+                    </select>
+                    <Button attr:r#type="submit" attr:disabled=move || busy.get()>"Ekle"</Button>
+                </form>
+            </CardContent>
+        </Card>
+    }
+}
@@ -0,0 +1,136 @@
+use leptos::prelude::*;
+use shared::{TransferSample, TransferTotal};
+use crate::utils::format::{format_bytes, format_speed};
+
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 180.0;
+
+fn local_time(ts: i64, fmt: &str) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format(fmt).to_string())
+        .unwrap_or_default()
+}
+
+/// SVG path through `(x, y)` points already scaled to the view box. With
+/// `closed`, the path is closed along the bottom edge for an area fill.
+fn svg_path(points: &[(f64, f64)], closed: bool) -> String {
+    let mut d = String::new();
+    for (i, (x, y)) in points.iter().enumerate() {
+        d.push_str(&format!("{}{:.1},{:.1} ", if i == 0 { "M" } else { "L" }, x, y));
+    }
+    if closed {
+        if let (Some(first), Some(last)) = (points.first(), points.last()) {
+            d.push_str(&format!("L{:.1},{:.1} L{:.1},{:.1} Z", last.0, HEIGHT, first.0, HEIGHT));
+        }
+    }
+    d
+}
+
+/// Download and upload speed over time as two area graphs.
+#[component]
+pub fn SpeedChart(#[prop(into)] samples: Signal<Vec<TransferSample>>) -> impl IntoView {
+    let geometry = Memo::new(move |_| {
+        samples.with(|samples| {
+            let (Some(first), Some(last)) = (samples.first(), samples.last()) else { return None };
+            let start = first.ts;
+            let span = ((last.ts + last.resolution) - start).max(1) as f64;
+            let max = samples
+                .iter()
+                .map(|s| s.down_rate.max(s.up_rate))
+                .max()
+                .unwrap_or(0)
+                .max(1024) as f64;
+            let scale = |rate: i64, ts: i64| {
+                ((ts - start) as f64 / span * WIDTH, HEIGHT - rate as f64 / max * (HEIGHT - 4.0))
+            };
+            let down: Vec<_> = samples.iter().map(|s| scale(s.down_rate, s.ts)).collect();
+            let up: Vec<_> = samples.iter().map(|s| scale(s.up_rate, s.ts)).collect();
+            Some((down, up, max as i64, start, last.ts + last.resolution))
+        })
+    });
+
+    view! {
+        {move || match geometry.get() {
+            None => view! {
+                <div class="flex h-48 items-center justify-center text-sm text-muted-foreground">
+                    "Bu aralık için kayıt yok"
+                </div>
+            }.into_any(),
+            Some((down, up, max, start, end)) => view! {
+                <div class="space-y-1">
+                    <div class="flex justify-between text-[10px] text-muted-foreground">
+                        <span>{format_speed(max)}</span>
+                    </div>
+                    <svg
+                        viewBox=format!("0 0 {} {}", WIDTH, HEIGHT)
+                        preserveAspectRatio="none"
+                        class="h-48 w-full overflow-visible border-b border-border"
+                    >
+                        <line x1="0" y1="4" x2=WIDTH y2="4" class="stroke-border" stroke-dasharray="4 4" />
+                        <path d=svg_path(&down, true) class="fill-blue-500/15" />
+                        <path d=svg_path(&down, false) class="fill-none stroke-blue-500" stroke-width="1.5" vector-effect="non-scaling-stroke" />
+                        <path d=svg_path(&up, true) class="fill-green-500/15" />
+                        <path d=svg_path(&up, false) class="fill-none stroke-green-500" stroke-width="1.5" vector-effect="non-scaling-stroke" />
+                    </svg>
+                    <div class="flex justify-between text-[10px] text-muted-foreground">
+                        <span>{local_time(start, "%d/%m %H:%M")}</span>
+                        <span>{local_time(end, "%d/%m %H:%M")}</span>
+                    </div>
+                </div>
+            }.into_any(),
+        }}
+    }
+}
+
+/// Downloaded and uploaded bytes per period as paired bars.
+#[component]
+pub fn TotalsChart(
+    #[prop(into)] totals: Signal<Vec<TransferTotal>>,
+    /// chrono format for the period labels
+    label_format: &'static str,
+) -> impl IntoView {
+    view! {
+        {move || totals.with(|totals| {
+            let max = totals
+                .iter()
+                .map(|t| t.downloaded.max(t.uploaded))
+                .max()
+                .unwrap_or(0)
+                .max(1) as f64;
+            let slot = WIDTH / totals.len().max(1) as f64;
+            let bar = (slot * 0.35).min(24.0);
+            let height = |bytes: i64| bytes as f64 / max * (HEIGHT - 4.0);
+
+            let bars = totals.iter().enumerate().map(|(i, total)| {
+                let center = slot * (i as f64 + 0.5);
+                let label = local_time(total.start, label_format);
+                view! {
+                    <g>
+                        <title>
+                            {format!("{}\nİndirilen: {}\nGönderilen: {}", label, format_bytes(total.downloaded), format_bytes(total.uploaded))}
+                        </title>
+                        <rect x=center - bar y=HEIGHT - height(total.downloaded) width=bar height=height(total.downloaded) class="fill-blue-500" />
+                        <rect x=center y=HEIGHT - height(total.uploaded) width=bar height=height(total.uploaded) class="fill-green-500" />
+                    </g>
+                }
+            }).collect_view();
+
+            // Label every bar only when they fit
+            let label_every = totals.len().div_ceil(12).max(1);
+            let labels = totals.iter().enumerate().map(|(i, total)| {
+                let label = if i % label_every == 0 { local_time(total.start, label_format) } else { String::new() };
+                view! { <span class="flex-1 truncate text-center">{label}</span> }
+            }).collect_view();
+
+            view! {
+                <div class="space-y-1">
+                    <div class="text-[10px] text-muted-foreground">{format_bytes(max as i64)}</div>
+                    <svg viewBox=format!("0 0 {} {}", WIDTH, HEIGHT) preserveAspectRatio="none" class="h-48 w-full border-b border-border">
+                        {bars}
+                    </svg>
+                    <div class="flex text-[10px] text-muted-foreground">{labels}</div>
+                </div>
+            }
+        })}
+    }
+}
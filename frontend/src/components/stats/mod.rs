@@ -0,0 +1,192 @@
+pub mod charts;
+
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::{TorrentTransferTotal, TransferPeriod, TransferSample, TransferTotal};
+
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::card::{Card, CardContent, CardDescription, CardHeader, CardTitle};
+use charts::{SpeedChart, TotalsChart};
+use crate::utils::format::{format_bytes, format_speed};
+
+const SPEED_RANGES: [(i64, &str); 4] = [(3600, "1 saat"), (6 * 3600, "6 saat"), (86400, "24 saat"), (7 * 86400, "7 gün")];
+const PERIODS: [(TransferPeriod, &str); 3] = [
+    (TransferPeriod::Day, "Günlük"),
+    (TransferPeriod::Week, "Haftalık"),
+    (TransferPeriod::Month, "Aylık"),
+];
+
+fn period_settings(period: TransferPeriod) -> (u32, &'static str) {
+    match period {
+        TransferPeriod::Day => (30, "%d/%m"),
+        TransferPeriod::Week => (12, "%d/%m"),
+        TransferPeriod::Month => (12, "%m/%Y"),
+    }
+}
+
+fn utc_offset_secs() -> i64 {
+    i64::from(chrono::Local::now().offset().local_minus_utc())
+}
+
+#[component]
+fn RangeButton(label: &'static str, active: Signal<bool>, on_select: impl Fn() + 'static) -> impl IntoView {
+    view! {
+        <Button
+            size=ButtonSize::Sm
+            variant=Signal::derive(move || if active.get() { ButtonVariant::Secondary } else { ButtonVariant::Ghost })
+            class="h-7 px-2 text-xs"
+            on:click=move |_| on_select()
+        >
+            {label}
+        </Button>
+    }
+}
+
+#[component]
+pub fn StatsPage() -> impl IntoView {
+    let speed_range = RwSignal::new(SPEED_RANGES[0].0);
+    let period = RwSignal::new(TransferPeriod::Day);
+    let samples = RwSignal::new(Vec::<TransferSample>::new());
+    let totals = RwSignal::new(Vec::<TransferTotal>::new());
+    let top = RwSignal::new(Vec::<TorrentTransferTotal>::new());
+    let error = RwSignal::new(Option::<String>::None);
+
+    Effect::new(move |_| {
+        let range = speed_range.get();
+        spawn_local(async move {
+            match shared::server_fns::stats::get_transfer_history(range).await {
+                Ok(s) => samples.set(s),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    });
+
+    Effect::new(move |_| {
+        let period = period.get();
+        let (count, _) = period_settings(period);
+        spawn_local(async move {
+            match shared::server_fns::stats::get_transfer_totals(period, count, utc_offset_secs()).await {
+                Ok(t) => totals.set(t),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    });
+
+    spawn_local(async move {
+        if let Ok(t) = shared::server_fns::stats::get_top_torrent_transfers(30, 10).await {
+            top.set(t);
+        }
+    });
+
+    let current_total = move |pick: fn(&TransferTotal) -> i64| {
+        totals.with(|t| t.last().map(pick).unwrap_or(0))
+    };
+    let period_total = move |pick: fn(&TransferTotal) -> i64| totals.with(|t| t.iter().map(pick).sum::<i64>());
+
+    view! {
+        <div class="h-full overflow-y-auto px-4 py-6">
+            <div class="mx-auto max-w-4xl space-y-6">
+                <div class="space-y-1">
+                    <h1 class="text-2xl font-semibold tracking-tight">"İstatistikler"</h1>
+                    <p class="text-sm text-muted-foreground">"Geçmiş aktarım hızları ve toplamları."</p>
+                </div>
+
+                {move || error.get().map(|e| view! { <p class="text-sm text-destructive">{e}</p> })}
+
+                <Card>
+                    <CardHeader>
+                        <div class="flex flex-wrap items-start justify-between gap-2">
+                            <div class="space-y-1">
+                                <CardTitle>"Hız Geçmişi"</CardTitle>
+                                <CardDescription>
+                                    <span class="text-blue-600 dark:text-blue-400">"İndirme"</span>
+                                    " / "
+                                    <span class="text-green-600 dark:text-green-400">"Gönderme"</span>
+                                </CardDescription>
+                            </div>
+                            <div class="flex gap-1">
+                                {SPEED_RANGES.iter().map(|(range, label)| {
+                                    let range = *range;
+                                    view! {
+                                        <RangeButton
+                                            label=label
+                                            active=Signal::derive(move || speed_range.get() == range)
+                                            on_select=move || speed_range.set(range)
+                                        />
+                                    }
+                                }).collect_view()}
+                            </div>
+                        </div>
+                    </CardHeader>
+                    <CardContent>
+                        <SpeedChart samples=samples />
+                    </CardContent>
+                </Card>
+
+                <Card>
+                    <CardHeader>
+                        <div class="flex flex-wrap items-start justify-between gap-2">
+                            <div class="space-y-1">
+                                <CardTitle>"Aktarım Toplamları"</CardTitle>
+                                <CardDescription>
+                                    {move || format!(
+                                        "Bu dönem: {} indirildi, {} gönderildi · Toplam: {} / {}",
+                                        format_bytes(current_total(|t| t.downloaded)),
+                                        format_bytes(current_total(|t| t.uploaded)),
+                                        format_bytes(period_total(|t| t.downloaded)),
+                                        format_bytes(period_total(|t| t.uploaded)),
+                                    )}
+                                </CardDescription>
+                            </div>
+                            <div class="flex gap-1">
+                                {PERIODS.iter().map(|(p, label)| {
+                                    let p = *p;
+                                    view! {
+                                        <RangeButton
+                                            label=label
+                                            active=Signal::derive(move || period.get() == p)
+                                            on_select=move || period.set(p)
+                                        />
+                                    }
+                                }).collect_view()}
+                            </div>
+                        </div>
+                    </CardHeader>
+                    <CardContent>
+                        {move || {
+                            let (_, label_format) = period_settings(period.get());
+                            view! { <TotalsChart totals=totals label_format=label_format /> }
+                        }}
+                    </CardContent>
+                </Card>
+
+                <Card>
+                    <CardHeader>
+                        <CardTitle>"En Çok Aktarım Yapanlar"</CardTitle>
+                        <CardDescription>"Son 30 gün"</CardDescription>
+                    </CardHeader>
+                    <CardContent>
+                        <Show
+                            when=move || top.with(|t| !t.is_empty())
+                            fallback=|| view! { <p class="text-sm text-muted-foreground">"Henüz kayıt yok"</p> }
+                        >
+                            <div class="divide-y divide-border text-sm">
+                                <For each=move || top.get() key=|t| t.hash.clone() let:torrent>
+                                    <div class="flex items-center gap-4 py-2">
+                                        <span class="min-w-0 flex-1 truncate" title=torrent.name.clone()>{torrent.name.clone()}</span>
+                                        <span class="shrink-0 font-mono text-xs text-blue-600 dark:text-blue-400">{format_bytes(torrent.downloaded)}</span>
+                                        <span class="shrink-0 font-mono text-xs text-green-600 dark:text-green-400">{format_bytes(torrent.uploaded)}</span>
+                                    </div>
+                                </For>
+                            </div>
+                        </Show>
+                    </CardContent>
+                </Card>
+
+                <p class="text-xs text-muted-foreground">
+                    {move || samples.with(|s| s.iter().map(|s| s.down_peak).max()).map(|peak| format!("En yüksek indirme hızı: {}", format_speed(peak)))}
+                </p>
+            </div>
+        </div>
+    }
+}
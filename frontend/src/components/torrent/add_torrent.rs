@@ -4,6 +4,7 @@ use wasm_bindgen::JsCast;
 use crate::components::ui::input::{Input, InputType};
 use crate::api;
 use crate::components::ui::button::Button;
+use crate::components::ui::directory_picker::DirectoryPicker;
 use crate::components::ui::dialog::{
     DialogBody, DialogHeader, DialogTitle, DialogDescription, DialogFooter, DialogClose
 };
@@ -11,12 +12,14 @@ use crate::components::ui::dialog::{
 #[component]
 pub fn AddTorrentDialogContent() -> impl IntoView {
     let uri = RwSignal::new(String::new());
+    let save_path = RwSignal::new(String::new());
     let is_loading = signal(false);
     let error_msg = signal(Option::<String>::None);
 
     let handle_submit = move |ev: web_sys::SubmitEvent| {
         ev.prevent_default();
         let uri_val = uri.get();
+        let save_path_val = Some(save_path.get()).filter(|p| !p.trim().is_empty());
         
         if uri_val.is_empty() {
             error_msg.1.set(Some("Lütfen bir Magnet URI veya URL girin".to_string()));
@@ -27,7 +30,7 @@ pub fn AddTorrentDialogContent() -> impl IntoView {
         error_msg.1.set(None);
 
         spawn_local(async move {
-            match api::torrent::add(&uri_val).await {
+            match api::torrent::add(&uri_val, save_path_val).await {
                 Ok(_) => {
                     log::info!("Torrent added successfully");
                     crate::store::toast_success("Torrent başarıyla eklendi");
@@ -42,6 +45,7 @@ pub fn AddTorrentDialogContent() -> impl IntoView {
                     }
                     
                     uri.set(String::new());
+                    save_path.set(String::new());
                     is_loading.1.set(false);
                 }
                 Err(e) => {
@@ -69,6 +73,11 @@ pub fn AddTorrentDialogContent() -> impl IntoView {
                     bind_value=uri
                     disabled=is_loading.0.get()
                 />
+
+                <div class="space-y-1.5">
+                    <label class="text-xs font-medium text-muted-foreground">"Kayıt klasörü"</label>
+                    <DirectoryPicker value=save_path disabled=is_loading.0 />
+                </div>
                 
                 {move || error_msg.0.get().map(|msg| view! {
                     <div class="rounded-lg border border-destructive/50 bg-destructive/10 p-3 text-sm text-destructive">
@@ -0,0 +1,285 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use wasm_bindgen::JsCast;
+use icons::{FolderInput, X};
+use shared::TorrentPieceMap;
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::checkbox::Checkbox;
+use crate::components::ui::directory_picker::DirectoryPicker;
+use crate::components::ui::sparkline::Sparkline;
+use crate::store::SPEED_HISTORY_LEN;
+use super::files::FileTreePanel;
+use crate::utils::format::{format_bytes, format_speed};
+
+/// Piece map refresh interval while the panel is open.
+const PIECE_MAP_REFRESH_MS: u64 = 5000;
+
+/// Reads a CSS color through an element's computed style so the canvas
+/// follows the active theme.
+fn computed_color(el: &web_sys::Element, fallback: &str) -> String {
+    window()
+        .get_computed_style(el)
+        .ok()
+        .flatten()
+        .and_then(|style| style.get_property_value("color").ok())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+/// Draws one column per device pixel. When there are more pieces than
+/// pixels, a column is "complete" only if every piece in it is, and missing
+/// columns are tinted by their rarest piece.
+fn draw_piece_map(canvas: &web_sys::HtmlCanvasElement, map: &TorrentPieceMap) {
+    let dpr = window().device_pixel_ratio().max(1.0);
+    let width = ((canvas.client_width() as f64) * dpr).max(1.0) as u32;
+    let height = ((canvas.client_height() as f64) * dpr).max(1.0) as u32;
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let Some(ctx) = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|c| c.dyn_into::<web_sys::CanvasRenderingContext2d>().ok())
+    else {
+        return;
+    };
+
+    let have_color = computed_color(canvas, "#3b82f6");
+    ctx.clear_rect(0.0, 0.0, width as f64, height as f64);
+
+    let pieces = map.piece_count;
+    if pieces == 0 {
+        return;
+    }
+
+    for x in 0..width {
+        let first = (x as u64 * pieces as u64 / width as u64) as u32;
+        let last = (((x as u64 + 1) * pieces as u64 / width as u64) as u32).max(first + 1).min(pieces);
+
+        let mut have = 0;
+        let mut rarest_missing = u8::MAX;
+        for piece in first..last {
+            if map.has_piece(piece) {
+                have += 1;
+            } else {
+                let seen = map.availability.get(piece as usize).copied().unwrap_or(0);
+                rarest_missing = rarest_missing.min(seen);
+            }
+        }
+
+        let span = last - first;
+        let color = if have == span {
+            have_color.clone()
+        } else if rarest_missing == 0 {
+            // Nobody in the swarm has this piece
+            "rgba(239, 68, 68, 0.85)".to_string()
+        } else if rarest_missing < 3 {
+            "rgba(245, 158, 11, 0.7)".to_string()
+        } else {
+            format!("rgba(148, 163, 184, {:.2})", 0.25 + 0.5 * have as f64 / span as f64)
+        };
+
+        ctx.set_fill_style_str(&color);
+        ctx.fill_rect(x as f64, 0.0, 1.0, height as f64);
+    }
+}
+
+#[component]
+pub fn PieceMapBar(hash: Signal<String>) -> impl IntoView {
+    let piece_map = RwSignal::new(Option::<TorrentPieceMap>::None);
+    let canvas_ref = NodeRef::<leptos::html::Canvas>::new();
+
+    let load = move || {
+        let requested = hash.get_untracked();
+        if requested.is_empty() {
+            return;
+        }
+        spawn_local(async move {
+            match shared::server_fns::torrent::get_piece_map(requested.clone()).await {
+                // Ignore late responses for a torrent that is no longer selected
+                Ok(map) if hash.get_untracked() == requested => piece_map.set(Some(map)),
+                Ok(_) => {}
+                Err(e) => log::warn!("Failed to load piece map: {:?}", e),
+            }
+        });
+    };
+
+    Effect::new(move |_| {
+        hash.track();
+        piece_map.set(None);
+        load();
+    });
+
+    let interval = set_interval_with_handle(load, std::time::Duration::from_millis(PIECE_MAP_REFRESH_MS)).ok();
+    on_cleanup(move || {
+        if let Some(handle) = interval {
+            handle.clear();
+        }
+    });
+
+    Effect::new(move |_| {
+        if let (Some(canvas), Some(map)) = (canvas_ref.get(), piece_map.get()) {
+            draw_piece_map(&canvas, &map);
+        }
+    });
+
+    let summary = move || {
+        piece_map.get().map(|map| {
+            let done = map.completed_pieces();
+            let missing_everywhere = (0..map.piece_count)
+                .filter(|i| !map.has_piece(*i) && map.availability.get(*i as usize).copied().unwrap_or(0) == 0)
+                .count();
+            format!(
+                "{} / {} parça · {} parça boyutu · {} parça kaynaksız",
+                done,
+                map.piece_count,
+                format_bytes(map.piece_size),
+                missing_everywhere
+            )
+        })
+    };
+
+    view! {
+        <div class="space-y-1.5">
+            <canvas node_ref=canvas_ref class="h-5 w-full rounded-sm bg-muted/60 text-primary" />
+            <div class="flex items-center justify-between text-[10px] text-muted-foreground">
+                <span>{move || summary().unwrap_or_else(|| "Parça haritası yükleniyor...".to_string())}</span>
+                <span class="flex items-center gap-3">
+                    <span class="flex items-center gap-1"><span class="size-2 rounded-sm bg-primary" />"Mevcut"</span>
+                    <span class="flex items-center gap-1"><span class="size-2 rounded-sm bg-amber-500/70" />"Nadir"</span>
+                    <span class="flex items-center gap-1"><span class="size-2 rounded-sm bg-red-500/85" />"Kaynaksız"</span>
+                </span>
+            </div>
+        </div>
+    }
+}
+
+/// Download and upload rates of the selected torrent, sampled from the
+/// one-second stats tick, on a shared scale.
+#[component]
+fn TorrentSpeedGraph() -> impl IntoView {
+    let store = use_context::<crate::store::TorrentStore>().expect("store not provided");
+    let history = store.selected_speed_history;
+    let down = Signal::derive(move || history.with(|h| h.down()));
+    let up = Signal::derive(move || history.with(|h| h.up()));
+    let peak = Signal::derive(move || history.with(|h| h.peak()).max(1024));
+    let current = move || history.with(|h| h.latest());
+
+    view! {
+        <div class="space-y-1">
+            <div class="flex items-center gap-4 text-[10px] font-mono">
+                <span class="text-blue-600 dark:text-blue-400">{move || format!("↓ {}", format_speed(current().0))}</span>
+                <span class="text-green-600 dark:text-green-400">{move || format!("↑ {}", format_speed(current().1))}</span>
+                <span class="ml-auto text-muted-foreground">{move || format!("En yüksek: {}", format_speed(peak.get()))}</span>
+            </div>
+            <div class="relative h-12 w-full rounded-sm bg-muted/40">
+                <Sparkline values=down slots=SPEED_HISTORY_LEN max=peak class="absolute inset-0 h-full w-full text-blue-500" />
+                <Sparkline values=up slots=SPEED_HISTORY_LEN max=peak class="absolute inset-0 h-full w-full text-green-500" />
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn MoveTorrentForm(hash: Signal<String>, open: RwSignal<bool>) -> impl IntoView {
+    let destination = RwSignal::new(String::new());
+    let move_data = RwSignal::new(true);
+    let busy = RwSignal::new(false);
+
+    let submit = move |ev: web_sys::SubmitEvent| {
+        ev.prevent_default();
+        let dest = destination.get_untracked();
+        if dest.trim().is_empty() {
+            crate::store::toast_error("Hedef klasör seçin");
+            return;
+        }
+        busy.set(true);
+        let hash = hash.get_untracked();
+        let move_data = move_data.get_untracked();
+        spawn_local(async move {
+            match shared::server_fns::torrent::move_torrent(hash, dest, move_data).await {
+                Ok(_) => {
+                    crate::store::toast_success("Torrent taşındı");
+                    destination.set(String::new());
+                    open.set(false);
+                }
+                Err(e) => crate::store::toast_error(format!("Taşınamadı: {}", e)),
+            }
+            busy.set(false);
+        });
+    };
+
+    view! {
+        <form class="space-y-2 rounded-md border border-border p-3" on:submit=submit>
+            <p class="text-xs font-medium">"Torrenti taşı"</p>
+            <DirectoryPicker value=destination placeholder="Hedef klasör" disabled=busy />
+            <div class="flex items-center justify-between gap-2">
+                <label class="flex items-center gap-2 text-xs text-muted-foreground">
+                    <Checkbox checked=move_data on_checked_change=Callback::new(move |v| move_data.set(v)) />
+                    "Verileri de taşı"
+                </label>
+                <div class="flex gap-2">
+                    <Button size=ButtonSize::Sm variant=ButtonVariant::Ghost attr:r#type="button" on:click=move |_| open.set(false)>"İptal"</Button>
+                    <Button size=ButtonSize::Sm attr:r#type="submit" attr:disabled=move || busy.get()>"Taşı"</Button>
+                </div>
+            </div>
+        </form>
+    }
+}
+
+#[component]
+pub fn TorrentDetails() -> impl IntoView {
+    let store = use_context::<crate::store::TorrentStore>().expect("store not provided");
+
+    let selected_hash = Memo::new(move |_| {
+        store.selected_torrent.get().filter(|hash| {
+            store.torrents.with(|map| map.contains_key(hash))
+        })
+    });
+    let name = Memo::new(move |_| {
+        selected_hash.get().and_then(|hash| {
+            store.torrents.with(|map| map.get(&hash).map(|t| t.name.clone()))
+        }).unwrap_or_default()
+    });
+    let hash = Signal::derive(move || selected_hash.get().unwrap_or_default());
+    let moving = RwSignal::new(false);
+
+    view! {
+        <Show when=move || selected_hash.get().is_some() fallback=|| ()>
+            <div class="border-t border-border bg-card/50 px-4 py-3 space-y-3">
+                <div class="flex items-center justify-between gap-3">
+                    <div class="min-w-0">
+                        <h3 class="truncate text-sm font-semibold" title=move || name.get()>{move || name.get()}</h3>
+                        <p class="text-[10px] font-mono text-muted-foreground truncate">{move || hash.get()}</p>
+                    </div>
+                    <div class="flex shrink-0 items-center gap-1">
+                        <Button
+                            variant=ButtonVariant::Ghost
+                            size=ButtonSize::Icon
+                            class="size-7"
+                            attr:title="Taşı"
+                            on:click=move |_| moving.update(|m| *m = !*m)
+                        >
+                            <FolderInput class="size-4" />
+                        </Button>
+                        <Button
+                            variant=ButtonVariant::Ghost
+                            size=ButtonSize::Icon
+                            class="size-7"
+                            on:click=move |_| store.selected_torrent.set(None)
+                        >
+                            <X class="size-4" />
+                        </Button>
+                    </div>
+                </div>
+                <Show when=move || moving.get() fallback=|| ()>
+                    <MoveTorrentForm hash=hash open=moving />
+                </Show>
+                <TorrentSpeedGraph />
+                <PieceMapBar hash=hash />
+                <FileTreePanel hash=hash />
+            </div>
+        </Show>
+    }
+}
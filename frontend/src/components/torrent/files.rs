@@ -0,0 +1,441 @@
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use std::collections::HashSet;
+use icons::{ChevronRight, Download, Eye, EyeOff, File, Folder, FolderOpen, Pencil, Play, X};
+use shared::{FileTreeNode, TorrentFileTree};
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::checkbox::Checkbox;
+use crate::components::ui::input::Input;
+use crate::store::{toast_error, toast_success};
+use crate::utils::format::format_bytes;
+
+fn priority_label(priority: u8) -> &'static str {
+    match priority {
+        0 => "Kapalı",
+        2 => "Yüksek",
+        _ => "Normal",
+    }
+}
+
+/// Files are served directly (with Range support), folders as a zip or tar.
+fn download_url(hash: &str, path: &str, format: Option<&str>) -> String {
+    let mut url = format!("/api/download/{}?path={}", hash, js_sys::encode_uri_component(path));
+    if let Some(format) = format {
+        url.push_str("&format=");
+        url.push_str(format);
+    }
+    url
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum MediaKind {
+    Video,
+    Audio,
+}
+
+/// Formats browsers can usually play natively.
+fn media_kind(name: &str) -> Option<MediaKind> {
+    let ext = name.rsplit_once('.')?.1.to_ascii_lowercase();
+    match ext.as_str() {
+        "mp4" | "m4v" | "webm" | "mkv" | "mov" | "ogv" => Some(MediaKind::Video),
+        "mp3" | "m4a" | "aac" | "flac" | "ogg" | "opus" | "wav" => Some(MediaKind::Audio),
+        _ => None,
+    }
+}
+
+#[derive(Clone, PartialEq)]
+struct NowPlaying {
+    index: u32,
+    name: String,
+    kind: MediaKind,
+    /// rTorrent accepted sequential download for the torrent
+    sequential: bool,
+}
+
+/// State shared by every row of the tree.
+#[derive(Clone, Copy)]
+struct FileTreeState {
+    hash: Signal<String>,
+    expanded: RwSignal<HashSet<String>>,
+    selected: RwSignal<HashSet<u32>>,
+    /// Bumped after every change to refetch the tree
+    version: RwSignal<u32>,
+    playing: RwSignal<Option<NowPlaying>>,
+    /// Single-file torrents are downloaded as the torrent itself, not by path
+    multi_file: RwSignal<bool>,
+}
+
+impl FileTreeState {
+    fn set_priority(self, node: &FileTreeNode, priority: u8) {
+        let hash = self.hash.get_untracked();
+        let target = match node.index {
+            Some(index) => Err(vec![index]),
+            None => Ok(node.path.clone()),
+        };
+        spawn_local(async move {
+            let result = match target {
+                Ok(folder) => shared::server_fns::torrent::set_folder_priority(hash, folder, priority).await,
+                Err(indices) => shared::server_fns::torrent::set_files_priority(hash, indices, priority).await,
+            };
+            match result {
+                Ok(_) => self.version.update(|v| *v += 1),
+                Err(e) => toast_error(format!("Öncelik ayarlanamadı: {}", e)),
+            }
+        });
+    }
+
+    fn play(self, index: u32, name: String, kind: MediaKind) {
+        let hash = self.hash.get_untracked();
+        spawn_local(async move {
+            match shared::server_fns::torrent::enable_streaming(hash, index).await {
+                Ok(sequential) => {
+                    self.playing.set(Some(NowPlaying { index, name, kind, sequential }));
+                    self.version.update(|v| *v += 1);
+                }
+                Err(e) => toast_error(format!("Akış başlatılamadı: {}", e)),
+            }
+        });
+    }
+
+    fn set_preview(self, indices: Vec<u32>, enabled: bool) {
+        let hash = self.hash.get_untracked();
+        spawn_local(async move {
+            match shared::server_fns::torrent::set_files_preview(hash, indices, enabled).await {
+                Ok(_) => self.version.update(|v| *v += 1),
+                Err(e) => toast_error(format!("Önizleme ayarlanamadı: {}", e)),
+            }
+        });
+    }
+}
+
+fn render_node(node: FileTreeNode, depth: usize, state: FileTreeState) -> AnyView {
+    let indices = node.file_indices();
+    let is_dir = node.is_dir();
+    let path = node.path.clone();
+
+    let checked = {
+        let indices = indices.clone();
+        Signal::derive(move || {
+            !indices.is_empty() && state.selected.with(|s| indices.iter().all(|i| s.contains(i)))
+        })
+    };
+    let on_checked = {
+        let indices = indices.clone();
+        Callback::new(move |value: bool| {
+            state.selected.update(|s| {
+                for index in &indices {
+                    if value { s.insert(*index); } else { s.remove(index); }
+                }
+            });
+        })
+    };
+
+    let is_expanded = {
+        let path = path.clone();
+        Signal::derive(move || state.expanded.with(|e| e.contains(&path)))
+    };
+    let toggle_expanded = {
+        let path = path.clone();
+        move |_| {
+            state.expanded.update(|e| {
+                if !e.remove(&path) { e.insert(path.clone()); }
+            });
+        }
+    };
+
+    let priority_value = node.priority.map(|p| p.to_string()).unwrap_or_default();
+    let priority_node = node.clone();
+    let on_priority = move |ev: leptos::ev::Event| {
+        if let Ok(priority) = event_target_value(&ev).parse::<u8>() {
+            state.set_priority(&priority_node, priority);
+        }
+    };
+
+    let preview = node.preview;
+    let preview_indices = indices.clone();
+    let progress = node.progress();
+    let children = node.children;
+
+    view! {
+        <div>
+            <div
+                class="group flex items-center gap-2 rounded-sm py-1 pr-2 text-xs hover:bg-muted/50"
+                style=format!("padding-left: {}rem", 0.5 + depth as f32 * 1.1)
+            >
+                <Checkbox checked=checked on_checked_change=on_checked aria_label="Seç" />
+                {if is_dir {
+                    view! {
+                        <button class="flex min-w-0 flex-1 items-center gap-1.5 text-left" on:click=toggle_expanded>
+                            <span class=move || if is_expanded.get() { "shrink-0 rotate-90 transition-transform" } else { "shrink-0 transition-transform" }>
+                                <ChevronRight class="size-3.5" />
+                            </span>
+                            {move || if is_expanded.get() {
+                                view! { <FolderOpen class="size-3.5 shrink-0 text-amber-500" /> }.into_any()
+                            } else {
+                                view! { <Folder class="size-3.5 shrink-0 text-amber-500" /> }.into_any()
+                            }}
+                            <span class="truncate font-medium" title=node.name.clone()>{node.name.clone()}</span>
+                        </button>
+                    }.into_any()
+                } else {
+                    view! {
+                        <span class="flex min-w-0 flex-1 items-center gap-1.5 pl-5">
+                            <File class="size-3.5 shrink-0 text-muted-foreground" />
+                            <span class="truncate" title=node.name.clone()>{node.name.clone()}</span>
+                        </span>
+                    }.into_any()
+                }}
+                <span class="w-20 shrink-0 text-right tabular-nums text-muted-foreground">{format_bytes(node.size)}</span>
+                <div class="flex w-28 shrink-0 items-center gap-1.5">
+                    <div class="h-1.5 flex-1 overflow-hidden rounded-full bg-muted">
+                        <div class="h-full bg-primary" style=format!("width: {:.1}%", progress) />
+                    </div>
+                    <span class="w-9 text-right tabular-nums text-[10px] text-muted-foreground">{format!("{:.0}%", progress)}</span>
+                </div>
+                <select
+                    class="h-6 w-20 shrink-0 rounded-sm border border-input bg-background px-1 text-[11px]"
+                    prop:value=priority_value
+                    on:change=on_priority
+                >
+                    <option value="" disabled=true>"Karışık"</option>
+                    {[2u8, 1, 0].into_iter().map(|p| view! { <option value=p.to_string()>{priority_label(p)}</option> }).collect_view()}
+                </select>
+                <div class="w-6 shrink-0">
+                    {(!is_dir).then(|| view! {
+                        <Button
+                            variant=ButtonVariant::Ghost
+                            size=ButtonSize::Icon
+                            class="size-6"
+                            attr:title=if preview { "Önizlemeyi kapat" } else { "İlk/son parçaları önce indir" }
+                            on:click=move |_| state.set_preview(preview_indices.clone(), !preview)
+                        >
+                            {if preview {
+                                view! { <Eye class="size-3.5 text-primary" /> }.into_any()
+                            } else {
+                                view! { <EyeOff class="size-3.5 text-muted-foreground" /> }.into_any()
+                            }}
+                        </Button>
+                    })}
+                </div>
+                <div class="w-6 shrink-0">
+                    {node.index.zip(media_kind(&node.name)).map(|(index, kind)| {
+                        let name = node.name.clone();
+                        view! {
+                            <Button
+                                variant=ButtonVariant::Ghost
+                                size=ButtonSize::Icon
+                                class="size-6"
+                                attr:title="Tarayıcıda oynat"
+                                on:click=move |_| state.play(index, name.clone(), kind)
+                            >
+                                <Play class="size-3.5" />
+                            </Button>
+                        }
+                    })}
+                </div>
+                <a
+                    class="flex size-6 shrink-0 items-center justify-center rounded-md text-muted-foreground hover:bg-accent hover:text-accent-foreground"
+                    href=move || {
+                        let path = if state.multi_file.get() { path.as_str() } else { "" };
+                        download_url(&state.hash.get(), path, None)
+                    }
+                    title=if is_dir { "ZIP olarak indir" } else { "İndir" }
+                    download=""
+                >
+                    <Download class="size-3.5" />
+                </a>
+            </div>
+            {move || is_expanded.get().then(|| {
+                children.iter().cloned().map(|child| render_node(child, depth + 1, state)).collect_view()
+            })}
+        </div>
+    }.into_any()
+}
+
+#[component]
+pub fn FileTreePanel(hash: Signal<String>) -> impl IntoView {
+    let tree = RwSignal::new(Option::<TorrentFileTree>::None);
+    let state = FileTreeState {
+        hash,
+        expanded: RwSignal::new(HashSet::new()),
+        selected: RwSignal::new(HashSet::new()),
+        version: RwSignal::new(0),
+        playing: RwSignal::new(None),
+        multi_file: RwSignal::new(true),
+    };
+
+    Effect::new(move |prev_hash: Option<String>| {
+        let requested = hash.get();
+        state.version.track();
+        if prev_hash.as_ref() != Some(&requested) {
+            tree.set(None);
+            state.expanded.set(HashSet::new());
+            state.selected.set(HashSet::new());
+            state.playing.set(None);
+        }
+        if !requested.is_empty() {
+            let requested = requested.clone();
+            spawn_local(async move {
+                match shared::server_fns::torrent::get_file_tree(requested.clone()).await {
+                    Ok(t) if hash.get_untracked() == requested => {
+                        state.multi_file.set(t.multi_file);
+                        tree.set(Some(t));
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Failed to load file tree: {:?}", e),
+                }
+            });
+        }
+        requested
+    });
+
+    let renaming = RwSignal::new(false);
+    let new_name = RwSignal::new(String::new());
+    let submit_rename = move || {
+        let name = new_name.get_untracked();
+        let hash = hash.get_untracked();
+        spawn_local(async move {
+            match shared::server_fns::torrent::rename_torrent_data(hash, name).await {
+                Ok(_) => {
+                    renaming.set(false);
+                    toast_success("Klasör yeniden adlandırıldı");
+                    state.version.update(|v| *v += 1);
+                }
+                Err(e) => toast_error(format!("Yeniden adlandırılamadı: {}", e)),
+            }
+        });
+    };
+
+    let selection_count = move || state.selected.with(|s| s.len());
+    let bulk_priority = move |priority: u8| {
+        let hash = hash.get_untracked();
+        let indices: Vec<u32> = state.selected.get_untracked().into_iter().collect();
+        spawn_local(async move {
+            match shared::server_fns::torrent::set_files_priority(hash, indices, priority).await {
+                Ok(_) => state.version.update(|v| *v += 1),
+                Err(e) => toast_error(format!("Öncelik ayarlanamadı: {}", e)),
+            }
+        });
+    };
+
+    let player = move || state.playing.get().map(|playing| {
+        let src = format!("/api/stream/{}/{}", hash.get_untracked(), playing.index);
+        view! {
+            <div class="space-y-1.5 rounded-md border border-border bg-black/90 p-2">
+                <div class="flex items-center justify-between gap-2 text-xs text-white/80">
+                    <span class="truncate">{playing.name.clone()}</span>
+                    <Button
+                        variant=ButtonVariant::Ghost
+                        size=ButtonSize::Icon
+                        class="size-6 text-white/80 hover:text-white"
+                        on:click=move |_| state.playing.set(None)
+                    >
+                        <X class="size-3.5" />
+                    </Button>
+                </div>
+                {match playing.kind {
+                    MediaKind::Video => view! { <video class="max-h-80 w-full" controls=true autoplay=true src=src /> }.into_any(),
+                    MediaKind::Audio => view! { <audio class="w-full" controls=true autoplay=true src=src /> }.into_any(),
+                }}
+                {(!playing.sequential).then(|| view! {
+                    <p class="text-[10px] text-white/60">
+                        "Bu rTorrent sürümü sıralı indirmeyi desteklemiyor; dosyanın ilk ve son parçaları öne alındı. İndirilmemiş bölümlere atlamak oynatmayı durdurabilir."
+                    </p>
+                })}
+            </div>
+        }
+    });
+
+    view! {
+        <div class="space-y-2">
+            {player}
+            {move || tree.get().map(|t| {
+                let root_name = t.root.name.clone();
+                let multi_file = t.multi_file;
+                view! {
+                    <div class="flex items-center justify-between gap-2 text-xs">
+                        <Show
+                            when=move || renaming.get()
+                            fallback=move || view! {
+                                <div class="flex min-w-0 items-center gap-1.5 text-muted-foreground">
+                                    <span class="truncate">{format!("{} · {}", root_name, format_bytes(t.root.size))}</span>
+                                    {multi_file.then(|| {
+                                        let root_name = t.root.name.clone();
+                                        view! {
+                                            <Button
+                                                variant=ButtonVariant::Ghost
+                                                size=ButtonSize::Icon
+                                                class="size-6"
+                                                attr:title="Klasörü yeniden adlandır"
+                                                on:click=move |_| {
+                                                    new_name.set(root_name.clone());
+                                                    renaming.set(true);
+                                                }
+                                            >
+                                                <Pencil class="size-3.5" />
+                                            </Button>
+                                        }
+                                    })}
+                                </div>
+                            }
+                        >
+                            <form
+                                class="flex flex-1 items-center gap-2"
+                                on:submit=move |ev| { ev.prevent_default(); submit_rename(); }
+                            >
+                                <Input class="h-7 text-xs" bind_value=new_name />
+                                <Button size=ButtonSize::Sm attr:r#type="submit">"Kaydet"</Button>
+                                <Button size=ButtonSize::Sm variant=ButtonVariant::Ghost on:click=move |_| renaming.set(false)>"İptal"</Button>
+                            </form>
+                        </Show>
+                        <Show when=move || { multi_file && selection_count() == 0 } fallback=|| ()>
+                            <div class="flex shrink-0 items-center gap-1">
+                                {["zip", "tar"].into_iter().map(|format| view! {
+                                    <a
+                                        class="inline-flex h-6 items-center gap-1 rounded-md border px-2 text-[11px] hover:bg-accent"
+                                        href=move || download_url(&hash.get(), "", Some(format))
+                                        download=""
+                                    >
+                                        <Download class="size-3" />
+                                        {format.to_uppercase()}
+                                    </a>
+                                }).collect_view()}
+                            </div>
+                        </Show>
+                        <Show when=move || { selection_count() > 0 } fallback=|| ()>
+                            <div class="flex shrink-0 items-center gap-1">
+                                <span class="mr-1 text-muted-foreground">{move || format!("{} dosya seçili", selection_count())}</span>
+                                {[2u8, 1, 0].into_iter().map(|p| view! {
+                                    <Button size=ButtonSize::Sm variant=ButtonVariant::Outline class="h-6 px-2 text-[11px]" on:click=move |_| bulk_priority(p)>
+                                        {priority_label(p)}
+                                    </Button>
+                                }).collect_view()}
+                                <Button
+                                    size=ButtonSize::Sm
+                                    variant=ButtonVariant::Outline
+                                    class="h-6 px-2 text-[11px]"
+                                    on:click=move |_| state.set_preview(state.selected.get_untracked().into_iter().collect(), true)
+                                >
+                                    "Önizleme"
+                                </Button>
+                                <Button
+                                    size=ButtonSize::Sm
+                                    variant=ButtonVariant::Ghost
+                                    class="h-6 px-2 text-[11px]"
+                                    on:click=move |_| state.selected.set(HashSet::new())
+                                >
+                                    "Temizle"
+                                </Button>
+                            </div>
+                        </Show>
+                    </div>
+                    <div class="max-h-72 overflow-y-auto rounded-md border border-border">
+                        {t.root.children.into_iter().map(|child| render_node(child, 0, state)).collect_view()}
+                    </div>
+                }.into_any()
+            }).unwrap_or_else(|| view! {
+                <p class="text-xs text-muted-foreground">"Dosyalar yükleniyor..."</p>
+            }.into_any())}
+        </div>
+    }
+}
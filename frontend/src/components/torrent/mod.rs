@@ -1,2 +1,4 @@
 pub mod table;
 pub mod add_torrent;
+pub mod details;
+pub mod files;
\ No newline at end of file
@@ -27,6 +27,7 @@ use crate::components::ui::alert_dialog::{
     AlertDialogTrigger,
 };
 use tailwind_fuse::tw_merge;
+use crate::utils::format::{format_bytes, format_speed};
 
 const ALL_COLUMNS: [(&str, &str); 8] = [
     ("Name", "Name"),
@@ -39,18 +40,6 @@ const ALL_COLUMNS: [(&str, &str); 8] = [
     ("AddedDate", "Date"),
 ];
 
-fn format_bytes(bytes: i64) -> String {
-    const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
-    if bytes < 1024 { return format!("{} B", bytes); }
-    let i = (bytes as f64).log2().div_euclid(10.0) as usize;
-    format!("{:.1} {}", (bytes as f64) / 1024_f64.powi(i as i32), UNITS[i])
-}
-
-fn format_speed(bytes_per_sec: i64) -> String {
-    if bytes_per_sec == 0 { return "0 B/s".to_string(); }
-    format!("{}/s", format_bytes(bytes_per_sec))
-}
-
 fn format_duration(seconds: i64) -> String {
     if seconds <= 0 { return "∞".to_string(); }
     let days = seconds / 86400;
@@ -0,0 +1,172 @@
+use icons::{ChevronRight, CornerLeftUp, Folder, FolderPlus};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use shared::DirectoryListing;
+use tw_merge::tw_merge;
+
+use crate::components::ui::button::{Button, ButtonSize, ButtonVariant};
+use crate::components::ui::input::Input;
+use crate::utils::format::format_bytes;
+
+/// Path input with a server-side directory browser. Only directories under
+/// the server's allowed roots can be listed; an empty value means "default".
+#[component]
+pub fn DirectoryPicker(
+    value: RwSignal<String>,
+    #[prop(into, optional)] class: String,
+    #[prop(into, optional)] placeholder: Option<String>,
+    #[prop(into, optional)] disabled: Signal<bool>,
+) -> impl IntoView {
+    let open = RwSignal::new(false);
+    let listing = RwSignal::new(Option::<DirectoryListing>::None);
+    let roots = RwSignal::new(Vec::<String>::new());
+    let error = RwSignal::new(Option::<String>::None);
+    let new_folder = RwSignal::new(String::new());
+
+    let navigate = move |path: String| {
+        error.set(None);
+        spawn_local(async move {
+            match shared::server_fns::browse::list_directories(path).await {
+                Ok(l) => listing.set(Some(l)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    let toggle = move |_| {
+        if open.get_untracked() {
+            open.set(false);
+            return;
+        }
+        open.set(true);
+        navigate(value.get_untracked());
+        if roots.with_untracked(|r| r.is_empty()) {
+            spawn_local(async move {
+                if let Ok(r) = shared::server_fns::browse::list_browse_roots().await {
+                    roots.set(r);
+                }
+            });
+        }
+    };
+
+    let create_folder = move || {
+        let Some(parent) = listing.with_untracked(|l| l.as_ref().map(|l| l.path.clone())) else { return };
+        let name = new_folder.get_untracked();
+        spawn_local(async move {
+            match shared::server_fns::browse::create_directory(parent, name).await {
+                Ok(path) => {
+                    new_folder.set(String::new());
+                    navigate(path);
+                }
+                Err(e) => error.set(Some(e.to_string())),
+            }
+        });
+    };
+
+    let select_current = move |_| {
+        if let Some(path) = listing.with_untracked(|l| l.as_ref().map(|l| l.path.clone())) {
+            value.set(path);
+        }
+        open.set(false);
+    };
+
+    view! {
+        <div data-name="DirectoryPicker" class=tw_merge!("space-y-2", class)>
+            <div class="flex gap-2">
+                <Input
+                    bind_value=value
+                    placeholder=placeholder.unwrap_or_else(|| "Varsayılan indirme klasörü".to_string())
+                />
+                <Button
+                    variant=ButtonVariant::Outline
+                    attr:r#type="button"
+                    attr:disabled=move || disabled.get()
+                    on:click=toggle
+                >
+                    <Folder class="size-4" />
+                    "Gözat"
+                </Button>
+            </div>
+
+            <Show when=move || open.get() fallback=|| ()>
+                <div class="rounded-md border border-border bg-card text-sm">
+                    <div class="flex items-center gap-2 border-b border-border px-2 py-1.5">
+                        <Show when=move || { roots.with(|r| r.len() > 1) } fallback=|| ()>
+                            <select
+                                class="h-7 max-w-40 rounded-sm border border-input bg-background px-1 text-xs"
+                                prop:value=move || listing.with(|l| l.as_ref().map(|l| l.root.clone()).unwrap_or_default())
+                                on:change=move |ev| navigate(event_target_value(&ev))
+                            >
+                                {move || roots.get().into_iter().map(|root| view! {
+                                    <option value=root.clone()>{root.clone()}</option>
+                                }).collect_view()}
+                            </select>
+                        </Show>
+                        <span class="min-w-0 flex-1 truncate font-mono text-xs" title=move || listing.with(|l| l.as_ref().map(|l| l.path.clone()))>
+                            {move || listing.with(|l| l.as_ref().map(|l| l.path.clone()).unwrap_or_else(|| "...".to_string()))}
+                        </span>
+                        {move || listing.with(|l| l.as_ref().and_then(|l| l.space.clone())).map(|space| view! {
+                            <span class="shrink-0 text-[10px] text-muted-foreground">
+                                {format!("{} boş / {}", format_bytes(space.available_bytes as i64), format_bytes(space.total_bytes as i64))}
+                            </span>
+                        })}
+                    </div>
+
+                    <div class="max-h-56 overflow-y-auto py-1">
+                        {move || listing.with(|l| l.as_ref().and_then(|l| l.parent.clone())).map(|parent| view! {
+                            <button
+                                type="button"
+                                class="flex w-full items-center gap-2 px-3 py-1 text-left text-xs text-muted-foreground hover:bg-accent"
+                                on:click=move |_| navigate(parent.clone())
+                            >
+                                <CornerLeftUp class="size-3.5" />
+                                ".."
+                            </button>
+                        })}
+                        {move || listing.get().map(|l| {
+                            if l.entries.is_empty() {
+                                return view! { <p class="px-3 py-1 text-xs text-muted-foreground">"Alt klasör yok"</p> }.into_any();
+                            }
+                            l.entries.into_iter().map(|entry| {
+                                let path = entry.path.clone();
+                                view! {
+                                    <button
+                                        type="button"
+                                        class="flex w-full items-center gap-2 px-3 py-1 text-left text-xs hover:bg-accent"
+                                        on:click=move |_| navigate(path.clone())
+                                    >
+                                        <Folder class="size-3.5 shrink-0 text-amber-500" />
+                                        <span class="flex-1 truncate">{entry.name}</span>
+                                        <ChevronRight class="size-3.5 shrink-0 text-muted-foreground" />
+                                    </button>
+                                }
+                            }).collect_view().into_any()
+                        })}
+                    </div>
+
+                    {move || error.get().map(|e| view! {
+                        <p class="border-t border-border px-3 py-1.5 text-xs text-destructive">{e}</p>
+                    })}
+
+                    <div class="flex items-center gap-2 border-t border-border px-2 py-1.5">
+                        <FolderPlus class="size-4 shrink-0 text-muted-foreground" />
+                        <Input class="h-7 text-xs" placeholder="Yeni klasör" bind_value=new_folder />
+                        <Button
+                            size=ButtonSize::Sm
+                            variant=ButtonVariant::Outline
+                            class="h-7"
+                            attr:r#type="button"
+                            attr:disabled=move || new_folder.with(|n| n.trim().is_empty())
+                            on:click=move |_| create_folder()
+                        >
+                            "Oluştur"
+                        </Button>
+                        <Button size=ButtonSize::Sm class="h-7" attr:r#type="button" on:click=select_current>
+                            "Bu klasörü seç"
+                        </Button>
+                    </div>
+                </div>
+            </Show>
+        </div>
+    }
+}
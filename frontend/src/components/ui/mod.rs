@@ -8,6 +8,7 @@ pub mod checkbox;
 pub mod context_menu;
 pub mod data_table;
 pub mod dialog;
+pub mod directory_picker;
 pub mod dropdown_menu;
 pub mod empty;
 pub mod input;
@@ -17,6 +18,7 @@ pub mod separator;
 pub mod sheet;
 pub mod sidenav;
 pub mod skeleton;
+pub mod sparkline;
 pub mod svg_icon;
 pub mod switch;
 pub mod table;
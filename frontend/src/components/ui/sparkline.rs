@@ -0,0 +1,64 @@
+use leptos::prelude::*;
+use tw_merge::tw_merge;
+
+const WIDTH: f64 = 100.0;
+const HEIGHT: f64 = 30.0;
+
+/// Builds the line and the area path for `values`, right-aligned in a box
+/// of `slots` points so a filling buffer grows from the right edge.
+fn paths(values: &[i64], slots: usize, max: i64) -> (String, String) {
+    if values.is_empty() {
+        return (String::new(), String::new());
+    }
+    let slots = slots.max(values.len()).max(2);
+    let step = WIDTH / (slots - 1) as f64;
+    let offset = slots - values.len();
+    let max = max.max(1) as f64;
+
+    let mut line = String::new();
+    for (i, value) in values.iter().enumerate() {
+        let x = (offset + i) as f64 * step;
+        let y = HEIGHT - (*value as f64 / max).min(1.0) * (HEIGHT - 1.0);
+        line.push_str(&format!("{}{:.2},{:.2} ", if i == 0 { "M" } else { "L" }, x, y));
+    }
+    let area = format!("{}L{:.2},{} L{:.2},{} Z", line, WIDTH, HEIGHT, offset as f64 * step, HEIGHT);
+    (line, area)
+}
+
+/// Minimal SVG line graph. Colour comes from `currentColor`, so set it with
+/// a `text-*` class. Pass a shared `max` to overlay several sparklines on
+/// the same scale.
+#[component]
+pub fn Sparkline(
+    #[prop(into)] values: Signal<Vec<i64>>,
+    /// Number of points the graph is wide; defaults to the number of values
+    #[prop(optional)] slots: usize,
+    #[prop(optional, into)] max: Option<Signal<i64>>,
+    #[prop(optional, into)] class: String,
+) -> impl IntoView {
+    let geometry = Memo::new(move |_| {
+        values.with(|values| {
+            let max = max.map(|m| m.get()).unwrap_or_else(|| values.iter().copied().max().unwrap_or(0));
+            paths(values, slots, max)
+        })
+    });
+
+    view! {
+        <svg
+            data-name="Sparkline"
+            viewBox=format!("0 0 {} {}", WIDTH, HEIGHT)
+            preserveAspectRatio="none"
+            class=tw_merge!("h-6 w-24 overflow-visible", class)
+        >
+            <path d=move || geometry.with(|g| g.1.clone()) fill="currentColor" fill-opacity="0.15" />
+            <path
+                d=move || geometry.with(|g| g.0.clone())
+                fill="none"
+                stroke="currentColor"
+                stroke-width="1.5"
+                stroke-linejoin="round"
+                vector-effect="non-scaling-stroke"
+            />
+        </svg>
+    }
+}
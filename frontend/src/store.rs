@@ -2,8 +2,8 @@ use futures::StreamExt;
 use gloo_net::eventsource::futures::EventSource;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
-use shared::{AppEvent, GlobalStats, NotificationLevel, Torrent};
-use std::collections::HashMap;
+use shared::{AppEvent, GlobalStats, NotificationLevel, Torrent, UserRole};
+use std::collections::{HashMap, VecDeque};
 use struct_patch::traits::Patch;
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
@@ -46,6 +46,48 @@ pub enum FilterStatus {
     All, Downloading, Seeding, Completed, Paused, Inactive, Active, Error,
 }
 
+/// Seconds of live rates kept for the speed graphs (stats arrive once a second).
+pub const SPEED_HISTORY_LEN: usize = 300;
+
+/// Ring buffer of the latest `(down_rate, up_rate)` samples, oldest first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpeedHistory {
+    samples: VecDeque<(i64, i64)>,
+}
+
+impl SpeedHistory {
+    pub fn push(&mut self, down_rate: i64, up_rate: i64) {
+        if self.samples.len() == SPEED_HISTORY_LEN {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((down_rate, up_rate));
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn down(&self) -> Vec<i64> {
+        self.samples.iter().map(|s| s.0).collect()
+    }
+
+    pub fn up(&self) -> Vec<i64> {
+        self.samples.iter().map(|s| s.1).collect()
+    }
+
+    pub fn latest(&self) -> (i64, i64) {
+        self.samples.back().copied().unwrap_or_default()
+    }
+
+    pub fn peak(&self) -> i64 {
+        self.samples.iter().map(|s| s.0.max(s.1)).max().unwrap_or(0)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct TorrentStore {
     pub torrents: RwSignal<HashMap<String, Torrent>>,
@@ -53,8 +95,14 @@ pub struct TorrentStore {
     pub search_query: RwSignal<String>,
     pub global_stats: RwSignal<GlobalStats>,
     pub user: RwSignal<Option<String>>,
+    /// Role of the logged-in user; controls what the UI offers
+    pub role: RwSignal<Option<UserRole>>,
     pub selected_torrent: RwSignal<Option<String>>,
     pub push_enabled: RwSignal<bool>,
+    /// Global rates over the last few minutes
+    pub speed_history: RwSignal<SpeedHistory>,
+    /// Rates of `selected_torrent` since it was selected
+    pub selected_speed_history: RwSignal<SpeedHistory>,
 }
 
 pub fn provide_torrent_store() {
@@ -63,14 +111,34 @@ pub fn provide_torrent_store() {
     let search_query = RwSignal::new(String::new());
     let global_stats = RwSignal::new(GlobalStats::default());
     let user = RwSignal::new(Option::<String>::None);
+    let role = RwSignal::new(Option::<UserRole>::None);
     let selected_torrent = RwSignal::new(Option::<String>::None);
     let push_enabled = RwSignal::new(false);
+    let speed_history = RwSignal::new(SpeedHistory::default());
+    let selected_speed_history = RwSignal::new(SpeedHistory::default());
 
     let show_browser_notification = crate::utils::notification::use_app_notification();
 
-    let store = TorrentStore { torrents, filter, search_query, global_stats, user, selected_torrent, push_enabled };
+    let store = TorrentStore {
+        torrents,
+        filter,
+        search_query,
+        global_stats,
+        user,
+        role,
+        selected_torrent,
+        push_enabled,
+        speed_history,
+        selected_speed_history,
+    };
     provide_context(store);
 
+    // A new selection starts a fresh graph
+    Effect::new(move |_| {
+        selected_torrent.track();
+        selected_speed_history.update(|h| h.clear());
+    });
+
     // Initial check for push status
     spawn_local(async move {
         if let Ok(enabled) = is_push_subscribed().await {
@@ -86,9 +154,16 @@ pub fn provide_torrent_store() {
         let mut backoff_ms: u32 = 1000;
         let mut was_connected = false;
         let mut disconnect_notified = false;
+        // Lets the server send only the events missed while disconnected
+        let mut last_event_id = String::new();
 
         loop {
-            let es_result = EventSource::new("/api/events");
+            let url = if last_event_id.is_empty() {
+                "/api/events".to_string()
+            } else {
+                format!("/api/events?last_event_id={}", last_event_id)
+            };
+            let es_result = EventSource::new(&url);
             match es_result {
                 Ok(mut es) => {
                     if let Ok(mut stream) = es.subscribe("message") {
@@ -104,6 +179,11 @@ pub fn provide_torrent_store() {
                                 was_connected = true;
                             }
 
+                            let event_id = msg.last_event_id();
+                            if !event_id.is_empty() {
+                                last_event_id = event_id;
+                            }
+
                             if let Some(data_str) = msg.data().as_string() {
                                 if let Ok(bytes) = BASE64.decode(&data_str) {
                                     if let Ok(event) = rmp_serde::from_slice::<AppEvent>(&bytes) {
@@ -120,13 +200,37 @@ pub fn provide_torrent_store() {
                                                     torrents_for_sse.update(|map| { if let Some(t) = map.get_mut(&hash) { t.apply(patch); } });
                                                 }
                                             }
-                                            AppEvent::Stats(stats) => { global_stats_for_sse.set(stats); }
+                                            AppEvent::Added(torrent) => {
+                                                torrents_for_sse.update(|map| { map.insert(torrent.hash.clone(), torrent); });
+                                            }
+                                            AppEvent::Removed(hash, _) => {
+                                                torrents_for_sse.update(|map| { map.remove(&hash); });
+                                            }
+                                            AppEvent::Stats(stats) => {
+                                                speed_history.update(|h| h.push(stats.down_rate, stats.up_rate));
+                                                // Sample the selected torrent on the same one-second tick
+                                                if let Some(hash) = selected_torrent.get_untracked() {
+                                                    let rates = torrents_for_sse.with_untracked(|map| map.get(&hash).map(|t| (t.down_rate, t.up_rate)));
+                                                    if let Some((down, up)) = rates {
+                                                        selected_speed_history.update(|h| h.push(down, up));
+                                                    }
+                                                }
+                                                global_stats_for_sse.set(stats);
+                                            }
                                             AppEvent::Notification(n) => {
                                                 show_toast(n.level.clone(), n.message.clone());
-                                                if n.message.contains("tamamlandı") || n.level == shared::NotificationLevel::Error {
+                                                if n.level == shared::NotificationLevel::Error {
                                                     show_browser_notification("VibeTorrent", &n.message);
                                                 }
                                             }
+                                            AppEvent::Lifecycle(event, _) => {
+                                                if event.shows_toast() {
+                                                    show_toast(event.level(), event.message());
+                                                }
+                                                if event.is_alert() {
+                                                    show_browser_notification(event.title(), &event.message());
+                                                }
+                                            }
                                         }
                                     }
                                 }
@@ -198,6 +302,26 @@ pub async fn subscribe_to_push_notifications() {
 
     // 4. Subscribe
     let push_manager = registration.push_manager().expect("no push manager");
+
+    // A subscription made with a since-rotated key blocks subscribing with
+    // the new one, and the server has already dropped it
+    if let Ok(existing) = push_manager.get_subscription() {
+        if let Ok(existing) = wasm_bindgen_futures::JsFuture::from(existing).await {
+            if let Ok(existing) = existing.dyn_into::<web_sys::PushSubscription>() {
+                let current_key = existing
+                    .options()
+                    .application_server_key()
+                    .ok()
+                    .flatten()
+                    .map(|key| js_sys::Uint8Array::new(&key).to_vec());
+                if current_key.as_deref() != Some(&decoded_key[..]) {
+                    if let Ok(promise) = existing.unsubscribe() {
+                        let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+                    }
+                }
+            }
+        }
+    }
     match wasm_bindgen_futures::JsFuture::from(push_manager.subscribe_with_options(&options).expect("subscribe failed")).await {
         Ok(subscription) => {
             let sub_js = subscription.clone();
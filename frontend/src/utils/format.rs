@@ -0,0 +1,11 @@
+/// Human-readable size with binary units, e.g. `1.5 MB`.
+pub fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 6] = ["B", "KB", "MB", "GB", "TB", "PB"];
+    if bytes < 1024 { return format!("{} B", bytes); }
+    let i = (bytes as f64).log2().div_euclid(10.0) as usize;
+    format!("{:.1} {}", (bytes as f64) / 1024_f64.powi(i as i32), UNITS[i])
+}
+
+pub fn format_speed(bytes_per_sec: i64) -> String {
+    format!("{}/s", format_bytes(bytes_per_sec))
+}
@@ -1,5 +1,6 @@
 use tailwind_fuse::merge::tw_merge;
 
+pub mod format;
 pub mod notification;
 pub mod platform;
 
@@ -0,0 +1,17 @@
+-- 003_blocklist.sql
+-- IP blocklist sources and generic key/value application settings
+
+CREATE TABLE IF NOT EXISTS blocklist_sources (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    -- Either a path on the server (re-read on every apply) or the uploaded file contents
+    path TEXT,
+    data BLOB,
+    range_count INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS app_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
@@ -0,0 +1,31 @@
+-- 004_transfer_history.sql
+-- Historical transfer statistics sampled by the backend poll loop
+
+-- Global rates and byte totals. Rows start at minute resolution and are
+-- folded into hourly, then daily rows as they age.
+CREATE TABLE IF NOT EXISTS transfer_samples (
+    -- Unix timestamp (UTC) of the start of the bucket
+    ts INTEGER NOT NULL,
+    -- Bucket length in seconds: 60, 3600 or 86400
+    resolution INTEGER NOT NULL,
+    down_rate INTEGER NOT NULL DEFAULT 0,
+    up_rate INTEGER NOT NULL DEFAULT 0,
+    down_peak INTEGER NOT NULL DEFAULT 0,
+    up_peak INTEGER NOT NULL DEFAULT 0,
+    downloaded INTEGER NOT NULL DEFAULT 0,
+    uploaded INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (resolution, ts)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transfer_samples_ts ON transfer_samples (ts);
+
+-- Per-torrent byte deltas, one row per torrent and UTC day
+CREATE TABLE IF NOT EXISTS torrent_transfer_daily (
+    day INTEGER NOT NULL,
+    hash TEXT NOT NULL,
+    -- Kept so totals stay readable after the torrent is removed
+    name TEXT NOT NULL,
+    downloaded INTEGER NOT NULL DEFAULT 0,
+    uploaded INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, hash)
+);
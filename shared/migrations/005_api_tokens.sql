@@ -0,0 +1,17 @@
+-- 005_api_tokens.sql
+-- Personal API tokens for scripts, sent as `Authorization: Bearer <token>`
+
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    -- SHA-256 of the token, hex encoded; the token itself is never stored
+    token_hash TEXT NOT NULL UNIQUE,
+    -- 'read', 'add' or 'full'
+    scope TEXT NOT NULL,
+    -- Unix timestamps
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER,
+    last_used_at INTEGER,
+    FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
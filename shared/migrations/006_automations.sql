@@ -0,0 +1,33 @@
+-- 006_automations.sql
+-- Webhooks and shell commands run on torrent events, with a delivery log
+
+CREATE TABLE IF NOT EXISTS automations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    -- Comma separated: 'added', 'completed', 'errored', 'removed'
+    triggers TEXT NOT NULL,
+    -- 'webhook' or 'script'
+    kind TEXT NOT NULL,
+    url TEXT,
+    method TEXT,
+    body_template TEXT,
+    -- HMAC key for webhook signatures; needed in clear to sign
+    secret TEXT,
+    command TEXT,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS automation_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    automation_id INTEGER NOT NULL,
+    trigger TEXT NOT NULL,
+    torrent_name TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    success INTEGER NOT NULL,
+    detail TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY(automation_id) REFERENCES automations(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_automation_deliveries_created ON automation_deliveries(created_at);
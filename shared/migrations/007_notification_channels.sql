@@ -0,0 +1,13 @@
+-- 007_notification_channels.sql
+-- Out-of-band notification channels (webhook, ntfy, Gotify, Discord, email)
+
+CREATE TABLE IF NOT EXISTS notification_channels (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    -- `ChannelConfig` as JSON, including tokens and passwords
+    config TEXT NOT NULL,
+    -- Comma separated `LifecycleKind` names, e.g. 'completed,errored'
+    events TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at INTEGER NOT NULL
+);
@@ -0,0 +1,22 @@
+-- 008_push_users.sql
+-- Ties push subscriptions to users and adds per-user push preferences
+
+ALTER TABLE push_subscriptions ADD COLUMN user_id INTEGER REFERENCES users(id) ON DELETE CASCADE;
+ALTER TABLE push_subscriptions ADD COLUMN user_agent TEXT;
+
+-- Subscriptions from before users were recorded go to the first account,
+-- which on a single-user install is the right owner
+UPDATE push_subscriptions SET user_id = (SELECT MIN(id) FROM users) WHERE user_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_push_subscriptions_user ON push_subscriptions(user_id);
+
+CREATE TABLE IF NOT EXISTS push_preferences (
+    user_id INTEGER PRIMARY KEY,
+    -- Comma separated `LifecycleKind` names
+    events TEXT NOT NULL,
+    -- Quiet hours as minutes after midnight in the user's time zone
+    quiet_start INTEGER,
+    quiet_end INTEGER,
+    utc_offset_minutes INTEGER NOT NULL DEFAULT 0,
+    FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- 009_user_roles.sql
+-- Roles and a disabled flag for user accounts
+
+-- Accounts created before roles existed were set up as the only user
+ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'admin';
+ALTER TABLE users ADD COLUMN disabled INTEGER NOT NULL DEFAULT 0;
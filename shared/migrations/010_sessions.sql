@@ -0,0 +1,23 @@
+-- 010_sessions.sql
+-- Database-backed login sessions. The old table was never written to while
+-- logins issued stateless JWTs, so it is recreated rather than migrated.
+
+DROP TABLE IF EXISTS sessions;
+
+CREATE TABLE sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    -- SHA-256 of the cookie value, hex encoded
+    token_hash TEXT NOT NULL UNIQUE,
+    -- "Remember me": a persistent cookie and a longer idle timeout
+    remember INTEGER NOT NULL DEFAULT 0,
+    ip TEXT,
+    user_agent TEXT,
+    -- Unix timestamps; expires_at moves forward while the session is used
+    created_at INTEGER NOT NULL,
+    last_seen_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    FOREIGN KEY(user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id);
@@ -0,0 +1,19 @@
+-- 011_users_autoincrement.sql
+-- Torrent owners are recorded in rTorrent by user id, so the id of a
+-- deleted account must never be given to a new one. Migrations run with
+-- foreign keys off, so dropping the old table does not cascade.
+
+CREATE TABLE users_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    role TEXT NOT NULL DEFAULT 'admin',
+    disabled INTEGER NOT NULL DEFAULT 0
+);
+
+INSERT INTO users_new (id, username, password_hash, created_at, role, disabled)
+    SELECT id, username, password_hash, created_at, role, disabled FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_new RENAME TO users;
@@ -0,0 +1,106 @@
+//! Personal API tokens: generation, hashing and scope checks.
+//!
+//! Tokens are 256 random bits, so a plain SHA-256 is enough to store them;
+//! unlike passwords they cannot be brute-forced from the hash.
+
+use crate::server_fns::{api_tokens, automations, sessions, torrent, users};
+use crate::ApiTokenScope;
+use leptos::server_fn::ServerFn;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Prefix that makes tokens easy to spot in configs and secret scanners.
+pub const TOKEN_PREFIX: &str = "vt_";
+
+/// Server functions that manage credentials or shell commands stay
+/// cookie-only, so a leaked token cannot mint others or run code.
+const COOKIE_ONLY_PATHS: [&str; 12] = [
+    sessions::ListSessions::PATH,
+    sessions::RevokeSession::PATH,
+    sessions::RevokeOtherSessions::PATH,
+    api_tokens::ListApiTokens::PATH,
+    api_tokens::CreateApiToken::PATH,
+    api_tokens::RevokeApiToken::PATH,
+    automations::CreateAutomation::PATH,
+    users::CreateUser::PATH,
+    users::DeleteUser::PATH,
+    users::SetUserRole::PATH,
+    users::SetUserDisabled::PATH,
+    users::ResetUserPassword::PATH,
+];
+
+const ADD_PATHS: [&str; 3] = ["/api/v1/torrents", torrent::AddTorrent::PATH, "/api/v2/torrents/add"];
+
+/// The WebSocket checks the scope per command, so every token may open it.
+const WS_PATH: &str = "/api/ws";
+
+/// Server functions are always POSTed, so read-only ones are told apart by
+/// name. Routes are the snake_case function name plus a hash.
+pub(crate) fn is_read_server_fn(path: &str) -> bool {
+    path.strip_prefix("/api/server_fns/")
+        .is_some_and(|name| name.starts_with("get_") || name.starts_with("list_"))
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn generate() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", TOKEN_PREFIX, to_hex(&bytes))
+}
+
+pub fn hash(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// Whether a request authenticated with a token of `scope` may go through.
+pub fn scope_allows(scope: ApiTokenScope, method: &str, path: &str) -> bool {
+    if COOKIE_ONLY_PATHS.iter().any(|p| path.starts_with(p)) {
+        return false;
+    }
+    if method == "GET" && path == WS_PATH {
+        return true;
+    }
+    match scope {
+        ApiTokenScope::Full => true,
+        ApiTokenScope::ReadOnly => method == "GET" || method == "HEAD" || is_read_server_fn(path),
+        ApiTokenScope::AddOnly => method == "POST" && ADD_PATHS.contains(&path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_hash() {
+        let token = generate();
+        assert!(token.starts_with(TOKEN_PREFIX));
+        assert_eq!(token.len(), TOKEN_PREFIX.len() + 64);
+        assert_eq!(hash(&token), hash(&token));
+        assert_ne!(hash(&token), hash(&generate()));
+    }
+
+    #[test]
+    fn test_scope_allows() {
+        let add_torrent = torrent::AddTorrent::PATH;
+        assert!(add_torrent.starts_with("/api/server_fns/add_torrent"));
+        assert!(scope_allows(ApiTokenScope::ReadOnly, "GET", "/api/v1/torrents"));
+        assert!(!scope_allows(ApiTokenScope::ReadOnly, "POST", "/api/v1/torrents/action"));
+        assert!(scope_allows(ApiTokenScope::ReadOnly, "POST", torrent::GetFiles::PATH));
+        assert!(!scope_allows(ApiTokenScope::ReadOnly, "POST", torrent::TorrentAction::PATH));
+        assert!(scope_allows(ApiTokenScope::AddOnly, "POST", "/api/v1/torrents"));
+        assert!(scope_allows(ApiTokenScope::AddOnly, "POST", add_torrent));
+        assert!(!scope_allows(ApiTokenScope::AddOnly, "GET", "/api/v1/torrents"));
+        assert!(!scope_allows(ApiTokenScope::AddOnly, "POST", "/api/v1/torrents/action"));
+        assert!(scope_allows(ApiTokenScope::AddOnly, "GET", "/api/ws"));
+        assert!(scope_allows(ApiTokenScope::Full, "DELETE", "/api/v1/torrents/abc"));
+        assert!(scope_allows(ApiTokenScope::Full, "POST", torrent::TorrentAction::PATH));
+        assert!(!scope_allows(ApiTokenScope::Full, "POST", api_tokens::CreateApiToken::PATH));
+        assert!(!scope_allows(ApiTokenScope::Full, "POST", sessions::ListSessions::PATH));
+        assert!(!scope_allows(ApiTokenScope::Full, "POST", users::CreateUser::PATH));
+        assert!(!scope_allows(ApiTokenScope::Full, "POST", automations::CreateAutomation::PATH));
+    }
+}
@@ -0,0 +1,306 @@
+#![cfg(feature = "ssr")]
+
+//! IP blocklist import (eMule DAT / PeerGuardian P2P) and compilation into
+//! rTorrent `ipv4_filter` commands.
+
+use crate::db::Db;
+use crate::xmlrpc::{RpcParam, RtorrentClient, XmlRpcError};
+use std::io::Read;
+use std::net::Ipv4Addr;
+
+/// Number of `ipv4_filter.add_address` calls sent per `system.multicall`.
+const APPLY_BATCH_SIZE: usize = 1000;
+
+/// eMule DAT entries with an access level above this value are allowed, not blocked.
+const DAT_MAX_BLOCKED_LEVEL: u32 = 127;
+
+/// Largest blocklist accepted, before and after decompression. Real lists
+/// are a few dozen megabytes; the cap keeps a gzip bomb from exhausting
+/// memory.
+pub const MAX_BLOCKLIST_BYTES: u64 = 64 << 20;
+
+pub const SETTING_APPLIED_RANGES: &str = "blocklist.applied_ranges";
+pub const SETTING_APPLIED_AT: &str = "blocklist.applied_at";
+
+#[derive(Debug, Default)]
+pub struct ParsedBlocklist {
+    /// Inclusive `(start, end)` ranges, as written in the source.
+    pub ranges: Vec<(u32, u32)>,
+    /// Lines that were neither comments nor valid entries.
+    pub skipped: usize,
+}
+
+/// Decompresses gzip input (detected by magic bytes) and returns plain text.
+pub fn decode(data: &[u8]) -> std::io::Result<String> {
+    decode_limited(data, MAX_BLOCKLIST_BYTES)
+}
+
+fn too_large(limit: u64) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("blocklist is larger than {} MB", limit >> 20),
+    )
+}
+
+fn decode_limited(data: &[u8], limit: u64) -> std::io::Result<String> {
+    if data.len() as u64 > limit {
+        return Err(too_large(limit));
+    }
+    let bytes = if data.starts_with(&[0x1f, 0x8b]) {
+        let mut out = Vec::new();
+        flate2::read::MultiGzDecoder::new(data).take(limit + 1).read_to_end(&mut out)?;
+        if out.len() as u64 > limit {
+            return Err(too_large(limit));
+        }
+        out
+    } else {
+        data.to_vec()
+    };
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Reads a blocklist file from disk, refusing files above `MAX_BLOCKLIST_BYTES`.
+pub async fn read_file(path: &str) -> std::io::Result<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    let file = tokio::fs::File::open(path).await?;
+    let mut data = Vec::new();
+    file.take(MAX_BLOCKLIST_BYTES + 1).read_to_end(&mut data).await?;
+    if data.len() as u64 > MAX_BLOCKLIST_BYTES {
+        return Err(too_large(MAX_BLOCKLIST_BYTES));
+    }
+    Ok(data)
+}
+
+/// Parses a blocklist in either eMule DAT or PeerGuardian P2P format.
+/// The format is detected per line, so mixed files are accepted as well.
+pub fn parse(text: &str) -> ParsedBlocklist {
+    let mut parsed = ParsedBlocklist::default();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        match parse_dat_line(line).or_else(|| parse_p2p_line(line)) {
+            Some(Some(range)) => parsed.ranges.push(range),
+            Some(None) => {} // Valid entry, but not a blocking one
+            None => parsed.skipped += 1,
+        }
+    }
+
+    parsed
+}
+
+/// `000.000.000.000 - 000.255.255.255 , 000 , Description`
+fn parse_dat_line(line: &str) -> Option<Option<(u32, u32)>> {
+    let mut fields = line.splitn(3, ',');
+    let range = fields.next()?;
+    let level: u32 = fields.next()?.trim().parse().ok()?;
+    let range = parse_range(range)?;
+
+    if level > DAT_MAX_BLOCKED_LEVEL {
+        return Some(None);
+    }
+    Some(Some(range))
+}
+
+/// `Description:1.2.3.0-1.2.3.255` (the description may itself contain colons)
+fn parse_p2p_line(line: &str) -> Option<Option<(u32, u32)>> {
+    let (_, range) = line.rsplit_once(':')?;
+    parse_range(range).map(Some)
+}
+
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    let (start, end) = range.split_once('-')?;
+    let start = parse_ipv4(start.trim())?;
+    let end = parse_ipv4(end.trim())?;
+    if start > end {
+        return None;
+    }
+    Some((start, end))
+}
+
+/// Lenient IPv4 parser: blocklists commonly zero-pad octets (`001.002.003.004`),
+/// which `Ipv4Addr::from_str` rejects.
+fn parse_ipv4(s: &str) -> Option<u32> {
+    let mut octets = [0u8; 4];
+    let mut parts = s.split('.');
+    for octet in octets.iter_mut() {
+        *octet = parts.next()?.parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(u32::from(Ipv4Addr::from(octets)))
+}
+
+/// Sorts and merges overlapping or adjacent ranges.
+pub fn merge_ranges(mut ranges: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+
+    for (start, end) in ranges {
+        if let Some(last) = merged.last_mut() {
+            if start <= last.1.saturating_add(1) {
+                last.1 = last.1.max(end);
+                continue;
+            }
+        }
+        merged.push((start, end));
+    }
+
+    merged
+}
+
+/// Splits an inclusive range into the minimal set of CIDR blocks.
+pub fn range_to_cidrs(start: u32, end: u32) -> Vec<String> {
+    let mut cidrs = Vec::new();
+    let mut current = start as u64;
+    let end = end as u64;
+
+    while current <= end {
+        // Largest block aligned at `current`...
+        let mut size = if current == 0 { 32 } else { current.trailing_zeros().min(32) };
+        // ...that does not overshoot `end`.
+        while size > 0 && current + (1u64 << size) - 1 > end {
+            size -= 1;
+        }
+        cidrs.push(format!("{}/{}", Ipv4Addr::from(current as u32), 32 - size));
+        current += 1u64 << size;
+    }
+
+    cidrs
+}
+
+/// Reads, parses and merges every stored source.
+pub async fn compile_sources(db: &Db) -> anyhow::Result<Vec<(u32, u32)>> {
+    let mut ranges = Vec::new();
+
+    for source in db.get_blocklist_sources_with_data().await? {
+        let data = match (source.data, source.path.as_deref()) {
+            (Some(data), _) => data,
+            (None, Some(path)) => match read_file(path).await {
+                Ok(data) => data,
+                Err(e) => {
+                    tracing::warn!("Blocklist source '{}' unreadable ({}): {}", source.name, path, e);
+                    continue;
+                }
+            },
+            (None, None) => continue,
+        };
+        ranges.extend(parse(&decode(&data)?).ranges);
+    }
+
+    Ok(merge_ranges(ranges))
+}
+
+/// Replaces rTorrent's IPv4 filter with the given merged ranges.
+pub async fn apply(client: &RtorrentClient, ranges: &[(u32, u32)]) -> Result<usize, XmlRpcError> {
+    client.call("ipv4_filter.reset", &[]).await?;
+
+    let cidrs: Vec<String> = ranges
+        .iter()
+        .flat_map(|(start, end)| range_to_cidrs(*start, *end))
+        .collect();
+
+    for chunk in cidrs.chunks(APPLY_BATCH_SIZE) {
+        let calls: Vec<(&str, Vec<RpcParam>)> = chunk
+            .iter()
+            .map(|cidr| {
+                (
+                    "ipv4_filter.add_address",
+                    vec![RpcParam::from(cidr.as_str()), RpcParam::from("unwanted")],
+                )
+            })
+            .collect();
+        client.multicall(&calls).await?;
+    }
+
+    Ok(cidrs.len())
+}
+
+/// Rebuilds the filter from the database and pushes it to rTorrent.
+/// Returns the number of merged ranges now blocked.
+pub async fn reapply(socket_path: &str, db: &Db) -> anyhow::Result<usize> {
+    let ranges = compile_sources(db).await?;
+    let client = RtorrentClient::new(socket_path);
+    let cidr_count = apply(&client, &ranges).await?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    db.set_setting(SETTING_APPLIED_RANGES, &ranges.len().to_string()).await?;
+    db.set_setting(SETTING_APPLIED_AT, &now.to_string()).await?;
+
+    tracing::info!(
+        "Applied IP blocklist: {} ranges ({} CIDR blocks)",
+        ranges.len(),
+        cidr_count
+    );
+    Ok(ranges.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dat_and_p2p() {
+        let text = "\
+# comment
+001.002.003.000 - 001.002.003.255 , 000 , Some ISP
+010.000.000.000 - 010.255.255.255 , 200 , Allowed range
+Bad Guys, Inc: Level 1:5.6.7.8-5.6.7.9
+garbage line
+";
+        let parsed = parse(text);
+        assert_eq!(
+            parsed.ranges,
+            vec![(0x01020300, 0x010203ff), (0x05060708, 0x05060709)]
+        );
+        assert_eq!(parsed.skipped, 1);
+    }
+
+    #[test]
+    fn test_merge_ranges() {
+        let merged = merge_ranges(vec![(10, 20), (0, 5), (6, 8), (15, 30), (40, 41)]);
+        assert_eq!(merged, vec![(0, 8), (10, 30), (40, 41)]);
+    }
+
+    #[test]
+    fn test_range_to_cidrs() {
+        let start = parse_ipv4("1.2.3.0").unwrap();
+        let end = parse_ipv4("1.2.4.1").unwrap();
+        assert_eq!(range_to_cidrs(start, end), vec!["1.2.3.0/24", "1.2.4.0/31"]);
+        assert_eq!(range_to_cidrs(0, u32::MAX), vec!["0.0.0.0/0"]);
+    }
+
+    #[test]
+    fn test_decode_gzip() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"x:1.1.1.1-1.1.1.1\n").unwrap();
+        let gz = encoder.finish().unwrap();
+
+        assert_eq!(decode(&gz).unwrap(), "x:1.1.1.1-1.1.1.1\n");
+    }
+
+    #[test]
+    fn test_decode_limit() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[b'0'; 4096]).unwrap();
+        let bomb = encoder.finish().unwrap();
+
+        assert!(decode_limited(&bomb, 4096).is_ok());
+        assert!(decode_limited(&bomb, 4095).is_err());
+        assert!(decode_limited(&[b'x'; 100], 99).is_err());
+    }
+}
@@ -3,6 +3,17 @@ use std::time::Duration;
 use anyhow::Result;
 use std::str::FromStr;
 
+const VAPID_PRIVATE_KEY_SETTING: &str = "vapid_private_key";
+const VAPID_PUBLIC_KEY_SETTING: &str = "vapid_public_key";
+
+/// A stored blocklist source including its raw contents (for uploads).
+pub struct BlocklistSourceRow {
+    pub id: i64,
+    pub name: String,
+    pub path: Option<String>,
+    pub data: Option<Vec<u8>>,
+}
+
 #[derive(Clone)]
 pub struct Db {
     pool: Pool<Sqlite>,
@@ -28,19 +39,26 @@ impl Db {
     }
 
     async fn run_migrations(&self) -> Result<()> {
-        sqlx::migrate!("./migrations").run(&self.pool).await?;
+        // Rebuilding a table that others reference must not cascade, and
+        // SQLite ignores this pragma inside the migration's transaction
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await?;
+        let result = sqlx::migrate!("./migrations").run(&mut *conn).await;
+        sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await?;
+        result?;
         Ok(())
     }
 
     // --- User Operations ---
 
-    pub async fn create_user(&self, username: &str, password_hash: &str) -> Result<()> {
-        sqlx::query("INSERT INTO users (username, password_hash) VALUES (?, ?)")
+    pub async fn create_user(&self, username: &str, password_hash: &str, role: crate::UserRole) -> Result<i64> {
+        let result = sqlx::query("INSERT INTO users (username, password_hash, role) VALUES (?, ?, ?)")
             .bind(username)
             .bind(password_hash)
+            .bind(role.as_str())
             .execute(&self.pool)
             .await?;
-        Ok(())
+        Ok(result.last_insert_rowid())
     }
 
     pub async fn get_user_by_username(&self, username: &str) -> Result<Option<(i64, String)>> {
@@ -61,6 +79,57 @@ impl Db {
         Ok(row.map(|r| r.get(0)))
     }
 
+    /// Role of an enabled user; `None` if the account is gone or disabled.
+    pub async fn get_active_user_role(&self, id: i64) -> Result<Option<crate::UserRole>> {
+        let row = sqlx::query_as::<_, (String,)>("SELECT role FROM users WHERE id = ? AND disabled = 0")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.and_then(|(role,)| crate::UserRole::parse(&role)))
+    }
+
+    pub async fn list_users(&self) -> Result<Vec<crate::User>> {
+        let rows = sqlx::query_as::<_, (i64, String, String, bool, String)>(
+            "SELECT id, username, role, disabled, created_at FROM users ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, username, role, disabled, created_at)| {
+                Some(crate::User { id, username, role: crate::UserRole::parse(&role)?, disabled, created_at })
+            })
+            .collect())
+    }
+
+    pub async fn set_user_role(&self, id: i64, role: crate::UserRole) -> Result<bool> {
+        let result = sqlx::query("UPDATE users SET role = ? WHERE id = ?")
+            .bind(role.as_str())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn set_user_disabled(&self, id: i64, disabled: bool) -> Result<bool> {
+        let result = sqlx::query("UPDATE users SET disabled = ? WHERE id = ?")
+            .bind(disabled)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes a user; sessions, tokens and push devices cascade.
+    pub async fn delete_user(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM users WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     pub async fn has_users(&self) -> Result<bool> {
         let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
             .fetch_one(&self.pool)
@@ -70,28 +139,84 @@ impl Db {
 
     // --- Session Operations ---
 
-    pub async fn create_session(&self, user_id: i64, token: &str, expires_at: i64) -> Result<()> {
-        sqlx::query("INSERT INTO sessions (token, user_id, expires_at) VALUES (?, ?, datetime(?, 'unixepoch'))")
-            .bind(token)
-            .bind(user_id)
-            .bind(expires_at)
+    pub async fn create_session(
+        &self,
+        user_id: i64,
+        token_hash: &str,
+        remember: bool,
+        ip: Option<&str>,
+        user_agent: Option<&str>,
+        now: i64,
+    ) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO sessions (user_id, token_hash, remember, ip, user_agent, created_at, last_seen_at, expires_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(remember)
+        .bind(ip)
+        .bind(user_agent)
+        .bind(now)
+        .bind(now)
+        .bind(now + crate::session::timeout(remember))
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Looks up an unexpired session by token hash, returning
+    /// `(id, user_id, remember, last_seen_at)`.
+    pub async fn get_session(&self, token_hash: &str, now: i64) -> Result<Option<(i64, i64, bool, i64)>> {
+        let row = sqlx::query_as::<_, (i64, i64, bool, i64)>(
+            "SELECT id, user_id, remember, last_seen_at FROM sessions WHERE token_hash = ? AND expires_at > ?"
+        )
+        .bind(token_hash)
+        .bind(now)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row)
+    }
+
+    /// Records a use of the session and moves its expiry forward.
+    pub async fn touch_session(&self, id: i64, remember: bool, now: i64) -> Result<()> {
+        sqlx::query("UPDATE sessions SET last_seen_at = ?, expires_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(now + crate::session::timeout(remember))
+            .bind(id)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
 
-    pub async fn get_session_user(&self, token: &str) -> Result<Option<i64>> {
-        let row = sqlx::query("SELECT user_id FROM sessions WHERE token = ? AND expires_at > datetime('now')")
-            .bind(token)
-            .fetch_optional(&self.pool)
-            .await?;
+    pub async fn list_sessions(&self, user_id: i64, now: i64) -> Result<Vec<crate::LoginSession>> {
+        let rows = sqlx::query_as::<_, (i64, Option<String>, Option<String>, bool, i64, i64, i64)>(
+            "SELECT id, ip, user_agent, remember, created_at, last_seen_at, expires_at
+             FROM sessions WHERE user_id = ? AND expires_at > ? ORDER BY last_seen_at DESC, id DESC"
+        )
+        .bind(user_id)
+        .bind(now)
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(row.map(|r| r.get(0)))
+        Ok(rows
+            .into_iter()
+            .map(|(id, ip, user_agent, remember, created_at, last_seen_at, expires_at)| crate::LoginSession {
+                id,
+                ip,
+                user_agent,
+                remember,
+                created_at,
+                last_seen_at,
+                expires_at,
+                current: false,
+            })
+            .collect())
     }
 
-    pub async fn delete_session(&self, token: &str) -> Result<()> {
-        sqlx::query("DELETE FROM sessions WHERE token = ?")
-            .bind(token)
+    pub async fn delete_session(&self, token_hash: &str) -> Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = ?")
+            .bind(token_hash)
             .execute(&self.pool)
             .await?;
         Ok(())
@@ -106,6 +231,26 @@ impl Db {
         Ok(())
     }
 
+    /// Deletes a session owned by `user_id`; returns false if there was none.
+    pub async fn revoke_session(&self, id: i64, user_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM sessions WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes every session of `user_id` except `keep_id`.
+    pub async fn revoke_other_sessions(&self, user_id: i64, keep_id: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM sessions WHERE user_id = ? AND id != ?")
+            .bind(user_id)
+            .bind(keep_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
     pub async fn delete_all_sessions_for_user(&self, user_id: i64) -> Result<()> {
         sqlx::query("DELETE FROM sessions WHERE user_id = ?")
             .bind(user_id)
@@ -114,16 +259,35 @@ impl Db {
         Ok(())
     }
 
+    pub async fn delete_expired_sessions(&self, now: i64) -> Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE expires_at <= ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     // --- Push Subscription Operations ---
 
-    pub async fn save_push_subscription(&self, endpoint: &str, p256dh: &str, auth: &str) -> Result<()> {
+    pub async fn save_push_subscription(
+        &self,
+        user_id: i64,
+        endpoint: &str,
+        p256dh: &str,
+        auth: &str,
+        user_agent: Option<&str>,
+    ) -> Result<()> {
         sqlx::query(
-            "INSERT INTO push_subscriptions (endpoint, p256dh, auth) VALUES (?, ?, ?)
-             ON CONFLICT(endpoint) DO UPDATE SET p256dh = EXCLUDED.p256dh, auth = EXCLUDED.auth"
+            "INSERT INTO push_subscriptions (user_id, endpoint, p256dh, auth, user_agent) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(endpoint) DO UPDATE SET
+                user_id = EXCLUDED.user_id, p256dh = EXCLUDED.p256dh,
+                auth = EXCLUDED.auth, user_agent = EXCLUDED.user_agent"
         )
+        .bind(user_id)
         .bind(endpoint)
         .bind(p256dh)
         .bind(auth)
+        .bind(user_agent)
         .execute(&self.pool)
         .await?;
         Ok(())
@@ -137,12 +301,677 @@ impl Db {
         Ok(())
     }
 
-    pub async fn get_all_push_subscriptions(&self) -> Result<Vec<(String, String, String)>> {
-        let rows = sqlx::query_as::<_, (String, String, String)>(
-            "SELECT endpoint, p256dh, auth FROM push_subscriptions"
+    /// Removes `endpoint` only if it belongs to `user_id`.
+    pub async fn remove_user_push_subscription(&self, endpoint: &str, user_id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM push_subscriptions WHERE endpoint = ? AND user_id = ?")
+            .bind(endpoint)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Every owned subscription as `(user_id, endpoint, p256dh, auth)`.
+    pub async fn get_all_push_subscriptions(&self) -> Result<Vec<(i64, String, String, String)>> {
+        let rows = sqlx::query_as::<_, (i64, String, String, String)>(
+            "SELECT user_id, endpoint, p256dh, auth FROM push_subscriptions WHERE user_id IS NOT NULL"
         )
         .fetch_all(&self.pool)
         .await?;
         Ok(rows)
     }
+
+    pub async fn list_push_devices(&self, user_id: i64) -> Result<Vec<crate::PushDevice>> {
+        let rows = sqlx::query_as::<_, (i64, Option<String>, String)>(
+            "SELECT id, user_agent, created_at FROM push_subscriptions WHERE user_id = ? ORDER BY id DESC"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, user_agent, created_at)| crate::PushDevice { id, user_agent, created_at })
+            .collect())
+    }
+
+    /// Deletes a device owned by `user_id`; returns false if there was none.
+    pub async fn revoke_push_device(&self, id: i64, user_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM push_subscriptions WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Stored preferences, or the defaults for users who never saved any.
+    pub async fn get_push_preferences(&self, user_id: i64) -> Result<crate::PushPreferences> {
+        let row = sqlx::query_as::<_, (String, Option<i64>, Option<i64>, i64)>(
+            "SELECT events, quiet_start, quiet_end, utc_offset_minutes FROM push_preferences WHERE user_id = ?"
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match row {
+            Some((events, quiet_start, quiet_end, utc_offset_minutes)) => crate::PushPreferences {
+                events: events.split(',').filter_map(crate::LifecycleKind::parse).collect(),
+                quiet_hours: quiet_start.zip(quiet_end).map(|(start, end)| crate::QuietHours {
+                    start_minute: start as u16,
+                    end_minute: end as u16,
+                    utc_offset_minutes: utc_offset_minutes as i32,
+                }),
+            },
+            None => crate::PushPreferences::default(),
+        })
+    }
+
+    pub async fn set_push_preferences(&self, user_id: i64, preferences: &crate::PushPreferences) -> Result<()> {
+        let events = preferences.events.iter().map(|e| e.as_str()).collect::<Vec<_>>().join(",");
+        let quiet = preferences.quiet_hours;
+        sqlx::query(
+            "INSERT INTO push_preferences (user_id, events, quiet_start, quiet_end, utc_offset_minutes)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(user_id) DO UPDATE SET
+                events = EXCLUDED.events, quiet_start = EXCLUDED.quiet_start,
+                quiet_end = EXCLUDED.quiet_end, utc_offset_minutes = EXCLUDED.utc_offset_minutes"
+        )
+        .bind(user_id)
+        .bind(events)
+        .bind(quiet.map(|q| q.start_minute as i64))
+        .bind(quiet.map(|q| q.end_minute as i64))
+        .bind(quiet.map_or(0, |q| q.utc_offset_minutes as i64))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// The stored VAPID keypair as `(private_key, public_key)`.
+    pub async fn get_vapid_keys(&self) -> Result<Option<(String, String)>> {
+        let private_key = self.get_setting(VAPID_PRIVATE_KEY_SETTING).await?;
+        let public_key = self.get_setting(VAPID_PUBLIC_KEY_SETTING).await?;
+        Ok(private_key.zip(public_key))
+    }
+
+    /// Stores a VAPID keypair and deletes every push subscription, since
+    /// those are bound to the key they were created with.
+    pub async fn replace_vapid_keys(&self, private_key: &str, public_key: &str) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        for (key, value) in [(VAPID_PRIVATE_KEY_SETTING, private_key), (VAPID_PUBLIC_KEY_SETTING, public_key)] {
+            sqlx::query(
+                "INSERT INTO app_settings (key, value) VALUES (?, ?)
+                 ON CONFLICT(key) DO UPDATE SET value = EXCLUDED.value"
+            )
+            .bind(key)
+            .bind(value)
+            .execute(&mut *tx)
+            .await?;
+        }
+        let removed = sqlx::query("DELETE FROM push_subscriptions")
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    // --- API Token Operations ---
+
+    pub async fn create_api_token(
+        &self,
+        user_id: i64,
+        name: &str,
+        token_hash: &str,
+        scope: crate::ApiTokenScope,
+        created_at: i64,
+        expires_at: Option<i64>,
+    ) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO api_tokens (user_id, name, token_hash, scope, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(name)
+        .bind(token_hash)
+        .bind(scope.as_str())
+        .bind(created_at)
+        .bind(expires_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn list_api_tokens(&self, user_id: i64) -> Result<Vec<crate::ApiToken>> {
+        let rows = sqlx::query_as::<_, (i64, String, String, i64, Option<i64>, Option<i64>)>(
+            "SELECT id, name, scope, created_at, expires_at, last_used_at
+             FROM api_tokens WHERE user_id = ? ORDER BY created_at DESC, id DESC"
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, name, scope, created_at, expires_at, last_used_at)| {
+                Some(crate::ApiToken {
+                    id,
+                    name,
+                    scope: crate::ApiTokenScope::parse(&scope)?,
+                    created_at,
+                    expires_at,
+                    last_used_at,
+                })
+            })
+            .collect())
+    }
+
+    /// Deletes a token owned by `user_id`; returns false if there was none.
+    pub async fn revoke_api_token(&self, id: i64, user_id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM api_tokens WHERE id = ? AND user_id = ?")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Looks up a token by hash, returning `(id, user_id, scope, expires_at)`.
+    pub async fn get_api_token_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<(i64, i64, crate::ApiTokenScope, Option<i64>)>> {
+        let row = sqlx::query_as::<_, (i64, i64, String, Option<i64>)>(
+            "SELECT id, user_id, scope, expires_at FROM api_tokens WHERE token_hash = ?"
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.and_then(|(id, user_id, scope, expires_at)| {
+            Some((id, user_id, crate::ApiTokenScope::parse(&scope)?, expires_at))
+        }))
+    }
+
+    /// Records a use of the token. Only writes when the stored value is a
+    /// minute old, so busy scripts do not turn every request into a write.
+    pub async fn touch_api_token(&self, id: i64, now: i64) -> Result<()> {
+        sqlx::query(
+            "UPDATE api_tokens SET last_used_at = ?
+             WHERE id = ? AND (last_used_at IS NULL OR last_used_at < ? - 60)"
+        )
+        .bind(now)
+        .bind(id)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // --- Automation Operations ---
+
+    pub async fn create_automation(
+        &self,
+        name: &str,
+        triggers: &[crate::AutomationTrigger],
+        action: &crate::AutomationAction,
+        created_at: i64,
+    ) -> Result<i64> {
+        let triggers = triggers.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(",");
+        let (kind, url, method, body_template, secret, command) = match action {
+            crate::AutomationAction::Webhook { url, method, body_template, secret } => {
+                ("webhook", Some(url), Some(method), Some(body_template), secret.as_ref(), None)
+            }
+            crate::AutomationAction::Script { command } => ("script", None, None, None, None, Some(command)),
+        };
+        let result = sqlx::query(
+            "INSERT INTO automations (name, triggers, kind, url, method, body_template, secret, command, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(name)
+        .bind(triggers)
+        .bind(kind)
+        .bind(url)
+        .bind(method)
+        .bind(body_template)
+        .bind(secret)
+        .bind(command)
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// All automations, secrets included; callers facing users must strip them.
+    pub async fn list_automations(&self) -> Result<Vec<crate::Automation>> {
+        #[allow(clippy::type_complexity)]
+        let rows = sqlx::query_as::<_, (
+            i64, String, String, String, Option<String>, Option<String>,
+            Option<String>, Option<String>, Option<String>, bool, i64,
+        )>(
+            "SELECT id, name, triggers, kind, url, method, body_template, secret, command, enabled, created_at
+             FROM automations ORDER BY created_at DESC, id DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, name, triggers, kind, url, method, body_template, secret, command, enabled, created_at)| {
+                let action = match kind.as_str() {
+                    "webhook" => crate::AutomationAction::Webhook {
+                        url: url?,
+                        method: method.unwrap_or_else(|| "POST".to_string()),
+                        body_template: body_template.unwrap_or_default(),
+                        secret,
+                    },
+                    "script" => crate::AutomationAction::Script { command: command? },
+                    _ => return None,
+                };
+                Some(crate::Automation {
+                    id,
+                    name,
+                    triggers: triggers.split(',').filter_map(crate::AutomationTrigger::parse).collect(),
+                    action,
+                    enabled,
+                    created_at,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn set_automation_enabled(&self, id: i64, enabled: bool) -> Result<bool> {
+        let result = sqlx::query("UPDATE automations SET enabled = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Deletes an automation and its delivery log; returns false if there was none.
+    pub async fn delete_automation(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM automations WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Logs a finished delivery, keeping only the latest 500 entries.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn add_automation_delivery(
+        &self,
+        automation_id: i64,
+        trigger: crate::AutomationTrigger,
+        torrent_name: &str,
+        attempts: i64,
+        success: bool,
+        detail: &str,
+        created_at: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO automation_deliveries (automation_id, trigger, torrent_name, attempts, success, detail, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(automation_id)
+        .bind(trigger.as_str())
+        .bind(torrent_name)
+        .bind(attempts)
+        .bind(success)
+        .bind(detail)
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM automation_deliveries WHERE id NOT IN
+             (SELECT id FROM automation_deliveries ORDER BY id DESC LIMIT 500)"
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list_automation_deliveries(&self, limit: i64) -> Result<Vec<crate::AutomationDelivery>> {
+        let rows = sqlx::query_as::<_, (i64, i64, String, String, String, i64, bool, String, i64)>(
+            "SELECT d.id, d.automation_id, a.name, d.trigger, d.torrent_name, d.attempts, d.success, d.detail, d.created_at
+             FROM automation_deliveries d JOIN automations a ON a.id = d.automation_id
+             ORDER BY d.id DESC LIMIT ?"
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, automation_id, automation_name, trigger, torrent_name, attempts, success, detail, created_at)| {
+                Some(crate::AutomationDelivery {
+                    id,
+                    automation_id,
+                    automation_name,
+                    trigger: crate::AutomationTrigger::parse(&trigger)?,
+                    torrent_name,
+                    attempts,
+                    success,
+                    detail,
+                    created_at,
+                })
+            })
+            .collect())
+    }
+
+    // --- Notification Channel Operations ---
+
+    pub async fn create_notification_channel(
+        &self,
+        name: &str,
+        config: &crate::ChannelConfig,
+        events: &[crate::LifecycleKind],
+        created_at: i64,
+    ) -> Result<i64> {
+        let events = events.iter().map(|e| e.as_str()).collect::<Vec<_>>().join(",");
+        let result = sqlx::query(
+            "INSERT INTO notification_channels (name, config, events, created_at) VALUES (?, ?, ?, ?)"
+        )
+        .bind(name)
+        .bind(serde_json::to_string(config)?)
+        .bind(events)
+        .bind(created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// All channels with their credentials; callers facing users must redact them.
+    pub async fn list_notification_channels(&self) -> Result<Vec<crate::NotificationChannel>> {
+        let rows = sqlx::query_as::<_, (i64, String, String, String, bool, i64)>(
+            "SELECT id, name, config, events, enabled, created_at
+             FROM notification_channels ORDER BY created_at DESC, id DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .filter_map(|(id, name, config, events, enabled, created_at)| {
+                Some(crate::NotificationChannel {
+                    id,
+                    name,
+                    config: serde_json::from_str(&config).ok()?,
+                    events: events.split(',').filter_map(crate::LifecycleKind::parse).collect(),
+                    enabled,
+                    created_at,
+                })
+            })
+            .collect())
+    }
+
+    pub async fn set_notification_channel_enabled(&self, id: i64, enabled: bool) -> Result<bool> {
+        let result = sqlx::query("UPDATE notification_channels SET enabled = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete_notification_channel(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM notification_channels WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // --- Settings Operations ---
+
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT value FROM app_settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|r| r.get(0)))
+    }
+
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO app_settings (key, value) VALUES (?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = EXCLUDED.value"
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // --- Blocklist Operations ---
+
+    pub async fn add_blocklist_source(
+        &self,
+        name: &str,
+        path: Option<&str>,
+        data: Option<&[u8]>,
+        range_count: i64,
+    ) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO blocklist_sources (name, path, data, range_count) VALUES (?, ?, ?, ?)"
+        )
+        .bind(name)
+        .bind(path)
+        .bind(data)
+        .bind(range_count)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn remove_blocklist_source(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM blocklist_sources WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_blocklist_sources(&self) -> Result<Vec<crate::BlocklistSource>> {
+        let rows = sqlx::query_as::<_, (i64, String, Option<String>, i64, String)>(
+            "SELECT id, name, path, range_count, created_at FROM blocklist_sources ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, path, range_count, created_at)| crate::BlocklistSource {
+                id,
+                name,
+                path,
+                range_count,
+                created_at,
+            })
+            .collect())
+    }
+
+    pub async fn get_blocklist_sources_with_data(&self) -> Result<Vec<BlocklistSourceRow>> {
+        let rows = sqlx::query_as::<_, (i64, String, Option<String>, Option<Vec<u8>>)>(
+            "SELECT id, name, path, data FROM blocklist_sources ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, path, data)| BlocklistSourceRow { id, name, path, data })
+            .collect())
+    }
+
+    // --- Transfer History ---
+
+    pub async fn insert_transfer_sample(&self, sample: &crate::TransferSample) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO transfer_samples (ts, resolution, down_rate, up_rate, down_peak, up_peak, downloaded, uploaded)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(resolution, ts) DO UPDATE SET
+                down_rate = excluded.down_rate,
+                up_rate = excluded.up_rate,
+                down_peak = MAX(down_peak, excluded.down_peak),
+                up_peak = MAX(up_peak, excluded.up_peak),
+                downloaded = downloaded + excluded.downloaded,
+                uploaded = uploaded + excluded.uploaded"
+        )
+        .bind(sample.ts)
+        .bind(sample.resolution)
+        .bind(sample.down_rate)
+        .bind(sample.up_rate)
+        .bind(sample.down_peak)
+        .bind(sample.up_peak)
+        .bind(sample.downloaded)
+        .bind(sample.uploaded)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Folds rows of resolution `from` older than `before` into rows of
+    /// resolution `to`. `before` must be aligned to `to`.
+    pub async fn downsample_transfer_samples(&self, from: i64, to: i64, before: i64) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO transfer_samples (ts, resolution, down_rate, up_rate, down_peak, up_peak, downloaded, uploaded)
+             SELECT (ts / ?1) * ?1, ?1, CAST(AVG(down_rate) AS INTEGER), CAST(AVG(up_rate) AS INTEGER), MAX(down_peak), MAX(up_peak), SUM(downloaded), SUM(uploaded)
+             FROM transfer_samples
+             WHERE resolution = ?2 AND ts < ?3
+             GROUP BY ts / ?1
+             ON CONFLICT(resolution, ts) DO UPDATE SET
+                down_peak = MAX(down_peak, excluded.down_peak),
+                up_peak = MAX(up_peak, excluded.up_peak),
+                downloaded = downloaded + excluded.downloaded,
+                uploaded = uploaded + excluded.uploaded"
+        )
+        .bind(to)
+        .bind(from)
+        .bind(before)
+        .execute(&mut *tx)
+        .await?;
+        let deleted = sqlx::query("DELETE FROM transfer_samples WHERE resolution = ? AND ts < ?")
+            .bind(from)
+            .bind(before)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    pub async fn delete_transfer_samples_before(&self, resolution: i64, before: i64) -> Result<()> {
+        sqlx::query("DELETE FROM transfer_samples WHERE resolution = ? AND ts < ?")
+            .bind(resolution)
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// All samples since `since`, whatever their resolution, oldest first.
+    pub async fn get_transfer_samples(&self, since: i64) -> Result<Vec<crate::TransferSample>> {
+        let rows = sqlx::query_as::<_, (i64, i64, i64, i64, i64, i64, i64, i64)>(
+            "SELECT ts, resolution, down_rate, up_rate, down_peak, up_peak, downloaded, uploaded
+             FROM transfer_samples WHERE ts >= ? ORDER BY ts"
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(ts, resolution, down_rate, up_rate, down_peak, up_peak, downloaded, uploaded)| {
+                crate::TransferSample {
+                    ts,
+                    resolution,
+                    down_rate,
+                    up_rate,
+                    down_peak,
+                    up_peak,
+                    downloaded,
+                    uploaded,
+                }
+            })
+            .collect())
+    }
+
+    pub async fn add_torrent_transfer(
+        &self,
+        day: i64,
+        hash: &str,
+        name: &str,
+        downloaded: i64,
+        uploaded: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO torrent_transfer_daily (day, hash, name, downloaded, uploaded) VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(day, hash) DO UPDATE SET
+                name = excluded.name,
+                downloaded = downloaded + excluded.downloaded,
+                uploaded = uploaded + excluded.uploaded"
+        )
+        .bind(day)
+        .bind(hash)
+        .bind(name)
+        .bind(downloaded)
+        .bind(uploaded)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Per-torrent totals since `since_day`, busiest first.
+    pub async fn get_top_torrent_transfers(&self, since_day: i64, limit: i64) -> Result<Vec<crate::TorrentTransferTotal>> {
+        let rows = sqlx::query_as::<_, (String, String, i64, i64)>(
+            "SELECT hash, MAX(name), SUM(downloaded) AS down, SUM(uploaded) AS up
+             FROM torrent_transfer_daily WHERE day >= ?
+             GROUP BY hash ORDER BY down + up DESC LIMIT ?"
+        )
+        .bind(since_day)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(hash, name, downloaded, uploaded)| crate::TorrentTransferTotal { hash, name, downloaded, uploaded })
+            .collect())
+    }
+
+    pub async fn delete_torrent_transfers_before(&self, day: i64) -> Result<()> {
+        sqlx::query("DELETE FROM torrent_transfer_daily WHERE day < ?")
+            .bind(day)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AuthUser, UserRole};
+
+    #[tokio::test]
+    async fn test_deleted_user_ids_are_not_reused() {
+        let path = std::env::temp_dir().join(format!("vt-db-{}.db", std::process::id()));
+        let db = Db::new(&format!("sqlite:{}", path.display())).await.unwrap();
+
+        db.create_user("admin", "x", UserRole::Admin).await.unwrap();
+        let old = db.create_user("old", "x", UserRole::Operator).await.unwrap();
+        assert!(db.delete_user(old).await.unwrap());
+        let new = db.create_user("new", "x", UserRole::Operator).await.unwrap();
+        db.pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+
+        // Torrents stay tagged with the deleted account's id
+        assert_ne!(new, old);
+        assert!(!AuthUser { id: new, role: UserRole::Operator }.owns(Some(old)));
+    }
 }
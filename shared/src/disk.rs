@@ -0,0 +1,41 @@
+#![cfg(feature = "ssr")]
+
+//! Filesystem capacity queries (`statvfs`).
+
+use crate::DiskSpace;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Walks up from `path` to the topmost ancestor on the same device.
+pub fn mount_point(path: &Path) -> std::io::Result<PathBuf> {
+    let dev = std::fs::metadata(path)?.dev();
+    let mut mount = path.to_path_buf();
+    while let Some(parent) = mount.parent() {
+        match std::fs::metadata(parent) {
+            Ok(meta) if meta.dev() == dev => mount = parent.to_path_buf(),
+            _ => break,
+        }
+    }
+    Ok(mount)
+}
+
+/// Capacity of the filesystem holding `path`. `available_bytes` is what an
+/// unprivileged process (like rTorrent) can still write.
+pub fn disk_space(path: &Path) -> std::io::Result<DiskSpace> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is NUL-terminated and `stat` is a valid out pointer.
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let fragment = stat.f_frsize as u64;
+    Ok(DiskSpace {
+        mount_point: mount_point(path)?.to_string_lossy().into_owned(),
+        total_bytes: stat.f_blocks as u64 * fragment,
+        available_bytes: stat.f_bavail as u64 * fragment,
+    })
+}
@@ -0,0 +1,151 @@
+//! Builds a directory tree out of rTorrent's flat file list.
+
+use crate::FileTreeNode;
+
+impl FileTreeNode {
+    /// A file leaf as reported by `f.multicall`.
+    pub fn file(index: u32, path: &str, size: i64, completed_bytes: i64, priority: u8, preview: bool) -> Self {
+        Self {
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            path: path.to_string(),
+            index: Some(index),
+            size,
+            completed_bytes,
+            priority: Some(priority),
+            preview,
+            children: Vec::new(),
+        }
+    }
+
+    fn dir(name: &str, path: String) -> Self {
+        Self {
+            name: name.to_string(),
+            path,
+            index: None,
+            size: 0,
+            completed_bytes: 0,
+            priority: None,
+            preview: false,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.index.is_none()
+    }
+
+    pub fn progress(&self) -> f64 {
+        if self.size <= 0 {
+            return 0.0;
+        }
+        self.completed_bytes as f64 / self.size as f64 * 100.0
+    }
+
+    /// Indices of every file at or below this node.
+    pub fn file_indices(&self) -> Vec<u32> {
+        let mut indices = Vec::new();
+        self.collect_indices(&mut indices);
+        indices
+    }
+
+    fn collect_indices(&self, out: &mut Vec<u32>) {
+        match self.index {
+            Some(index) => out.push(index),
+            None => self.children.iter().for_each(|c| c.collect_indices(out)),
+        }
+    }
+
+    /// Looks up a node by its relative path (`""` is the node itself).
+    pub fn find(&self, path: &str) -> Option<&FileTreeNode> {
+        let path = path.trim_matches('/');
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.split('/').try_fold(self, |node, part| {
+            node.children.iter().find(|c| c.name == part)
+        })
+    }
+
+    /// Recomputes directory sizes, progress and priority bottom-up and sorts
+    /// children with directories first.
+    fn aggregate(&mut self) {
+        if !self.is_dir() {
+            return;
+        }
+        self.children.iter_mut().for_each(Self::aggregate);
+        self.children
+            .sort_by(|a, b| b.is_dir().cmp(&a.is_dir()).then_with(|| a.name.cmp(&b.name)));
+
+        self.size = self.children.iter().map(|c| c.size).sum();
+        self.completed_bytes = self.children.iter().map(|c| c.completed_bytes).sum();
+        let mut priorities = self.children.iter().map(|c| c.priority);
+        let first = priorities.next().flatten();
+        self.priority = if priorities.all(|p| p == first) { first } else { None };
+    }
+}
+
+/// Nests file leaves (whose `path` is relative to the torrent root) under a
+/// root directory named `root_name`.
+pub fn build(root_name: &str, files: Vec<FileTreeNode>) -> FileTreeNode {
+    let mut root = FileTreeNode::dir(root_name, String::new());
+
+    for file in files {
+        let mut node = &mut root;
+        let mut parts: Vec<&str> = file.path.split('/').filter(|p| !p.is_empty()).collect();
+        parts.pop();
+
+        for part in parts {
+            let pos = match node.children.iter().position(|c| c.is_dir() && c.name == part) {
+                Some(pos) => pos,
+                None => {
+                    let path = if node.path.is_empty() {
+                        part.to_string()
+                    } else {
+                        format!("{}/{}", node.path, part)
+                    };
+                    node.children.push(FileTreeNode::dir(part, path));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[pos];
+        }
+        node.children.push(file);
+    }
+
+    root.aggregate();
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_aggregates_directories() {
+        let tree = build(
+            "Show",
+            vec![
+                FileTreeNode::file(0, "S01/e01.mkv", 100, 100, 1, false),
+                FileTreeNode::file(1, "S01/e02.mkv", 100, 50, 1, false),
+                FileTreeNode::file(2, "S02/e01.mkv", 200, 0, 0, false),
+                FileTreeNode::file(3, "info.nfo", 10, 10, 2, false),
+            ],
+        );
+
+        assert_eq!(tree.size, 410);
+        assert_eq!(tree.completed_bytes, 160);
+        assert_eq!(tree.priority, None);
+        assert_eq!(
+            tree.children.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+            vec!["S01", "S02", "info.nfo"]
+        );
+
+        let season = tree.find("S01").unwrap();
+        assert_eq!(season.path, "S01");
+        assert_eq!(season.priority, Some(1));
+        assert_eq!(season.progress(), 75.0);
+        assert_eq!(season.file_indices(), vec![0, 1]);
+        assert_eq!(tree.find("S02/e01.mkv").unwrap().index, Some(2));
+        assert!(tree.find("S03").is_none());
+    }
+}
@@ -0,0 +1,203 @@
+//! Bucketing and calendar helpers for the transfer history.
+//!
+//! Samples are stored at minute resolution and folded into hourly and daily
+//! rows as they age, so queries may see a mix of resolutions.
+
+use crate::{TransferPeriod, TransferSample, TransferTotal};
+
+pub const MINUTE: i64 = 60;
+pub const HOUR: i64 = 3600;
+pub const DAY: i64 = 86400;
+
+/// Minute samples older than this are folded into hourly rows.
+pub const MINUTE_RETENTION: i64 = 2 * DAY;
+/// Hourly rows older than this are folded into daily rows.
+pub const HOUR_RETENTION: i64 = 90 * DAY;
+/// Daily rows and per-torrent totals older than this are deleted.
+pub const DAY_RETENTION: i64 = 5 * 365 * DAY;
+pub const TORRENT_RETENTION: i64 = 365 * DAY;
+
+/// Maximum number of points returned for a speed graph.
+const MAX_POINTS: i64 = 360;
+const STEPS: [i64; 9] = [MINUTE, 5 * MINUTE, 15 * MINUTE, 30 * MINUTE, HOUR, 3 * HOUR, 6 * HOUR, 12 * HOUR, DAY];
+
+/// Smallest bucket length that keeps a graph over `range` seconds under
+/// `MAX_POINTS` points.
+pub fn step_for_range(range: i64) -> i64 {
+    STEPS
+        .iter()
+        .copied()
+        .find(|step| range / step <= MAX_POINTS)
+        .unwrap_or(DAY)
+}
+
+/// Regroups samples into `step`-sized buckets. Rates are averaged over the
+/// time each sample covers, peaks and byte counts are combined.
+pub fn rebucket(samples: &[TransferSample], step: i64) -> Vec<TransferSample> {
+    let mut buckets: Vec<TransferSample> = Vec::new();
+    let mut covered = 0;
+
+    for sample in samples {
+        let ts = sample.ts.div_euclid(step) * step;
+        let weight = sample.resolution.max(1);
+        match buckets.last_mut() {
+            Some(bucket) if bucket.ts == ts => {
+                bucket.down_rate = (bucket.down_rate * covered + sample.down_rate * weight) / (covered + weight);
+                bucket.up_rate = (bucket.up_rate * covered + sample.up_rate * weight) / (covered + weight);
+                bucket.down_peak = bucket.down_peak.max(sample.down_peak);
+                bucket.up_peak = bucket.up_peak.max(sample.up_peak);
+                bucket.downloaded += sample.downloaded;
+                bucket.uploaded += sample.uploaded;
+                covered += weight;
+            }
+            _ => {
+                buckets.push(TransferSample { ts, resolution: step, ..sample.clone() });
+                covered = weight;
+            }
+        }
+    }
+    buckets
+}
+
+/// Days since 1970-01-01 to `(year, month, day)`.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// `(year, month, day)` to days since 1970-01-01.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Start of the period containing `ts`. Days and weeks (starting Monday)
+/// follow the caller's `utc_offset`.
+pub fn period_start(ts: i64, period: TransferPeriod, utc_offset: i64) -> i64 {
+    let days = (ts + utc_offset).div_euclid(DAY);
+    let start_day = match period {
+        TransferPeriod::Day => days,
+        // 1970-01-01 was a Thursday
+        TransferPeriod::Week => days - (days + 3).rem_euclid(7),
+        TransferPeriod::Month => {
+            let (year, month, _) = civil_from_days(days);
+            days_from_civil(year, month, 1)
+        }
+    };
+    start_day * DAY - utc_offset
+}
+
+fn previous_period_start(start: i64, period: TransferPeriod, utc_offset: i64) -> i64 {
+    match period {
+        TransferPeriod::Day => start - DAY,
+        TransferPeriod::Week => start - 7 * DAY,
+        TransferPeriod::Month => period_start(start - DAY, period, utc_offset),
+    }
+}
+
+/// Start of the oldest of the last `count` periods, the current one included.
+pub fn oldest_period_start(now: i64, period: TransferPeriod, count: u32, utc_offset: i64) -> i64 {
+    let mut start = period_start(now, period, utc_offset);
+    for _ in 1..count {
+        start = previous_period_start(start, period, utc_offset);
+    }
+    start
+}
+
+/// Byte totals for the last `count` periods, oldest first. Periods without
+/// samples are included with zero totals.
+pub fn totals_by_period(
+    samples: &[TransferSample],
+    period: TransferPeriod,
+    count: u32,
+    now: i64,
+    utc_offset: i64,
+) -> Vec<TransferTotal> {
+    let mut starts = vec![period_start(now, period, utc_offset)];
+    for _ in 1..count {
+        let previous = previous_period_start(*starts.last().unwrap(), period, utc_offset);
+        starts.push(previous);
+    }
+    starts.reverse();
+
+    let mut totals: Vec<TransferTotal> = starts
+        .into_iter()
+        .map(|start| TransferTotal { start, downloaded: 0, uploaded: 0 })
+        .collect();
+    for sample in samples {
+        let start = period_start(sample.ts, period, utc_offset);
+        if let Ok(i) = totals.binary_search_by_key(&start, |t| t.start) {
+            totals[i].downloaded += sample.downloaded;
+            totals[i].uploaded += sample.uploaded;
+        }
+    }
+    totals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(ts: i64, resolution: i64, down_rate: i64, downloaded: i64) -> TransferSample {
+        TransferSample {
+            ts,
+            resolution,
+            down_rate,
+            down_peak: down_rate,
+            downloaded,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_rebucket_weights_by_resolution() {
+        let samples = [sample(0, 60, 100, 6000), sample(60, 60, 300, 18000), sample(300, 60, 50, 3000)];
+        let buckets = rebucket(&samples, 300);
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].down_rate, 200);
+        assert_eq!(buckets[0].down_peak, 300);
+        assert_eq!(buckets[0].downloaded, 24000);
+        assert_eq!(buckets[1].ts, 300);
+    }
+
+    #[test]
+    fn test_calendar() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+        // 2026-10-18 is a Sunday
+        let ts = days_from_civil(2026, 10, 18) * DAY + 12 * HOUR;
+        assert_eq!(period_start(ts, TransferPeriod::Week, 0), days_from_civil(2026, 10, 12) * DAY);
+        assert_eq!(period_start(ts, TransferPeriod::Month, 0), days_from_civil(2026, 10, 1) * DAY);
+        // UTC+3: 22:00 UTC is already the next day
+        let late = days_from_civil(2026, 10, 18) * DAY + 22 * HOUR;
+        assert_eq!(period_start(late, TransferPeriod::Day, 3 * HOUR), days_from_civil(2026, 10, 19) * DAY - 3 * HOUR);
+    }
+
+    #[test]
+    fn test_totals_by_period() {
+        let now = days_from_civil(2026, 3, 10) * DAY;
+        let samples = [
+            sample(days_from_civil(2026, 1, 20) * DAY, DAY, 0, 5),
+            sample(days_from_civil(2026, 2, 3) * DAY, DAY, 0, 7),
+            sample(now, 60, 0, 1),
+        ];
+        let totals = totals_by_period(&samples, TransferPeriod::Month, 2, now, 0);
+        assert_eq!(totals.len(), 2);
+        assert_eq!(totals[0].start, days_from_civil(2026, 2, 1) * DAY);
+        assert_eq!(totals[0].downloaded, 7);
+        assert_eq!(totals[1].downloaded, 1);
+    }
+}
@@ -11,6 +11,31 @@ pub mod xmlrpc;
 #[cfg(feature = "ssr")]
 pub mod db;
 
+#[cfg(feature = "ssr")]
+pub mod blocklist;
+
+#[cfg(feature = "ssr")]
+pub mod paths;
+
+#[cfg(feature = "ssr")]
+pub mod disk;
+
+#[cfg(feature = "ssr")]
+pub mod api_token;
+
+#[cfg(feature = "ssr")]
+pub mod roles;
+
+#[cfg(feature = "ssr")]
+pub mod session;
+
+#[cfg(feature = "ssr")]
+pub mod notifier;
+
+pub mod file_tree;
+
+pub mod history;
+
 pub mod codec;
 
 pub mod server_fns;
@@ -18,6 +43,8 @@ pub mod server_fns;
 #[derive(Clone, Debug)]
 pub struct ServerContext {
     pub scgi_socket_path: String,
+    /// Extra directories the file browser may expose besides `directory.default`
+    pub browse_roots: Vec<String>,
 }
 
 #[cfg(feature = "ssr")]
@@ -26,6 +53,30 @@ pub struct DbContext {
     pub db: db::Db,
 }
 
+/// The caller of a request, attached by the auth middleware.
+#[cfg(feature = "ssr")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuthUser {
+    pub id: i64,
+    pub role: UserRole,
+}
+
+#[cfg(feature = "ssr")]
+impl AuthUser {
+    /// Admins see every torrent, others only the ones they added.
+    pub fn sees_all(&self) -> bool {
+        self.role == UserRole::Admin
+    }
+
+    pub fn owns(&self, owner: Option<i64>) -> bool {
+        self.sees_all() || owner == Some(self.id)
+    }
+
+    pub fn sees(&self, torrent: &Torrent) -> bool {
+        self.owns(torrent.owner)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema, Patch)]
 #[patch_derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema, Default)]
 #[patch_name = "TorrentUpdate"]
@@ -42,6 +93,8 @@ pub struct Torrent {
     pub error_message: String,
     pub added_date: i64,
     pub label: Option<String>,
+    /// User who added the torrent; `None` for ones added outside VibeTorrent
+    pub owner: Option<i64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
@@ -58,8 +111,65 @@ pub enum TorrentStatus {
 pub enum AppEvent {
     FullList(Vec<Torrent>, u64),
     Update(TorrentUpdate),
+    Added(Torrent),
+    /// Hash of the torrent that is gone, and the user who owned it
+    Removed(String, Option<i64>),
     Stats(GlobalStats),
     Notification(SystemNotification),
+    /// The event, and the owner of the torrent it is about
+    Lifecycle(LifecycleEvent, Option<i64>),
+}
+
+/// What a WebSocket client can subscribe to on `/api/ws`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub enum WsTopic {
+    /// `AppEvent::Stats`
+    Stats,
+    /// `AppEvent::Notification` and `AppEvent::Lifecycle`
+    Notifications,
+    /// List and updates for every torrent
+    Torrents,
+    /// List and updates for torrents carrying this label
+    Label(String),
+    /// Updates for one torrent plus its files, peers and trackers
+    Torrent(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum WsCommand {
+    Action { hash: String, action: String },
+    SetLabel { hash: String, label: String },
+    AddTorrent { uri: String, save_path: Option<String> },
+    SetGlobalLimits { max_download_rate: Option<i64>, max_upload_rate: Option<i64> },
+}
+
+/// Binary msgpack frames a WebSocket client sends.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum WsClientMessage {
+    Subscribe(Vec<WsTopic>),
+    Unsubscribe(Vec<WsTopic>),
+    /// Resend the current state for every subscription
+    Refresh,
+    /// `id` is echoed back in the matching `CommandResult`
+    Command { id: u64, command: WsCommand },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TorrentDetails {
+    pub hash: String,
+    pub files: Vec<TorrentFile>,
+    pub peers: Vec<TorrentPeer>,
+    pub trackers: Vec<TorrentTracker>,
+}
+
+/// Binary msgpack frames the server sends on `/api/ws`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum WsServerMessage {
+    Event(AppEvent),
+    Details(TorrentDetails),
+    CommandResult { id: u64, error: Option<String> },
+    /// A frame the server could not decode
+    Error(String),
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq, Eq)]
@@ -76,13 +186,142 @@ pub enum NotificationLevel {
     Error,
 }
 
+/// Something that happened to a torrent or the server. Each channel (toast,
+/// browser notification, push) picks what it shows by type, never by text.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq, Eq)]
+pub enum LifecycleEvent {
+    TorrentCompleted { hash: String, name: String },
+    TorrentErrored { hash: String, name: String, message: String },
+    TorrentAdded { hash: String, name: String },
+    TorrentRemoved { hash: String, name: String },
+    TrackerError { hash: String, name: String, message: String },
+    /// The disk guard paused `paused` torrents on `mount_point`
+    DiskLow { mount_point: String, available_bytes: u64, paused: usize },
+}
+
+impl LifecycleEvent {
+    pub fn level(&self) -> NotificationLevel {
+        match self {
+            Self::TorrentCompleted { .. } => NotificationLevel::Success,
+            Self::TorrentErrored { .. } => NotificationLevel::Error,
+            Self::TorrentAdded { .. } | Self::TorrentRemoved { .. } => NotificationLevel::Info,
+            Self::TrackerError { .. } | Self::DiskLow { .. } => NotificationLevel::Warning,
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::TorrentCompleted { .. } => "Torrent Tamamlandı",
+            Self::TorrentErrored { .. } => "Torrent Hatası",
+            Self::TorrentAdded { .. } => "Torrent Eklendi",
+            Self::TorrentRemoved { .. } => "Torrent Kaldırıldı",
+            Self::TrackerError { .. } => "İzleyici Hatası",
+            Self::DiskLow { .. } => "Disk Alanı Azaldı",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            Self::TorrentCompleted { name, .. } => format!("Torrent tamamlandı: {}", name),
+            Self::TorrentAdded { name, .. } => format!("Torrent eklendi: {}", name),
+            Self::TorrentRemoved { name, .. } => format!("Torrent kaldırıldı: {}", name),
+            Self::TorrentErrored { name, message, .. } | Self::TrackerError { name, message, .. } => {
+                format!("{}: {}", name, message)
+            }
+            Self::DiskLow { mount_point, available_bytes, paused } => format!(
+                "Disk alanı azaldı: {} ({:.1} GB boş). {} torrent duraklatıldı.",
+                mount_point,
+                *available_bytes as f64 / (1u64 << 30) as f64,
+                paused
+            ),
+        }
+    }
+
+    /// Added and removed torrents already show up in the list itself.
+    pub fn shows_toast(&self) -> bool {
+        !matches!(self, Self::TorrentAdded { .. } | Self::TorrentRemoved { .. })
+    }
+
+    /// Worth a browser or push notification when the app is not in view.
+    pub fn is_alert(&self) -> bool {
+        matches!(
+            self,
+            Self::TorrentCompleted { .. } | Self::TorrentErrored { .. } | Self::DiskLow { .. }
+        )
+    }
+
+    /// The torrent the event is about, if any.
+    pub fn hash(&self) -> Option<&str> {
+        match self {
+            Self::TorrentCompleted { hash, .. }
+            | Self::TorrentErrored { hash, .. }
+            | Self::TorrentAdded { hash, .. }
+            | Self::TorrentRemoved { hash, .. }
+            | Self::TrackerError { hash, .. } => Some(hash),
+            Self::DiskLow { .. } => None,
+        }
+    }
+
+    pub fn kind(&self) -> LifecycleKind {
+        match self {
+            Self::TorrentCompleted { .. } => LifecycleKind::Completed,
+            Self::TorrentErrored { .. } => LifecycleKind::Errored,
+            Self::TorrentAdded { .. } => LifecycleKind::Added,
+            Self::TorrentRemoved { .. } => LifecycleKind::Removed,
+            Self::TrackerError { .. } => LifecycleKind::TrackerError,
+            Self::DiskLow { .. } => LifecycleKind::DiskLow,
+        }
+    }
+}
+
+/// `LifecycleEvent` without its payload, for per-channel event filters.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, PartialEq, Eq)]
+pub enum LifecycleKind {
+    Completed,
+    Errored,
+    Added,
+    Removed,
+    TrackerError,
+    DiskLow,
+}
+
+impl LifecycleKind {
+    pub const ALL: [LifecycleKind; 6] = [
+        LifecycleKind::Completed,
+        LifecycleKind::Errored,
+        LifecycleKind::Added,
+        LifecycleKind::Removed,
+        LifecycleKind::TrackerError,
+        LifecycleKind::DiskLow,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecycleKind::Completed => "completed",
+            LifecycleKind::Errored => "errored",
+            LifecycleKind::Added => "added",
+            LifecycleKind::Removed => "removed",
+            LifecycleKind::TrackerError => "tracker_error",
+            LifecycleKind::DiskLow => "disk_low",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == s)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema, Default)]
 pub struct GlobalStats {
     pub down_rate: i64,
     pub up_rate: i64,
     pub down_limit: Option<i64>,
     pub up_limit: Option<i64>,
+    /// Available bytes on the filesystem holding `directory.default`
     pub free_space: Option<i64>,
+    /// Every filesystem holding torrent data
+    #[serde(default)]
+    pub disks: Vec<DiskSpace>,
 }
 
 // REMOVED: Manual TorrentUpdate struct definition as it's now generated by Patch macro
@@ -107,6 +346,30 @@ pub struct TorrentFile {
     pub priority: u8, // 0: Off, 1: Normal, 2: High
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct FileTreeNode {
+    pub name: String,
+    /// `/`-separated path relative to the torrent root, empty for the root itself
+    pub path: String,
+    /// rTorrent file index; `None` for directories
+    pub index: Option<u32>,
+    pub size: i64,
+    pub completed_bytes: i64,
+    /// Priority shared by every file below this node, `None` if mixed
+    pub priority: Option<u8>,
+    /// First and last pieces are downloaded early (files only)
+    pub preview: bool,
+    #[schema(no_recursion)]
+    pub children: Vec<FileTreeNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct TorrentFileTree {
+    /// Only multi-file torrents own a folder that can be renamed
+    pub multi_file: bool,
+    pub root: FileTreeNode,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct TorrentPeer {
     pub ip: String,
@@ -123,6 +386,34 @@ pub struct TorrentTracker {
     pub message: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, Default)]
+pub struct TorrentPieceMap {
+    pub piece_count: u32,
+    pub piece_size: i64,
+    /// Packed bitfield as returned by `d.bitfield` (MSB first)
+    pub bitfield: Vec<u8>,
+    /// Number of peers seen with each piece (`d.chunks_seen`, saturates at 255)
+    pub availability: Vec<u8>,
+}
+
+impl TorrentPieceMap {
+    pub fn has_piece(&self, index: u32) -> bool {
+        self.bitfield
+            .get((index / 8) as usize)
+            .map(|byte| byte & (0x80 >> (index % 8)) != 0)
+            .unwrap_or(false)
+    }
+
+    pub fn completed_pieces(&self) -> u32 {
+        (0..self.piece_count).filter(|i| self.has_piece(*i)).count() as u32
+    }
+
+    /// First piece in `first..=last` that is not downloaded yet.
+    pub fn first_missing(&self, first: u32, last: u32) -> Option<u32> {
+        (first..=last.min(self.piece_count.saturating_sub(1))).find(|i| !self.has_piece(*i))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct SetFilePriorityRequest {
     pub hash: String,
@@ -146,4 +437,413 @@ pub struct SetLabelRequest {
 pub struct AddTorrentRequest {
     #[schema(example = "magnet:?xt=urn:btih:...")]
     pub uri: String,
-}
\ No newline at end of file
+    /// Directory inside one of the browse roots, `directory.default` when empty
+    #[serde(default)]
+    pub save_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq, Default)]
+pub struct DiskSpace {
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct DirectoryEntry {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct DirectoryListing {
+    /// Allowed root that contains `path`
+    pub root: String,
+    pub path: String,
+    /// `None` at the root, browsing never goes above it
+    pub parent: Option<String>,
+    pub entries: Vec<DirectoryEntry>,
+    pub space: Option<DiskSpace>,
+}
+
+/// One bucket of global transfer history. Rates are bytes per second,
+/// `downloaded`/`uploaded` are bytes transferred within the bucket.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq, Default)]
+pub struct TransferSample {
+    /// Unix timestamp (UTC) of the start of the bucket
+    pub ts: i64,
+    /// Bucket length in seconds
+    pub resolution: i64,
+    pub down_rate: i64,
+    pub up_rate: i64,
+    pub down_peak: i64,
+    pub up_peak: i64,
+    pub downloaded: i64,
+    pub uploaded: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, PartialEq, Eq)]
+pub enum TransferPeriod {
+    Day,
+    Week,
+    Month,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct TransferTotal {
+    /// Unix timestamp of the start of the period (in the caller's time zone)
+    pub start: i64,
+    pub downloaded: i64,
+    pub uploaded: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct TorrentTransferTotal {
+    pub hash: String,
+    pub name: String,
+    pub downloaded: i64,
+    pub uploaded: i64,
+}
+
+/// What a user account may do.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, PartialEq, Eq)]
+pub enum UserRole {
+    /// Everything, including user management and automations
+    Admin,
+    /// Managing torrents and limits
+    Operator,
+    /// Watching progress only
+    Viewer,
+}
+
+impl UserRole {
+    pub const ALL: [UserRole; 3] = [UserRole::Admin, UserRole::Operator, UserRole::Viewer];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Admin => "admin",
+            UserRole::Operator => "operator",
+            UserRole::Viewer => "viewer",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "admin" => Some(UserRole::Admin),
+            "operator" => Some(UserRole::Operator),
+            "viewer" => Some(UserRole::Viewer),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub role: UserRole,
+    pub disabled: bool,
+    pub created_at: String,
+}
+
+/// What a personal API token may do.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, PartialEq, Eq)]
+pub enum ApiTokenScope {
+    /// Read-only requests (`GET`/`HEAD`)
+    ReadOnly,
+    /// Adding torrents only
+    AddOnly,
+    /// Everything except managing API tokens
+    Full,
+}
+
+impl ApiTokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiTokenScope::ReadOnly => "read",
+            ApiTokenScope::AddOnly => "add",
+            ApiTokenScope::Full => "full",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "read" => Some(ApiTokenScope::ReadOnly),
+            "add" => Some(ApiTokenScope::AddOnly),
+            "full" => Some(ApiTokenScope::Full),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub scope: ApiTokenScope,
+    /// Unix timestamps
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+}
+
+/// Returned once on creation; only a hash of `secret` is stored.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct NewApiToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+/// Torrent events an automation can react to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, PartialEq, Eq)]
+pub enum AutomationTrigger {
+    Added,
+    Completed,
+    /// Torrent or tracker errors
+    Errored,
+    Removed,
+}
+
+impl AutomationTrigger {
+    pub const ALL: [AutomationTrigger; 4] = [
+        AutomationTrigger::Added,
+        AutomationTrigger::Completed,
+        AutomationTrigger::Errored,
+        AutomationTrigger::Removed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AutomationTrigger::Added => "added",
+            AutomationTrigger::Completed => "completed",
+            AutomationTrigger::Errored => "errored",
+            AutomationTrigger::Removed => "removed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "added" => Some(AutomationTrigger::Added),
+            "completed" => Some(AutomationTrigger::Completed),
+            "errored" => Some(AutomationTrigger::Errored),
+            "removed" => Some(AutomationTrigger::Removed),
+            _ => None,
+        }
+    }
+
+    /// The trigger a lifecycle event fires, if any.
+    pub fn of(event: &LifecycleEvent) -> Option<Self> {
+        match event {
+            LifecycleEvent::TorrentAdded { .. } => Some(AutomationTrigger::Added),
+            LifecycleEvent::TorrentCompleted { .. } => Some(AutomationTrigger::Completed),
+            LifecycleEvent::TorrentErrored { .. } | LifecycleEvent::TrackerError { .. } => {
+                Some(AutomationTrigger::Errored)
+            }
+            LifecycleEvent::TorrentRemoved { .. } => Some(AutomationTrigger::Removed),
+            LifecycleEvent::DiskLow { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub enum AutomationAction {
+    /// HTTP request; `body_template` is JSON with `{{event}}`, `{{hash}}`,
+    /// `{{name}}` and `{{message}}` placeholders. With a secret the body is
+    /// signed as `X-VibeTorrent-Signature: sha256=<hmac>`.
+    Webhook {
+        url: String,
+        method: String,
+        body_template: String,
+        secret: Option<String>,
+    },
+    /// Run through `sh -c` with the event in `VT_EVENT`, `VT_HASH`,
+    /// `VT_NAME` and `VT_MESSAGE`.
+    Script { command: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct Automation {
+    pub id: i64,
+    pub name: String,
+    pub triggers: Vec<AutomationTrigger>,
+    pub action: AutomationAction,
+    pub enabled: bool,
+    /// Unix timestamp
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct AutomationDelivery {
+    pub id: i64,
+    pub automation_id: i64,
+    pub automation_name: String,
+    pub trigger: AutomationTrigger,
+    pub torrent_name: String,
+    pub attempts: i64,
+    pub success: bool,
+    /// HTTP status, exit code or error of the last attempt
+    pub detail: String,
+    /// Unix timestamp
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, PartialEq, Eq)]
+pub enum SmtpSecurity {
+    None,
+    StartTls,
+    /// Implicit TLS, usually port 465
+    Tls,
+}
+
+/// Where and how a notification channel delivers.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub enum ChannelConfig {
+    /// `POST` of `{"title", "message", "level", "event"}` as JSON
+    Webhook { url: String },
+    /// ntfy topic URL, e.g. `https://ntfy.sh/my-topic`
+    Ntfy { url: String, token: Option<String> },
+    /// Gotify server URL and application token
+    Gotify { url: String, token: String },
+    /// Discord or Slack incoming webhook
+    Discord { url: String },
+    Email {
+        host: String,
+        port: u16,
+        security: SmtpSecurity,
+        username: Option<String>,
+        password: Option<String>,
+        from: String,
+        to: String,
+    },
+}
+
+impl ChannelConfig {
+    /// Blanks tokens and passwords before the config leaves the server.
+    pub fn redact(&mut self) {
+        match self {
+            ChannelConfig::Ntfy { token: Some(secret), .. }
+            | ChannelConfig::Gotify { token: secret, .. }
+            | ChannelConfig::Email { password: Some(secret), .. } => secret.clear(),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct NotificationChannel {
+    pub id: i64,
+    pub name: String,
+    pub config: ChannelConfig,
+    pub events: Vec<LifecycleKind>,
+    pub enabled: bool,
+    /// Unix timestamp
+    pub created_at: i64,
+}
+
+/// A logged-in browser or client of the current user.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct LoginSession {
+    pub id: i64,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub remember: bool,
+    /// Unix timestamps
+    pub created_at: i64,
+    pub last_seen_at: i64,
+    pub expires_at: i64,
+    /// The session the list was requested from
+    pub current: bool,
+}
+
+/// A browser registered for Web Push.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct PushDevice {
+    pub id: i64,
+    pub user_agent: Option<String>,
+    pub created_at: String,
+}
+
+/// Local time window in which no push is sent.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, ToSchema, PartialEq, Eq)]
+pub struct QuietHours {
+    /// Minutes after midnight; a start after the end spans midnight
+    pub start_minute: u16,
+    pub end_minute: u16,
+    /// The user's offset from UTC when the window was saved
+    pub utc_offset_minutes: i32,
+}
+
+impl QuietHours {
+    pub fn contains(&self, unix_time: i64) -> bool {
+        let minute = (unix_time / 60 + self.utc_offset_minutes as i64).rem_euclid(1440) as u16;
+        if self.start_minute <= self.end_minute {
+            (self.start_minute..self.end_minute).contains(&minute)
+        } else {
+            minute >= self.start_minute || minute < self.end_minute
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema, PartialEq)]
+pub struct PushPreferences {
+    pub events: Vec<LifecycleKind>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for PushPreferences {
+    /// The events that were pushed before preferences existed.
+    fn default() -> Self {
+        Self {
+            events: vec![LifecycleKind::Completed, LifecycleKind::Errored, LifecycleKind::DiskLow],
+            quiet_hours: None,
+        }
+    }
+}
+
+impl PushPreferences {
+    /// Whether to push an event of `kind` (`None` for tests) at `unix_time`.
+    pub fn wants(&self, kind: Option<LifecycleKind>, unix_time: i64) -> bool {
+        kind.is_none_or(|k| self.events.contains(&k))
+            && !self.quiet_hours.is_some_and(|q| q.contains(unix_time))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct BlocklistSource {
+    pub id: i64,
+    pub name: String,
+    /// Server-side path for file sources, `None` for uploaded lists
+    pub path: Option<String>,
+    pub range_count: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct BlocklistStatus {
+    pub sources: Vec<BlocklistSource>,
+    /// Merged ranges currently loaded into rTorrent's ipv4_filter
+    pub blocked_ranges: i64,
+    pub applied_at: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_hours() {
+        // 23:00-07:00 at UTC+3, i.e. 20:00-04:00 UTC
+        let quiet = QuietHours { start_minute: 23 * 60, end_minute: 7 * 60, utc_offset_minutes: 180 };
+        let at = |h: i64, m: i64| (h * 60 + m) * 60;
+        assert!(quiet.contains(at(20, 0)));
+        assert!(quiet.contains(at(1, 30)));
+        assert!(!quiet.contains(at(4, 0)));
+        assert!(!quiet.contains(at(12, 0)));
+
+        let prefs = PushPreferences { events: vec![LifecycleKind::Completed], quiet_hours: Some(quiet) };
+        assert!(prefs.wants(Some(LifecycleKind::Completed), at(12, 0)));
+        assert!(!prefs.wants(Some(LifecycleKind::Completed), at(21, 0)));
+        assert!(!prefs.wants(Some(LifecycleKind::Added), at(12, 0)));
+        assert!(prefs.wants(None, at(12, 0)));
+    }
+}
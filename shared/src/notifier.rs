@@ -0,0 +1,402 @@
+//! Out-of-band notification channels. Every channel kind implements
+//! `Notifier`; the backend fans lifecycle events out to the channels whose
+//! event filter matches, and the settings page can send a test through one.
+
+use crate::{AuthUser, ChannelConfig, LifecycleEvent, LifecycleKind, NotificationLevel, SmtpSecurity};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// What gets delivered, independent of the channel.
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    pub level: NotificationLevel,
+    /// `None` for test messages
+    pub kind: Option<LifecycleKind>,
+    /// Torrent the event is about, if any
+    pub hash: Option<String>,
+    /// User who added that torrent
+    pub owner: Option<i64>,
+}
+
+impl Notification {
+    pub fn from_event(event: &LifecycleEvent) -> Self {
+        Self {
+            title: event.title().to_string(),
+            body: event.message(),
+            level: event.level(),
+            kind: Some(event.kind()),
+            hash: event.hash().map(str::to_string),
+            owner: None,
+        }
+    }
+
+    pub fn test() -> Self {
+        Self {
+            title: "VibeTorrent".to_string(),
+            body: "Test bildirimi: bu kanal çalışıyor.".to_string(),
+            level: NotificationLevel::Info,
+            kind: None,
+            hash: None,
+            owner: None,
+        }
+    }
+
+    /// Torrent events reach admins and the torrent's owner; the rest
+    /// reaches everyone.
+    pub fn visible_to(&self, user: &AuthUser) -> bool {
+        self.hash.is_none() || user.owns(self.owner)
+    }
+
+    fn level_str(&self) -> &'static str {
+        match self.level {
+            NotificationLevel::Info => "info",
+            NotificationLevel::Success => "success",
+            NotificationLevel::Warning => "warning",
+            NotificationLevel::Error => "error",
+        }
+    }
+}
+
+pub trait Notifier: Send + Sync {
+    fn send<'a>(&'a self, notification: &'a Notification) -> SendFuture<'a>;
+}
+
+pub fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(TIMEOUT)
+        .user_agent(concat!("VibeTorrent/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .unwrap_or_default()
+}
+
+async fn check(response: Result<reqwest::Response, reqwest::Error>) -> Result<(), String> {
+    let response = response.map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", response.status().as_u16()))
+    }
+}
+
+struct Webhook {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Webhook {
+    fn payload(n: &Notification) -> serde_json::Value {
+        serde_json::json!({
+            "title": n.title,
+            "message": n.body,
+            "level": n.level_str(),
+            "event": n.kind.map(|k| k.as_str()),
+        })
+    }
+}
+
+impl Notifier for Webhook {
+    fn send<'a>(&'a self, n: &'a Notification) -> SendFuture<'a> {
+        Box::pin(async move {
+            let body = Self::payload(n).to_string();
+            check(self.client.post(&self.url).header("Content-Type", "application/json").body(body).send().await).await
+        })
+    }
+}
+
+struct Ntfy {
+    client: reqwest::Client,
+    url: String,
+    token: Option<String>,
+}
+
+/// Splits `https://ntfy.sh/alerts` into the server and the topic.
+fn ntfy_target(url: &str) -> Result<(&str, &str), String> {
+    match url.trim_end_matches('/').rsplit_once('/') {
+        Some((server, topic)) if !topic.is_empty() && !server.ends_with('/') => Ok((server, topic)),
+        _ => Err("ntfy URL must end with the topic".to_string()),
+    }
+}
+
+impl Ntfy {
+    fn payload(topic: &str, n: &Notification) -> serde_json::Value {
+        let priority = match n.level {
+            NotificationLevel::Error => 4,
+            _ => 3,
+        };
+        serde_json::json!({ "topic": topic, "title": n.title, "message": n.body, "priority": priority })
+    }
+}
+
+impl Notifier for Ntfy {
+    fn send<'a>(&'a self, n: &'a Notification) -> SendFuture<'a> {
+        Box::pin(async move {
+            // JSON publishing instead of headers, which cannot carry non-ASCII titles
+            let (server, topic) = ntfy_target(&self.url)?;
+            let body = Self::payload(topic, n);
+            let mut request = self.client.post(server).header("Content-Type", "application/json").body(body.to_string());
+            if let Some(token) = self.token.as_deref().filter(|t| !t.is_empty()) {
+                request = request.bearer_auth(token);
+            }
+            check(request.send().await).await
+        })
+    }
+}
+
+struct Gotify {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl Gotify {
+    fn payload(n: &Notification) -> serde_json::Value {
+        let priority = match n.level {
+            NotificationLevel::Error => 8,
+            NotificationLevel::Warning => 5,
+            _ => 3,
+        };
+        serde_json::json!({ "title": n.title, "message": n.body, "priority": priority })
+    }
+}
+
+impl Notifier for Gotify {
+    fn send<'a>(&'a self, n: &'a Notification) -> SendFuture<'a> {
+        Box::pin(async move {
+            let url = format!("{}/message", self.url.trim_end_matches('/'));
+            let body = Self::payload(n).to_string();
+            check(
+                self.client
+                    .post(url)
+                    .header("X-Gotify-Key", &self.token)
+                    .header("Content-Type", "application/json")
+                    .body(body)
+                    .send()
+                    .await,
+            )
+            .await
+        })
+    }
+}
+
+struct Discord {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl Discord {
+    fn payload(n: &Notification) -> serde_json::Value {
+        let text = format!("**{}**\n{}", n.title, n.body);
+        // Discord reads `content`, Slack reads `text`; each ignores the other
+        serde_json::json!({ "content": text, "text": text })
+    }
+}
+
+impl Notifier for Discord {
+    fn send<'a>(&'a self, n: &'a Notification) -> SendFuture<'a> {
+        Box::pin(async move {
+            let body = Self::payload(n).to_string();
+            check(self.client.post(&self.url).header("Content-Type", "application/json").body(body).send().await).await
+        })
+    }
+}
+
+struct Email {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    username: Option<String>,
+    password: Option<String>,
+    from: String,
+    to: String,
+}
+
+impl Email {
+    fn message(&self, n: &Notification) -> Result<lettre::Message, String> {
+        use lettre::message::header::ContentType;
+
+        lettre::Message::builder()
+            .from(self.from.parse().map_err(|e| format!("Invalid sender: {}", e))?)
+            .to(self.to.parse().map_err(|e| format!("Invalid recipient: {}", e))?)
+            .subject(&n.title)
+            .header(ContentType::TEXT_PLAIN)
+            .body(n.body.clone())
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl Notifier for Email {
+    fn send<'a>(&'a self, n: &'a Notification) -> SendFuture<'a> {
+        use lettre::transport::smtp::authentication::Credentials;
+        use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+        Box::pin(async move {
+            let message = self.message(n)?;
+
+            let builder = match self.security {
+                SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host),
+                SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host),
+                SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host)),
+            }
+            .map_err(|e| e.to_string())?;
+            let mut builder = builder.port(self.port).timeout(Some(TIMEOUT));
+            if let Some(username) = &self.username {
+                builder = builder.credentials(Credentials::new(
+                    username.clone(),
+                    self.password.clone().unwrap_or_default(),
+                ));
+            }
+
+            builder.build().send(message).await.map(|_| ()).map_err(|e| e.to_string())
+        })
+    }
+}
+
+pub fn build(config: &ChannelConfig, client: &reqwest::Client) -> Box<dyn Notifier> {
+    let client = client.clone();
+    match config.clone() {
+        ChannelConfig::Webhook { url } => Box::new(Webhook { client, url }),
+        ChannelConfig::Ntfy { url, token } => Box::new(Ntfy { client, url, token }),
+        ChannelConfig::Gotify { url, token } => Box::new(Gotify { client, url, token }),
+        ChannelConfig::Discord { url } => Box::new(Discord { client, url }),
+        ChannelConfig::Email { host, port, security, username, password, from, to } => {
+            Box::new(Email { host, port, security, username, password, from, to })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn notification() -> Notification {
+        let event = LifecycleEvent::TorrentErrored {
+            hash: "ABC".to_string(),
+            name: "debian.iso".to_string(),
+            message: "disk full".to_string(),
+        };
+        Notification::from_event(&event)
+    }
+
+    #[test]
+    fn test_ntfy_target() {
+        assert_eq!(ntfy_target("https://ntfy.sh/alerts"), Ok(("https://ntfy.sh", "alerts")));
+        assert_eq!(ntfy_target("https://ntfy.sh/alerts/"), Ok(("https://ntfy.sh", "alerts")));
+        assert_eq!(ntfy_target("http://host/ntfy/alerts"), Ok(("http://host/ntfy", "alerts")));
+        assert!(ntfy_target("https://ntfy.sh").is_err());
+        assert!(ntfy_target("https://ntfy.sh/").is_err());
+        assert!(ntfy_target("alerts").is_err());
+    }
+
+    #[test]
+    fn test_payloads() {
+        let n = notification();
+        assert_eq!(
+            Webhook::payload(&n),
+            serde_json::json!({
+                "title": "Torrent Hatası",
+                "message": "debian.iso: disk full",
+                "level": "error",
+                "event": "errored",
+            })
+        );
+        assert_eq!(
+            Ntfy::payload("alerts", &n),
+            serde_json::json!({ "topic": "alerts", "title": "Torrent Hatası", "message": "debian.iso: disk full", "priority": 4 })
+        );
+        assert_eq!(
+            Gotify::payload(&n),
+            serde_json::json!({ "title": "Torrent Hatası", "message": "debian.iso: disk full", "priority": 8 })
+        );
+        let text = "**Torrent Hatası**\ndebian.iso: disk full";
+        assert_eq!(Discord::payload(&n), serde_json::json!({ "content": text, "text": text }));
+
+        let test = Notification::test();
+        assert_eq!(Webhook::payload(&test)["event"], serde_json::Value::Null);
+        assert_eq!(Gotify::payload(&test)["priority"], 3);
+    }
+
+    #[test]
+    fn test_email_message() {
+        let mut email = Email {
+            host: "localhost".to_string(),
+            port: 25,
+            security: SmtpSecurity::None,
+            username: None,
+            password: None,
+            from: "VibeTorrent <vt@example.com>".to_string(),
+            to: "admin@example.com".to_string(),
+        };
+        let mut n = notification();
+        n.title = "Torrent error".to_string();
+        let message = email.message(&n).unwrap();
+        let envelope = message.envelope();
+        assert_eq!(envelope.from().unwrap().to_string(), "vt@example.com");
+        assert_eq!(envelope.to()[0].to_string(), "admin@example.com");
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("Subject: Torrent error\r\n"));
+        assert!(formatted.contains("Content-Type: text/plain"));
+        assert!(formatted.ends_with("debian.iso: disk full"));
+
+        email.to = "not an address".to_string();
+        assert!(email.message(&n).unwrap_err().starts_with("Invalid recipient"));
+    }
+
+    /// Answers one request with `status` and returns the raw request.
+    async fn serve_once(listener: TcpListener, status: u16) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 1024];
+        loop {
+            let n = stream.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "connection closed mid-request");
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf).to_string();
+            let Some((head, body)) = text.split_once("\r\n\r\n") else { continue };
+            let length = head
+                .lines()
+                .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                .map_or(0, |v| v.parse().unwrap());
+            if body.len() >= length {
+                let response = format!("HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                stream.write_all(response.as_bytes()).await.unwrap();
+                return text;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ntfy/alerts", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener, 200));
+        let config = ChannelConfig::Ntfy { url, token: Some("secret".to_string()) };
+        build(&config, &http_client()).send(&notification()).await.unwrap();
+
+        let request = server.await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("POST /ntfy HTTP/1.1\r\n"));
+        let head = head.to_ascii_lowercase();
+        assert!(head.contains("authorization: bearer secret\r\n"));
+        assert!(head.contains("content-type: application/json\r\n"));
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body, Ntfy::payload("alerts", &notification()));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener, 500));
+        let config = ChannelConfig::Gotify { url, token: "app-token".to_string() };
+        let result = build(&config, &http_client()).send(&notification()).await;
+        assert_eq!(result, Err("HTTP 500".to_string()));
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /message HTTP/1.1\r\n"));
+        assert!(request.to_ascii_lowercase().contains("x-gotify-key: app-token\r\n"));
+    }
+}
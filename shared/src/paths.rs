@@ -0,0 +1,181 @@
+#![cfg(feature = "ssr")]
+
+//! Path containment checks for anything that touches torrent data on disk.
+
+use crate::xmlrpc::{
+    parse_i64_response, parse_multicall_response, parse_string_response, RpcParam, RtorrentClient, XmlRpcError,
+};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PathError {
+    #[error("Failed to get download root: {0}")]
+    Rpc(#[from] XmlRpcError),
+    #[error("Invalid download root: {0}")]
+    InvalidRoot(std::io::Error),
+    #[error("Invalid data path: {0}")]
+    InvalidPath(std::io::Error),
+    #[error("Security Error: Path is outside the download directory")]
+    OutsideRoot,
+    #[error("Security Error: Path is the download root directory")]
+    IsRoot,
+}
+
+/// Canonical form of rTorrent's `directory.default`.
+pub async fn download_root(client: &RtorrentClient) -> Result<PathBuf, PathError> {
+    let root_xml = client.call("directory.default", &[]).await?;
+    let root_path_str = parse_string_response(&root_xml)?;
+
+    tokio::fs::canonicalize(Path::new(&root_path_str))
+        .await
+        .map_err(PathError::InvalidRoot)
+}
+
+/// Canonicalizes `path` (resolving symlinks and `..`) and makes sure the
+/// result lives strictly inside `root`.
+pub async fn contain(root: &Path, path: &Path) -> Result<PathBuf, PathError> {
+    let target = tokio::fs::canonicalize(path)
+        .await
+        .map_err(PathError::InvalidPath)?;
+
+    if !target.starts_with(root) {
+        return Err(PathError::OutsideRoot);
+    }
+    if target == root {
+        return Err(PathError::IsRoot);
+    }
+    Ok(target)
+}
+
+/// The torrent's own data (`base/name` for a single file, `base` for a
+/// folder) and the path `relative` names inside it. Only the torrent itself,
+/// one of its files or a folder holding some of them may be named, so a
+/// single-file torrent cannot reach its siblings in the shared directory.
+fn torrent_target(
+    base: &Path,
+    name: &str,
+    multi_file: bool,
+    files: &[String],
+    relative: &str,
+) -> Result<(PathBuf, PathBuf), PathError> {
+    let data = if multi_file { base.to_path_buf() } else { base.join(name) };
+    if relative.is_empty() {
+        return Ok((data.clone(), data));
+    }
+    let known = multi_file
+        && files.iter().any(|file| {
+            file == relative || file.strip_prefix(relative).is_some_and(|rest| rest.starts_with('/'))
+        });
+    if !known {
+        return Err(PathError::OutsideRoot);
+    }
+    let target = data.join(relative);
+    Ok((data, target))
+}
+
+/// Resolves a path inside a torrent's data (`/`-separated and relative to
+/// the torrent root, as in the file tree) to a contained absolute path.
+/// An empty `relative` means the torrent's own file or folder.
+pub async fn resolve_torrent_path(
+    client: &RtorrentClient,
+    hash: &str,
+    relative: &str,
+) -> Result<PathBuf, PathError> {
+    let relative = relative.trim_matches('/');
+    if relative.split('/').any(|part| part == "..") {
+        return Err(PathError::OutsideRoot);
+    }
+
+    let params = vec![RpcParam::from(hash)];
+    // For single-file torrents this is the containing directory
+    let base = parse_string_response(&client.call("d.directory_base", &params).await?)?;
+    let multi_file = parse_i64_response(&client.call("d.is_multi_file", &params).await?)? == 1;
+    let name = parse_string_response(&client.call("d.name", &params).await?)?;
+    let files: Vec<String> = if multi_file && !relative.is_empty() {
+        let file_params = [RpcParam::from(hash), RpcParam::from(""), RpcParam::from("f.path=")];
+        parse_multicall_response(&client.call("f.multicall", &file_params).await?)?
+            .into_iter()
+            .filter_map(|row| row.into_iter().next())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let (data, target) = torrent_target(Path::new(&base), &name, multi_file, &files, relative)?;
+    let root = download_root(client).await?;
+    let data = contain(&root, &data).await?;
+    let target = contain(&root, &target).await?;
+    if !target.starts_with(&data) {
+        return Err(PathError::OutsideRoot);
+    }
+    Ok(target)
+}
+
+/// Directories the file browser may expose: `directory.default` followed by
+/// any configured extra roots that exist, all canonicalized.
+pub async fn browse_roots(client: &RtorrentClient, extra: &[String]) -> Result<Vec<PathBuf>, PathError> {
+    let mut roots = vec![download_root(client).await?];
+    for root in extra {
+        match tokio::fs::canonicalize(root).await {
+            Ok(path) if !roots.contains(&path) => roots.push(path),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Ignoring browse root {}: {}", root, e),
+        }
+    }
+    Ok(roots)
+}
+
+/// Canonicalizes `path` and returns it together with the allowed root that
+/// contains it. Unlike [`contain`], the root itself is accepted.
+pub async fn contain_in_roots(roots: &[PathBuf], path: &Path) -> Result<(PathBuf, PathBuf), PathError> {
+    let target = tokio::fs::canonicalize(path)
+        .await
+        .map_err(PathError::InvalidPath)?;
+
+    roots
+        .iter()
+        .find(|root| target.starts_with(root))
+        .map(|root| (root.clone(), target.clone()))
+        .ok_or(PathError::OutsideRoot)
+}
+
+/// Validates a single path component supplied by a user (a new file or
+/// folder name), rejecting separators and traversal.
+pub fn is_safe_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains('/')
+        && !name.contains('\\')
+        && !name.contains('\0')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_file_target() {
+        let base = Path::new("/data/downloads");
+        let (data, target) = torrent_target(base, "movie.mkv", false, &[], "").unwrap();
+        assert_eq!(data, Path::new("/data/downloads/movie.mkv"));
+        assert_eq!(target, data);
+        // Siblings in the shared directory are not part of the torrent
+        assert!(torrent_target(base, "movie.mkv", false, &[], "other.iso").is_err());
+        assert!(torrent_target(base, "movie.mkv", false, &["movie.mkv".into()], "movie.mkv").is_err());
+    }
+
+    #[test]
+    fn test_multi_file_target() {
+        let base = Path::new("/data/downloads/Album");
+        let files = vec!["cd1/01.flac".to_string(), "cd1/02.flac".to_string(), "cover.jpg".to_string()];
+        let (data, target) = torrent_target(base, "Album", true, &files, "").unwrap();
+        assert_eq!((data.as_path(), target.as_path()), (base, base));
+        let (_, target) = torrent_target(base, "Album", true, &files, "cd1/02.flac").unwrap();
+        assert_eq!(target, Path::new("/data/downloads/Album/cd1/02.flac"));
+        assert!(torrent_target(base, "Album", true, &files, "cd1").is_ok());
+        assert!(torrent_target(base, "Album", true, &files, "cd").is_err());
+        assert!(torrent_target(base, "Album", true, &files, "notes.txt").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! What each user role may do. The auth middleware checks every request
+//! against the role of its user, on top of the API token scope if one was
+//! used, so a token can never do more than its owner.
+
+use crate::api_token::is_read_server_fn;
+use crate::server_fns::{api_tokens, auth, automations, blocklist, notifications, push, sessions, settings, users};
+use crate::UserRole;
+use leptos::server_fn::ServerFn;
+
+/// Accounts, settings that run commands, send data elsewhere, read files
+/// on the server or affect every user, and the qBittorrent/Transmission
+/// APIs, which have no notion of torrent ownership.
+const ADMIN_PATHS: [&str; 23] = [
+    users::ListUsers::PATH,
+    users::CreateUser::PATH,
+    users::DeleteUser::PATH,
+    users::SetUserRole::PATH,
+    users::SetUserDisabled::PATH,
+    users::ResetUserPassword::PATH,
+    automations::ListAutomations::PATH,
+    automations::ListAutomationDeliveries::PATH,
+    automations::CreateAutomation::PATH,
+    automations::SetAutomationEnabled::PATH,
+    automations::DeleteAutomation::PATH,
+    notifications::ListNotificationChannels::PATH,
+    notifications::CreateNotificationChannel::PATH,
+    notifications::SetNotificationChannelEnabled::PATH,
+    notifications::DeleteNotificationChannel::PATH,
+    notifications::TestNotificationChannel::PATH,
+    blocklist::ImportBlocklistPath::PATH,
+    blocklist::ImportBlocklistUpload::PATH,
+    blocklist::RemoveBlocklistSource::PATH,
+    blocklist::ApplyBlocklist::PATH,
+    settings::SetGlobalLimits::PATH,
+    "/api/v2/",
+    "/transmission/",
+];
+
+/// REST routes that anyone may read but only admins may change.
+const ADMIN_WRITE_PATHS: [&str; 1] = ["/api/v1/limits"];
+
+/// A user's own sessions, push devices and API tokens, which viewers may
+/// manage too.
+const SELF_SERVICE_PATHS: [&str; 9] = [
+    auth::Logout::PATH,
+    sessions::RevokeSession::PATH,
+    sessions::RevokeOtherSessions::PATH,
+    push::SubscribePush::PATH,
+    push::UnsubscribePush::PATH,
+    push::RevokePushDevice::PATH,
+    push::SetPushPreferences::PATH,
+    api_tokens::CreateApiToken::PATH,
+    api_tokens::RevokeApiToken::PATH,
+];
+
+/// Whether a user with `role` may make this request.
+pub fn role_allows(role: UserRole, method: &str, path: &str) -> bool {
+    if role == UserRole::Admin {
+        return true;
+    }
+    let read = method == "GET" || method == "HEAD";
+    if ADMIN_PATHS.iter().any(|p| path.starts_with(p)) || (!read && ADMIN_WRITE_PATHS.contains(&path)) {
+        return false;
+    }
+    match role {
+        UserRole::Admin | UserRole::Operator => true,
+        UserRole::Viewer => {
+            read || is_read_server_fn(path)
+                || SELF_SERVICE_PATHS.iter().any(|p| path.starts_with(p))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server_fns::{stats, torrent};
+
+    #[test]
+    fn test_role_allows() {
+        let create_user = users::CreateUser::PATH;
+        assert!(create_user.starts_with("/api/server_fns/create_user"));
+        assert!(role_allows(UserRole::Admin, "POST", create_user));
+        assert!(!role_allows(UserRole::Operator, "POST", create_user));
+        assert!(!role_allows(UserRole::Operator, "POST", automations::CreateAutomation::PATH));
+        assert!(!role_allows(UserRole::Operator, "POST", notifications::TestNotificationChannel::PATH));
+        assert!(!role_allows(UserRole::Operator, "POST", blocklist::ImportBlocklistPath::PATH));
+        assert!(!role_allows(UserRole::Operator, "POST", settings::SetGlobalLimits::PATH));
+        assert!(role_allows(UserRole::Operator, "POST", torrent::TorrentAction::PATH));
+        assert!(!role_allows(UserRole::Operator, "PUT", "/api/v1/limits"));
+        assert!(role_allows(UserRole::Viewer, "GET", "/api/v1/limits"));
+        assert!(role_allows(UserRole::Viewer, "GET", "/api/v1/torrents"));
+        assert!(role_allows(UserRole::Viewer, "POST", torrent::GetFiles::PATH));
+        assert!(role_allows(UserRole::Viewer, "POST", stats::GetTransferHistory::PATH));
+        assert!(role_allows(UserRole::Viewer, "POST", auth::GetUser::PATH));
+        assert!(role_allows(UserRole::Viewer, "POST", auth::Logout::PATH));
+        assert!(role_allows(UserRole::Viewer, "POST", push::SubscribePush::PATH));
+        assert!(!role_allows(UserRole::Viewer, "POST", torrent::TorrentAction::PATH));
+        assert!(!role_allows(UserRole::Viewer, "POST", torrent::AddTorrent::PATH));
+        assert!(!role_allows(UserRole::Viewer, "DELETE", "/api/v1/torrents/abc"));
+        assert!(!role_allows(UserRole::Viewer, "POST", users::ListUsers::PATH));
+        assert!(!role_allows(UserRole::Operator, "GET", "/api/v2/torrents/info"));
+        assert!(role_allows(UserRole::Admin, "POST", "/transmission/rpc"));
+    }
+}
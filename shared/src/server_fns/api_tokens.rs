@@ -0,0 +1,70 @@
+use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::{ApiToken, ApiTokenScope, NewApiToken};
+
+#[cfg(feature = "ssr")]
+use super::auth::current_user_id;
+
+#[server(ListApiTokens, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_api_tokens() -> Result<Vec<ApiToken>, ServerFnError> {
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    db.list_api_tokens(user_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))
+}
+
+/// Creates a token for the logged-in user. The secret is only returned here.
+#[server(CreateApiToken, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn create_api_token(
+    name: String,
+    scope: ApiTokenScope,
+    expires_in_days: Option<u32>,
+) -> Result<NewApiToken, ServerFnError> {
+    use crate::api_token;
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServerFnError::new("Token name is required"));
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let expires_at = expires_in_days.filter(|d| *d > 0).map(|d| now + d as i64 * 86_400);
+
+    let secret = api_token::generate();
+    let id = db
+        .create_api_token(user_id, name, &api_token::hash(&secret), scope, now, expires_at)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    Ok(NewApiToken {
+        token: ApiToken {
+            id,
+            name: name.to_string(),
+            scope,
+            created_at: now,
+            expires_at,
+            last_used_at: None,
+        },
+        secret,
+    })
+}
+
+#[server(RevokeApiToken, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn revoke_api_token(id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    let revoked = db
+        .revoke_api_token(id, user_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if !revoked {
+        return Err(ServerFnError::new("Token not found"));
+    }
+    Ok(())
+}
@@ -1,18 +1,13 @@
 use leptos::prelude::*;
 use serde::{Deserialize, Serialize};
 use crate::codec::MsgPack;
+use crate::UserRole;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UserResponse {
     pub id: i64,
     pub username: String,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Claims {
-    pub sub: String, // username
-    pub uid: i64,    // user id
-    pub exp: usize,
+    pub role: UserRole,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -49,51 +44,75 @@ pub async fn setup(username: String, password: String) -> Result<(), ServerFnErr
     let password_hash = bcrypt::hash(&password, 6)
         .map_err(|_| ServerFnError::new("Hashing error"))?;
 
-    db_context.db.create_user(&username, &password_hash).await
+    // The first account manages everyone else
+    db_context.db.create_user(&username, &password_hash, UserRole::Admin).await
+        .map(|_| ())
         .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
 
     Ok(())
 }
 
-#[server(Login, "/api/server_fns", input = MsgPack, output = MsgPack)]
-pub async fn login(username: String, password: String) -> Result<UserResponse, ServerFnError> {
-    use crate::DbContext;
-    use leptos_axum::ResponseOptions;
-    use jsonwebtoken::{encode, Header, EncodingKey};
-    use cookie::{Cookie, SameSite};
-    use std::time::{SystemTime, UNIX_EPOCH};
+/// Returns the user id if `password` is right for `username` and the
+/// account is not disabled.
+#[cfg(feature = "ssr")]
+pub async fn check_credentials(db: &crate::db::Db, username: &str, password: &str) -> Result<Option<i64>, ServerFnError> {
+    let user_opt = db.get_user_by_username(username).await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
 
-    let db_context = use_context::<DbContext>().ok_or_else(|| ServerFnError::new("DB Context missing"))?;
-    
-    let user_opt = db_context.db.get_user_by_username(&username).await
+    let Some(uid) = user_opt.and_then(|(uid, password_hash)| {
+        bcrypt::verify(password, &password_hash).unwrap_or(false).then_some(uid)
+    }) else {
+        return Ok(None);
+    };
+
+    let role = db.get_active_user_role(uid).await
         .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    Ok(role.map(|_| uid))
+}
 
-    if let Some((uid, password_hash)) = user_opt {
-        let valid = bcrypt::verify(&password, &password_hash).unwrap_or(false);
-        if !valid {
-             return Err(ServerFnError::new("Invalid credentials"));
-        }
+/// Starts a session for `uid` and returns the token for its cookie.
+/// Expired sessions are cleared out on the way.
+#[cfg(feature = "ssr")]
+pub async fn create_session(
+    db: &crate::db::Db,
+    uid: i64,
+    remember: bool,
+    headers: &axum::http::HeaderMap,
+    peer: Option<std::net::SocketAddr>,
+) -> Result<String, ServerFnError> {
+    use crate::session;
+
+    let now = session::unix_now();
+    let (ip, user_agent) = session::client_info(headers, peer);
+    let token = session::generate();
+    db.create_session(uid, &session::hash(&token), remember, ip.as_deref(), user_agent.as_deref(), now)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if let Err(e) = db.delete_expired_sessions(now).await {
+        tracing::warn!("Failed to delete expired sessions: {}", e);
+    }
+    Ok(token)
+}
 
-        let expiration = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as usize + 24 * 3600; // 24 hours
+#[server(Login, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn login(username: String, password: String, remember_me: bool) -> Result<UserResponse, ServerFnError> {
+    use crate::session;
+    use crate::DbContext;
+    use axum::extract::ConnectInfo;
+    use axum::http::HeaderMap;
+    use leptos_axum::{extract, ResponseOptions};
 
-        let claims = Claims {
-            sub: username.clone(),
-            uid,
-            exp: expiration,
-        };
+    let db_context = use_context::<DbContext>().ok_or_else(|| ServerFnError::new("DB Context missing"))?;
 
-        let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))
-            .map_err(|e| ServerFnError::new(format!("Token error: {}", e)))?;
+    if let Some(uid) = check_credentials(&db_context.db, &username, &password).await? {
+        let role = db_context.db.get_active_user_role(uid).await
+            .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?
+            .ok_or_else(|| ServerFnError::new("Invalid credentials"))?;
 
-        let cookie = Cookie::build(("auth_token", token))
-            .path("/")
-            .http_only(true)
-            .same_site(SameSite::Strict)
-            .build();
+        let headers: HeaderMap = extract().await.map_err(|e| ServerFnError::new(format!("Extract error: {}", e)))?;
+        let peer = extract::<ConnectInfo<std::net::SocketAddr>>().await.ok().map(|ConnectInfo(addr)| addr);
+        let token = create_session(&db_context.db, uid, remember_me, &headers, peer).await?;
+        let cookie = session::cookie(session::COOKIE_NAME, &token, remember_me);
 
         if let Some(options) = use_context::<ResponseOptions>() {
             options.insert_header(
@@ -105,6 +124,7 @@ pub async fn login(username: String, password: String) -> Result<UserResponse, S
         Ok(UserResponse {
             id: uid,
             username,
+            role,
         })
     } else {
         Err(ServerFnError::new("Invalid credentials"))
@@ -113,16 +133,21 @@ pub async fn login(username: String, password: String) -> Result<UserResponse, S
 
 #[server(Logout, "/api/server_fns", input = MsgPack, output = MsgPack)]
 pub async fn logout() -> Result<(), ServerFnError> {
-    use leptos_axum::ResponseOptions;
-    use cookie::{Cookie, SameSite};
+    use crate::session;
+    use axum::http::HeaderMap;
+    use leptos_axum::{extract, ResponseOptions};
 
-    let cookie = Cookie::build(("auth_token", ""))
-        .path("/")
-        .http_only(true)
-        .same_site(SameSite::Strict)
-        .max_age(cookie::time::Duration::seconds(0))
-        .build();
+    // Ends the session on the server too, so a copied cookie stops working
+    let headers: HeaderMap = extract().await.map_err(|e| ServerFnError::new(format!("Extract error: {}", e)))?;
+    if let Some(token) = session::token_from_headers(&headers, session::COOKIE_NAME) {
+        expect_context::<crate::DbContext>()
+            .db
+            .delete_session(&session::hash(&token))
+            .await
+            .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    }
 
+    let cookie = session::removal_cookie(session::COOKIE_NAME);
     if let Some(options) = use_context::<ResponseOptions>() {
         options.insert_header(
             axum::http::header::SET_COOKIE,
@@ -132,38 +157,44 @@ pub async fn logout() -> Result<(), ServerFnError> {
     Ok(())
 }
 
-#[server(GetUser, "/api/server_fns", input = MsgPack, output = MsgPack)]
-pub async fn get_user() -> Result<Option<UserResponse>, ServerFnError> {
-    use axum::http::HeaderMap;
-    use leptos_axum::extract;
-    use jsonwebtoken::{decode, Validation, DecodingKey};
+/// ID of the logged-in user, or an error for anonymous requests.
+#[cfg(feature = "ssr")]
+pub async fn current_user_id() -> Result<i64, ServerFnError> {
+    use_context::<crate::AuthUser>()
+        .map(|user| user.id)
+        .ok_or_else(|| ServerFnError::new("Not logged in"))
+}
 
-    let headers: HeaderMap = extract().await.map_err(|e| ServerFnError::new(format!("Extract error: {}", e)))?;
-    let cookie_header = headers.get(axum::http::header::COOKIE)
-        .and_then(|h| h.to_str().ok());
-
-    if let Some(cookie_str) = cookie_header {
-        for c_str in cookie_str.split(';') {
-            if let Ok(c) = cookie::Cookie::parse(c_str.trim()) {
-                if c.name() == "auth_token" {
-                    let token = c.value();
-                    let secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-                    let token_data = decode::<Claims>(
-                        token,
-                        &DecodingKey::from_secret(secret.as_bytes()),
-                        &Validation::default(),
-                    );
-
-                    if let Ok(data) = token_data {
-                        return Ok(Some(UserResponse {
-                            id: data.claims.uid,
-                            username: data.claims.sub,
-                        }));
-                    }
-                }
-            }
-        }
+/// ID of the logged-in user if they are an admin. The middleware already
+/// keeps others out of admin paths; this guards the functions themselves.
+#[cfg(feature = "ssr")]
+pub async fn require_admin() -> Result<i64, ServerFnError> {
+    let user_id = current_user_id().await?;
+    let role = expect_context::<crate::DbContext>()
+        .db
+        .get_active_user_role(user_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if role != Some(UserRole::Admin) {
+        return Err(ServerFnError::new("Admin role required"));
     }
+    Ok(user_id)
+}
 
-    Ok(None)
-}
\ No newline at end of file
+#[server(GetUser, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn get_user() -> Result<Option<UserResponse>, ServerFnError> {
+    // The middleware looked the role up for this request
+    let Some(user) = use_context::<crate::AuthUser>() else {
+        return Ok(None);
+    };
+    let username = expect_context::<crate::DbContext>()
+        .db
+        .get_username_by_id(user.id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    Ok(username.map(|username| UserResponse {
+        id: user.id,
+        username,
+        role: user.role,
+    }))
+}
@@ -0,0 +1,128 @@
+use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::{Automation, AutomationAction, AutomationDelivery, AutomationTrigger};
+
+#[cfg(feature = "ssr")]
+const WEBHOOK_METHODS: [&str; 4] = ["POST", "PUT", "PATCH", "GET"];
+
+/// Secrets are write-only: they come back empty so the UI can only tell
+/// whether one is set.
+#[server(ListAutomations, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_automations() -> Result<Vec<Automation>, ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    let mut automations = db
+        .list_automations()
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    for automation in &mut automations {
+        if let AutomationAction::Webhook { secret: Some(secret), .. } = &mut automation.action {
+            secret.clear();
+        }
+    }
+    Ok(automations)
+}
+
+#[server(CreateAutomation, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn create_automation(
+    name: String,
+    triggers: Vec<AutomationTrigger>,
+    action: AutomationAction,
+) -> Result<Automation, ServerFnError> {
+    // Scripts run as the server's user
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServerFnError::new("Automation name is required"));
+    }
+    if triggers.is_empty() {
+        return Err(ServerFnError::new("Select at least one event"));
+    }
+
+    let action = match action {
+        AutomationAction::Webhook { url, method, body_template, secret } => {
+            let url = url.trim().to_string();
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(ServerFnError::new("Webhook URL must start with http:// or https://"));
+            }
+            let method = method.trim().to_uppercase();
+            if !WEBHOOK_METHODS.contains(&method.as_str()) {
+                return Err(ServerFnError::new(format!("Unsupported HTTP method: {}", method)));
+            }
+            AutomationAction::Webhook {
+                url,
+                method,
+                body_template: body_template.trim().to_string(),
+                secret: secret.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+            }
+        }
+        AutomationAction::Script { command } => {
+            let command = command.trim().to_string();
+            if command.is_empty() {
+                return Err(ServerFnError::new("Command is required"));
+            }
+            AutomationAction::Script { command }
+        }
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let id = db
+        .create_automation(name, &triggers, &action, now)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    let mut automation = Automation {
+        id,
+        name: name.to_string(),
+        triggers,
+        action,
+        enabled: true,
+        created_at: now,
+    };
+    if let AutomationAction::Webhook { secret: Some(secret), .. } = &mut automation.action {
+        secret.clear();
+    }
+    Ok(automation)
+}
+
+#[server(SetAutomationEnabled, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn set_automation_enabled(id: i64, enabled: bool) -> Result<(), ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    let updated = db
+        .set_automation_enabled(id, enabled)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if !updated {
+        return Err(ServerFnError::new("Automation not found"));
+    }
+    Ok(())
+}
+
+#[server(DeleteAutomation, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn delete_automation(id: i64) -> Result<(), ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    let deleted = db
+        .delete_automation(id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if !deleted {
+        return Err(ServerFnError::new("Automation not found"));
+    }
+    Ok(())
+}
+
+#[server(ListAutomationDeliveries, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_automation_deliveries() -> Result<Vec<AutomationDelivery>, ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    db.list_automation_deliveries(50)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))
+}
@@ -0,0 +1,116 @@
+use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::BlocklistStatus;
+
+#[server(GetBlocklistStatus, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn get_blocklist_status() -> Result<BlocklistStatus, ServerFnError> {
+    use crate::blocklist::{SETTING_APPLIED_AT, SETTING_APPLIED_RANGES};
+    let db = expect_context::<crate::DbContext>().db;
+
+    let sources = db
+        .get_blocklist_sources()
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    let blocked_ranges = db
+        .get_setting(SETTING_APPLIED_RANGES)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let applied_at = db
+        .get_setting(SETTING_APPLIED_AT)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?
+        .and_then(|v| v.parse().ok());
+
+    Ok(BlocklistStatus {
+        sources,
+        blocked_ranges,
+        applied_at,
+    })
+}
+
+/// Registers a blocklist file that lives on the server. The file is re-read
+/// every time the filter is applied, so it can be refreshed by a cron job.
+#[server(ImportBlocklistPath, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn import_blocklist_path(path: String) -> Result<BlocklistStatus, ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+
+    let data = crate::blocklist::read_file(&path)
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to read {}: {}", path, e)))?;
+    let range_count = validate_blocklist(&data)?;
+
+    let name = std::path::Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.clone());
+
+    db.add_blocklist_source(&name, Some(&path), None, range_count)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    apply_and_get_status().await
+}
+
+/// Stores an uploaded blocklist (plain or gzip-compressed) in the database.
+#[server(ImportBlocklistUpload, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn import_blocklist_upload(
+    name: String,
+    data: Vec<u8>,
+) -> Result<BlocklistStatus, ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    let range_count = validate_blocklist(&data)?;
+
+    db.add_blocklist_source(&name, None, Some(&data), range_count)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    apply_and_get_status().await
+}
+
+#[server(RemoveBlocklistSource, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn remove_blocklist_source(id: i64) -> Result<BlocklistStatus, ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+
+    db.remove_blocklist_source(id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    apply_and_get_status().await
+}
+
+#[server(ApplyBlocklist, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn apply_blocklist() -> Result<BlocklistStatus, ServerFnError> {
+    super::auth::require_admin().await?;
+    apply_and_get_status().await
+}
+
+#[cfg(feature = "ssr")]
+fn validate_blocklist(data: &[u8]) -> Result<i64, ServerFnError> {
+    let text = crate::blocklist::decode(data)
+        .map_err(|e| ServerFnError::new(format!("Failed to read blocklist: {}", e)))?;
+    let parsed = crate::blocklist::parse(&text);
+
+    if parsed.ranges.is_empty() {
+        return Err(ServerFnError::new(
+            "No valid ranges found (expected eMule DAT or PeerGuardian P2P format)",
+        ));
+    }
+    Ok(parsed.ranges.len() as i64)
+}
+
+#[cfg(feature = "ssr")]
+async fn apply_and_get_status() -> Result<BlocklistStatus, ServerFnError> {
+    let ctx = expect_context::<crate::ServerContext>();
+    let db = expect_context::<crate::DbContext>().db;
+
+    crate::blocklist::reapply(&ctx.scgi_socket_path, &db)
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to apply blocklist: {}", e)))?;
+
+    get_blocklist_status().await
+}
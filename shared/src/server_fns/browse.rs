@@ -0,0 +1,122 @@
+use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::DirectoryListing;
+
+#[cfg(feature = "ssr")]
+async fn allowed_roots() -> Result<Vec<std::path::PathBuf>, ServerFnError> {
+    use crate::xmlrpc::RtorrentClient;
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+
+    crate::paths::browse_roots(&client, &ctx.browse_roots)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))
+}
+
+/// Canonicalizes a user-supplied directory and checks it against the allowed
+/// roots. Returns `(root, directory)`.
+#[cfg(feature = "ssr")]
+pub async fn resolve_directory(path: &str) -> Result<(std::path::PathBuf, std::path::PathBuf), ServerFnError> {
+    use crate::xmlrpc::RtorrentClient;
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+
+    resolve_directory_in(&client, &ctx.browse_roots, path).await
+}
+
+/// `resolve_directory` for callers outside a server function context.
+#[cfg(feature = "ssr")]
+pub async fn resolve_directory_in(
+    client: &crate::xmlrpc::RtorrentClient,
+    extra_roots: &[String],
+    path: &str,
+) -> Result<(std::path::PathBuf, std::path::PathBuf), ServerFnError> {
+    let roots = crate::paths::browse_roots(client, extra_roots)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let (root, dir) = crate::paths::contain_in_roots(&roots, std::path::Path::new(path))
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    if !dir.is_dir() {
+        return Err(ServerFnError::new("Not a directory"));
+    }
+    Ok((root, dir))
+}
+
+#[server(ListBrowseRoots, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_browse_roots() -> Result<Vec<String>, ServerFnError> {
+    Ok(allowed_roots()
+        .await?
+        .into_iter()
+        .map(|root| root.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Lists the subdirectories of `path` (or of the default root when empty).
+/// Hidden directories and symlinks are left out.
+#[server(ListDirectories, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_directories(path: String) -> Result<DirectoryListing, ServerFnError> {
+    use crate::DirectoryEntry;
+
+    let (root, dir) = if path.trim().is_empty() {
+        let root = allowed_roots()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ServerFnError::new("No browse roots configured"))?;
+        (root.clone(), root)
+    } else {
+        resolve_directory(path.trim()).await?
+    };
+
+    let mut read_dir = tokio::fs::read_dir(&dir)
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to read directory: {}", e)))?;
+    let mut entries = Vec::new();
+    while let Ok(Some(item)) = read_dir.next_entry().await {
+        let name = item.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        if item.file_type().await.map(|t| t.is_dir()).unwrap_or(false) {
+            entries.push(DirectoryEntry {
+                path: item.path().to_string_lossy().into_owned(),
+                name,
+            });
+        }
+    }
+    entries.sort_by_key(|e| e.name.to_lowercase());
+
+    let space_dir = dir.clone();
+    let space = tokio::task::spawn_blocking(move || crate::disk::disk_space(&space_dir).ok())
+        .await
+        .ok()
+        .flatten();
+
+    Ok(DirectoryListing {
+        root: root.to_string_lossy().into_owned(),
+        path: dir.to_string_lossy().into_owned(),
+        parent: (dir != root)
+            .then(|| dir.parent().map(|p| p.to_string_lossy().into_owned()))
+            .flatten(),
+        entries,
+        space,
+    })
+}
+
+/// Creates `name` inside `parent` and returns the new directory's path.
+#[server(CreateDirectory, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn create_directory(parent: String, name: String) -> Result<String, ServerFnError> {
+    let name = name.trim();
+    if !crate::paths::is_safe_file_name(name) || name.starts_with('.') {
+        return Err(ServerFnError::new("Invalid folder name"));
+    }
+
+    let (_, parent) = resolve_directory(&parent).await?;
+    let path = parent.join(name);
+    tokio::fs::create_dir(&path)
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to create folder: {}", e)))?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
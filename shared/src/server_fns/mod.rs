@@ -1,4 +1,12 @@
 pub mod torrent;
 pub mod settings;
 pub mod push;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod blocklist;
+pub mod browse;
+pub mod stats;
+pub mod api_tokens;
+pub mod automations;
+pub mod notifications;
+pub mod users;
+pub mod sessions;
@@ -0,0 +1,153 @@
+use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::{ChannelConfig, LifecycleKind, NotificationChannel};
+
+#[cfg(feature = "ssr")]
+fn require_url(url: &str) -> Result<String, ServerFnError> {
+    let url = url.trim().trim_end_matches('/').to_string();
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(ServerFnError::new("URL must start with http:// or https://"));
+    }
+    Ok(url)
+}
+
+#[cfg(feature = "ssr")]
+fn optional(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+#[cfg(feature = "ssr")]
+fn validate(config: ChannelConfig) -> Result<ChannelConfig, ServerFnError> {
+    Ok(match config {
+        ChannelConfig::Webhook { url } => ChannelConfig::Webhook { url: require_url(&url)? },
+        ChannelConfig::Ntfy { url, token } => ChannelConfig::Ntfy { url: require_url(&url)?, token: optional(token) },
+        ChannelConfig::Gotify { url, token } => {
+            let token = token.trim().to_string();
+            if token.is_empty() {
+                return Err(ServerFnError::new("Gotify application token is required"));
+            }
+            ChannelConfig::Gotify { url: require_url(&url)?, token }
+        }
+        ChannelConfig::Discord { url } => ChannelConfig::Discord { url: require_url(&url)? },
+        ChannelConfig::Email { host, port, security, username, password, from, to } => {
+            let host = host.trim().to_string();
+            if host.is_empty() || port == 0 {
+                return Err(ServerFnError::new("SMTP host and port are required"));
+            }
+            if from.trim().is_empty() || to.trim().is_empty() {
+                return Err(ServerFnError::new("Sender and recipient are required"));
+            }
+            ChannelConfig::Email {
+                host,
+                port,
+                security,
+                username: optional(username),
+                password,
+                from: from.trim().to_string(),
+                to: to.trim().to_string(),
+            }
+        }
+    })
+}
+
+#[server(ListNotificationChannels, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_notification_channels() -> Result<Vec<NotificationChannel>, ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    let mut channels = db
+        .list_notification_channels()
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    for channel in &mut channels {
+        channel.config.redact();
+    }
+    Ok(channels)
+}
+
+#[server(CreateNotificationChannel, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn create_notification_channel(
+    name: String,
+    config: ChannelConfig,
+    events: Vec<LifecycleKind>,
+) -> Result<NotificationChannel, ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServerFnError::new("Channel name is required"));
+    }
+    if events.is_empty() {
+        return Err(ServerFnError::new("Select at least one event"));
+    }
+    let config = validate(config)?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    let id = db
+        .create_notification_channel(name, &config, &events, now)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    let mut channel = NotificationChannel {
+        id,
+        name: name.to_string(),
+        config,
+        events,
+        enabled: true,
+        created_at: now,
+    };
+    channel.config.redact();
+    Ok(channel)
+}
+
+#[server(SetNotificationChannelEnabled, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn set_notification_channel_enabled(id: i64, enabled: bool) -> Result<(), ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    let updated = db
+        .set_notification_channel_enabled(id, enabled)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if !updated {
+        return Err(ServerFnError::new("Channel not found"));
+    }
+    Ok(())
+}
+
+#[server(DeleteNotificationChannel, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn delete_notification_channel(id: i64) -> Result<(), ServerFnError> {
+    super::auth::require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    let deleted = db
+        .delete_notification_channel(id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if !deleted {
+        return Err(ServerFnError::new("Channel not found"));
+    }
+    Ok(())
+}
+
+/// Sends a test message through a saved channel, even a disabled one.
+#[server(TestNotificationChannel, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn test_notification_channel(id: i64) -> Result<(), ServerFnError> {
+    super::auth::require_admin().await?;
+    use crate::notifier::{self, Notification};
+    let db = expect_context::<crate::DbContext>().db;
+
+    let channel = db
+        .list_notification_channels()
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or_else(|| ServerFnError::new("Channel not found"))?;
+
+    notifier::build(&channel.config, &notifier::http_client())
+        .send(&Notification::test())
+        .await
+        .map_err(ServerFnError::new)
+}
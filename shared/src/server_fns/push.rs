@@ -1,10 +1,23 @@
 use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::{PushDevice, PushPreferences};
+
+#[cfg(feature = "ssr")]
+use super::auth::current_user_id;
 
 #[server(GetPushPublicKey, "/api/server_fns")]
 pub async fn get_public_key() -> Result<String, ServerFnError> {
-    let key = std::env::var("VAPID_PUBLIC_KEY")
-        .map_err(|_| ServerFnError::new("VAPID_PUBLIC_KEY not configured"))?;
-    Ok(key)
+    // Keys from the environment take precedence over the generated ones,
+    // mirroring how the backend picks the signing key
+    if let (Ok(_), Ok(key)) = (std::env::var("VAPID_PRIVATE_KEY"), std::env::var("VAPID_PUBLIC_KEY")) {
+        return Ok(key);
+    }
+    let db = expect_context::<crate::DbContext>().db;
+    db.get_vapid_keys()
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?
+        .map(|(_, public_key)| public_key)
+        .ok_or_else(|| ServerFnError::new("Push notifications are not configured"))
 }
 
 #[server(SubscribePush, "/api/server_fns")]
@@ -13,20 +26,80 @@ pub async fn subscribe_push(
     p256dh: String,
     auth: String,
 ) -> Result<(), ServerFnError> {
+    use axum::http::HeaderMap;
+    use leptos_axum::extract;
+
+    let user_id = current_user_id().await?;
+    let headers: HeaderMap = extract().await.map_err(|e| ServerFnError::new(format!("Extract error: {}", e)))?;
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|h| h.to_str().ok());
+
     let db_ctx = expect_context::<crate::DbContext>();
     db_ctx
         .db
-        .save_push_subscription(&endpoint, &p256dh, &auth)
+        .save_push_subscription(user_id, &endpoint, &p256dh, &auth, user_agent)
         .await
         .map_err(|e| ServerFnError::new(format!("Failed to save subscription: {}", e)))
 }
 
 #[server(UnsubscribePush, "/api/server_fns")]
 pub async fn unsubscribe_push(endpoint: String) -> Result<(), ServerFnError> {
+    let user_id = current_user_id().await?;
     let db_ctx = expect_context::<crate::DbContext>();
     db_ctx
         .db
-        .remove_push_subscription(&endpoint)
+        .remove_user_push_subscription(&endpoint, user_id)
         .await
         .map_err(|e| ServerFnError::new(format!("Failed to remove subscription: {}", e)))
 }
+
+#[server(ListPushDevices, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_push_devices() -> Result<Vec<PushDevice>, ServerFnError> {
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    db.list_push_devices(user_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))
+}
+
+#[server(RevokePushDevice, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn revoke_push_device(id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    let revoked = db
+        .revoke_push_device(id, user_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if !revoked {
+        return Err(ServerFnError::new("Device not found"));
+    }
+    Ok(())
+}
+
+#[server(GetPushPreferences, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn get_push_preferences() -> Result<PushPreferences, ServerFnError> {
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    db.get_push_preferences(user_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))
+}
+
+#[server(SetPushPreferences, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn set_push_preferences(preferences: PushPreferences) -> Result<(), ServerFnError> {
+    if let Some(quiet) = preferences.quiet_hours {
+        if quiet.start_minute >= 1440 || quiet.end_minute >= 1440 {
+            return Err(ServerFnError::new("Quiet hours must be within a day"));
+        }
+        if quiet.utc_offset_minutes.abs() > 14 * 60 {
+            return Err(ServerFnError::new("Invalid UTC offset"));
+        }
+    }
+
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    db.set_push_preferences(user_id, &preferences)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))
+}
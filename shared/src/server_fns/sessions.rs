@@ -0,0 +1,56 @@
+use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::LoginSession;
+
+#[cfg(feature = "ssr")]
+use super::auth::current_user_id;
+
+/// The session this request came with; sessions can only be managed from one.
+#[cfg(feature = "ssr")]
+fn current_session_id() -> Result<i64, ServerFnError> {
+    use_context::<crate::session::CurrentSession>()
+        .map(|session| session.id)
+        .ok_or_else(|| ServerFnError::new("Not logged in"))
+}
+
+/// Active sessions of the logged-in user, most recently used first.
+#[server(ListSessions, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_sessions() -> Result<Vec<LoginSession>, ServerFnError> {
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    let current = current_session_id()?;
+    let mut sessions = db
+        .list_sessions(user_id, crate::session::unix_now())
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    for session in &mut sessions {
+        session.current = session.id == current;
+    }
+    Ok(sessions)
+}
+
+/// Logs out one of the user's sessions, e.g. a lost device.
+#[server(RevokeSession, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn revoke_session(id: i64) -> Result<(), ServerFnError> {
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    let revoked = db
+        .revoke_session(id, user_id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    if !revoked {
+        return Err(ServerFnError::new("Session not found"));
+    }
+    Ok(())
+}
+
+/// Logs out everywhere except the session making the request; returns how
+/// many sessions ended.
+#[server(RevokeOtherSessions, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn revoke_other_sessions() -> Result<u64, ServerFnError> {
+    let db = expect_context::<crate::DbContext>().db;
+    let user_id = current_user_id().await?;
+    db.revoke_other_sessions(user_id, current_session_id()?)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))
+}
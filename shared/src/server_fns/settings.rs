@@ -3,10 +3,17 @@ use crate::GlobalLimitRequest;
 
 #[server(GetGlobalLimits, "/api/server_fns")]
 pub async fn get_global_limits() -> Result<GlobalLimitRequest, ServerFnError> {
-    use crate::xmlrpc::{self, RtorrentClient};
+    use crate::xmlrpc::RtorrentClient;
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
 
+    get_global_limits_inner(&client).await
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_global_limits_inner(client: &crate::xmlrpc::RtorrentClient) -> Result<GlobalLimitRequest, ServerFnError> {
+    use crate::xmlrpc;
+
     let down = match client.call("throttle.global_down.max_rate", &[]).await {
         Ok(xml) => xmlrpc::parse_i64_response(&xml).unwrap_or(0),
         Err(_) => -1,
@@ -28,10 +35,22 @@ pub async fn set_global_limits(
     max_download_rate: Option<i64>,
     max_upload_rate: Option<i64>,
 ) -> Result<(), ServerFnError> {
-    use crate::xmlrpc::{RpcParam, RtorrentClient};
+    use crate::xmlrpc::RtorrentClient;
+    super::auth::require_admin().await?;
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
 
+    set_global_limits_inner(&client, max_download_rate, max_upload_rate).await
+}
+
+#[cfg(feature = "ssr")]
+pub async fn set_global_limits_inner(
+    client: &crate::xmlrpc::RtorrentClient,
+    max_download_rate: Option<i64>,
+    max_upload_rate: Option<i64>,
+) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::RpcParam;
+
     if let Some(down) = max_download_rate {
         let down_kb = down / 1024;
         client
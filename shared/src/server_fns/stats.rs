@@ -0,0 +1,81 @@
+use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::{TorrentTransferTotal, TransferPeriod, TransferSample, TransferTotal};
+
+#[cfg(feature = "ssr")]
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Speed history over the last `range_secs` seconds, regrouped so a graph
+/// gets at most a few hundred points.
+#[server(GetTransferHistory, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn get_transfer_history(range_secs: i64) -> Result<Vec<TransferSample>, ServerFnError> {
+    use crate::history;
+    let db = expect_context::<crate::DbContext>().db;
+
+    let range = range_secs.clamp(history::HOUR, 366 * history::DAY);
+    let samples = db
+        .get_transfer_samples(unix_now() - range)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    Ok(history::rebucket(&samples, history::step_for_range(range)))
+}
+
+/// Download/upload totals for the last `count` days, weeks or months.
+/// `utc_offset_secs` is the browser's offset, so days start at local midnight.
+#[server(GetTransferTotals, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn get_transfer_totals(
+    period: TransferPeriod,
+    count: u32,
+    utc_offset_secs: i64,
+) -> Result<Vec<TransferTotal>, ServerFnError> {
+    use crate::history;
+    let db = expect_context::<crate::DbContext>().db;
+
+    let count = count.clamp(1, 366);
+    let offset = utc_offset_secs.clamp(-14 * history::HOUR, 14 * history::HOUR);
+    let now = unix_now();
+    let since = history::oldest_period_start(now, period, count, offset);
+    // Daily rows are aligned to UTC, include the one straddling `since`
+    let samples = db
+        .get_transfer_samples(since - history::DAY)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    Ok(history::totals_by_period(&samples, period, count, now, offset))
+}
+
+/// Torrents that transferred the most over the last `days` days.
+#[server(GetTopTorrentTransfers, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn get_top_torrent_transfers(days: u32, limit: u32) -> Result<Vec<TorrentTransferTotal>, ServerFnError> {
+    use crate::history::DAY;
+    let db = expect_context::<crate::DbContext>().db;
+
+    let today = unix_now().div_euclid(DAY) * DAY;
+    let since = today - i64::from(days.saturating_sub(1)) * DAY;
+    let limit = limit.clamp(1, 100) as usize;
+    let user = super::torrent::auth_user()?;
+    if user.sees_all() {
+        return db
+            .get_top_torrent_transfers(since, limit as i64)
+            .await
+            .map_err(|e| ServerFnError::new(format!("DB error: {}", e)));
+    }
+
+    // History does not know owners; keep the torrents the user still has
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = crate::xmlrpc::RtorrentClient::new(&ctx.scgi_socket_path);
+    let owned = super::torrent::owned_hashes(&client, user.id).await?;
+    let mut top = db
+        .get_top_torrent_transfers(since, i64::MAX)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    top.retain(|t| owned.contains(&t.hash.to_uppercase()));
+    top.truncate(limit);
+    Ok(top)
+}
@@ -1,14 +1,145 @@
 use leptos::prelude::*;
-use crate::{TorrentFile, TorrentPeer, TorrentTracker};
+use crate::codec::MsgPack;
+use crate::{TorrentFile, TorrentFileTree, TorrentPeer, TorrentPieceMap, TorrentTracker};
 
+/// Adds a torrent, optionally saving it to `save_path` (which must be inside
+/// one of the file browser's allowed roots) instead of `directory.default`.
 #[server(AddTorrent, "/api/server_fns")]
-pub async fn add_torrent(uri: String) -> Result<(), ServerFnError> {
-    use crate::xmlrpc::{RpcParam, RtorrentClient};
+pub async fn add_torrent(uri: String, save_path: Option<String>) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::RtorrentClient;
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
-    let params = vec![RpcParam::from(""), RpcParam::from(uri.as_str())];
+    let user = auth_user()?;
+
+    add_torrent_inner(&client, &ctx.browse_roots, &uri, save_path.as_deref(), Some(user.id)).await
+}
+
+#[cfg(feature = "ssr")]
+pub async fn add_torrent_inner(
+    client: &crate::xmlrpc::RtorrentClient,
+    browse_roots: &[String],
+    uri: &str,
+    save_path: Option<&str>,
+    owner: Option<i64>,
+) -> Result<(), ServerFnError> {
+    let options = LoadOptions { save_path, owner, ..LoadOptions::default() };
+    load_torrent_inner(client, browse_roots, TorrentSource::Uri(uri), &options).await
+}
+
+/// What to hand rTorrent: a magnet/URL/path, or the contents of a `.torrent` file.
+#[cfg(feature = "ssr")]
+pub enum TorrentSource<'a> {
+    Uri(&'a str),
+    File(&'a [u8]),
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Default)]
+pub struct LoadOptions<'a> {
+    pub save_path: Option<&'a str>,
+    pub label: Option<&'a str>,
+    /// Load without starting
+    pub paused: bool,
+    /// User recorded as the owner
+    pub owner: Option<i64>,
+}
+
+/// rTorrent custom key holding the id of the user who added a torrent.
+pub const OWNER_KEY: &str = "vt_owner";
+
+/// The calling user, attached by the auth middleware.
+#[cfg(feature = "ssr")]
+pub fn auth_user() -> Result<crate::AuthUser, ServerFnError> {
+    use_context::<crate::AuthUser>().ok_or_else(|| ServerFnError::new("Unauthorized"))
+}
+
+/// Fails unless the calling user may manage the torrent: admins may manage
+/// all of them, others only the ones they added. Someone else's torrent is
+/// reported like an unknown one.
+#[cfg(feature = "ssr")]
+pub async fn require_owner(client: &crate::xmlrpc::RtorrentClient, hash: &str) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::{parse_string_response, RpcParam};
+    let user = auth_user()?;
+    if user.sees_all() {
+        return Ok(());
+    }
+    let owner = client
+        .call("d.custom", &[RpcParam::from(hash), RpcParam::from(OWNER_KEY)])
+        .await
+        .ok()
+        .and_then(|xml| parse_string_response(&xml).ok())
+        .and_then(|value| value.parse().ok());
+    if user.owns(owner) {
+        Ok(())
+    } else {
+        Err(ServerFnError::new("Torrent not found"))
+    }
+}
+
+/// Upper-case hashes of the torrents `user_id` added.
+#[cfg(feature = "ssr")]
+pub async fn owned_hashes(
+    client: &crate::xmlrpc::RtorrentClient,
+    user_id: i64,
+) -> Result<std::collections::HashSet<String>, ServerFnError> {
+    use crate::xmlrpc::{parse_multicall_response, RpcParam};
+    let owner_field = format!("d.custom={}", OWNER_KEY);
+    let params = vec![
+        RpcParam::from(""),
+        RpcParam::from("main"),
+        RpcParam::from("d.hash="),
+        RpcParam::from(owner_field.as_str()),
+    ];
+    let xml = client
+        .call("d.multicall2", &params)
+        .await
+        .map_err(|e| ServerFnError::new(format!("RPC error: {}", e)))?;
+    let rows = parse_multicall_response(&xml).map_err(|e| ServerFnError::new(format!("Parse error: {}", e)))?;
+    let owner = user_id.to_string();
+    Ok(rows
+        .into_iter()
+        .filter(|row| row.get(1) == Some(&owner))
+        .filter_map(|row| row.into_iter().next())
+        .map(|hash| hash.to_uppercase())
+        .collect())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn load_torrent_inner(
+    client: &crate::xmlrpc::RtorrentClient,
+    browse_roots: &[String],
+    source: TorrentSource<'_>,
+    options: &LoadOptions<'_>,
+) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::RpcParam;
+    let (method, target) = match (source, options.paused) {
+        (TorrentSource::Uri(uri), false) => ("load.start", RpcParam::from(uri)),
+        (TorrentSource::Uri(uri), true) => ("load.normal", RpcParam::from(uri)),
+        (TorrentSource::File(data), false) => ("load.raw_start", RpcParam::Base64(data.to_vec())),
+        (TorrentSource::File(data), true) => ("load.raw", RpcParam::Base64(data.to_vec())),
+    };
+    let mut params = vec![RpcParam::from(""), target];
+
+    // Values below are embedded in rTorrent command strings
+    if let Some(save_path) = options.save_path.filter(|p| !p.trim().is_empty()) {
+        let (_, dir) = super::browse::resolve_directory_in(client, browse_roots, save_path.trim()).await?;
+        let dir = dir.to_string_lossy();
+        if dir.contains('"') {
+            return Err(ServerFnError::new("Unsupported character in save path"));
+        }
+        params.push(RpcParam::from(format!("d.directory.set=\"{}\"", dir)));
+    }
+    if let Some(label) = options.label.map(str::trim).filter(|l| !l.is_empty()) {
+        if label.contains('"') {
+            return Err(ServerFnError::new("Unsupported character in label"));
+        }
+        params.push(RpcParam::from(format!("d.custom1.set=\"{}\"", label)));
+    }
+    if let Some(owner) = options.owner {
+        params.push(RpcParam::from(format!("d.custom.set={},{}", OWNER_KEY, owner)));
+    }
 
-    match client.call("load.start", &params).await {
+    match client.call(method, &params).await {
         Ok(response) => {
             if response.contains("faultCode") {
                 return Err(ServerFnError::new("rTorrent returned fault"));
@@ -21,22 +152,37 @@ pub async fn add_torrent(uri: String) -> Result<(), ServerFnError> {
 
 #[server(TorrentAction, "/api/server_fns")]
 pub async fn torrent_action(hash: String, action: String) -> Result<String, ServerFnError> {
-    use crate::xmlrpc::{RpcParam, RtorrentClient};
+    use crate::xmlrpc::RtorrentClient;
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    torrent_action_inner(&client, &hash, &action).await
+}
+
+/// Actions accepted by `torrent_action`.
+pub const TORRENT_ACTIONS: [&str; 4] = ["start", "stop", "delete", "delete_with_data"];
+
+#[cfg(feature = "ssr")]
+pub async fn torrent_action_inner(
+    client: &crate::xmlrpc::RtorrentClient,
+    hash: &str,
+    action: &str,
+) -> Result<String, ServerFnError> {
+    use crate::xmlrpc::RpcParam;
 
     if action == "delete_with_data" {
-        return delete_torrent_with_data_inner(&client, &hash).await;
+        return delete_torrent_with_data_inner(client, hash).await;
     }
 
-    let method = match action.as_str() {
+    let method = match action {
         "start" => "d.start",
         "stop" => "d.stop",
         "delete" => "d.erase",
         _ => return Err(ServerFnError::new("Invalid action")),
     };
 
-    let params = vec![RpcParam::from(hash.as_str())];
+    let params = vec![RpcParam::from(hash)];
     match client.call(method, &params).await {
         Ok(_) => Ok("Action executed".to_string()),
         Err(e) => Err(ServerFnError::new(format!("RPC error: {}", e))),
@@ -60,17 +206,9 @@ async fn delete_torrent_with_data_inner(
     let path = parse_string_response(&path_xml)
         .map_err(|e| ServerFnError::new(format!("Failed to parse path: {}", e)))?;
 
-    let root_xml = client
-        .call("directory.default", &[])
+    let root_path = crate::paths::download_root(client)
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to get download root: {}", e)))?;
-
-    let root_path_str = parse_string_response(&root_xml)
-        .map_err(|e| ServerFnError::new(format!("Failed to parse root path: {}", e)))?;
-
-    let root_path = tokio::fs::canonicalize(std::path::Path::new(&root_path_str))
-        .await
-        .map_err(|e| ServerFnError::new(format!("Invalid download root: {}", e)))?;
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
 
     let target_path_raw = std::path::Path::new(&path);
     if !tokio::fs::try_exists(target_path_raw).await.unwrap_or(false) {
@@ -81,21 +219,9 @@ async fn delete_torrent_with_data_inner(
         return Ok("Torrent removed (Data not found)".to_string());
     }
 
-    let target_path = tokio::fs::canonicalize(target_path_raw)
+    let target_path = crate::paths::contain(&root_path, target_path_raw)
         .await
-        .map_err(|e| ServerFnError::new(format!("Invalid data path: {}", e)))?;
-
-    if !target_path.starts_with(&root_path) {
-        return Err(ServerFnError::new(
-            "Security Error: Cannot delete files outside download directory",
-        ));
-    }
-
-    if target_path == root_path {
-        return Err(ServerFnError::new(
-            "Security Error: Cannot delete the download root directory",
-        ));
-    }
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
 
     client
         .call("d.erase", &params_hash)
@@ -116,11 +242,19 @@ async fn delete_torrent_with_data_inner(
 
 #[server(GetFiles, "/api/server_fns")]
 pub async fn get_files(hash: String) -> Result<Vec<TorrentFile>, ServerFnError> {
-    use crate::xmlrpc::{parse_multicall_response, RpcParam, RtorrentClient};
+    use crate::xmlrpc::RtorrentClient;
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    get_files_inner(&client, &hash).await
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_files_inner(client: &crate::xmlrpc::RtorrentClient, hash: &str) -> Result<Vec<TorrentFile>, ServerFnError> {
+    use crate::xmlrpc::{parse_multicall_response, RpcParam};
     let params = vec![
-        RpcParam::from(hash.as_str()),
+        RpcParam::from(hash),
         RpcParam::from(""),
         RpcParam::from("f.path="),
         RpcParam::from("f.size_bytes="),
@@ -149,13 +283,90 @@ pub async fn get_files(hash: String) -> Result<Vec<TorrentFile>, ServerFnError>
         .collect())
 }
 
+#[server(GetFileTree, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn get_file_tree(hash: String) -> Result<TorrentFileTree, ServerFnError> {
+    use crate::xmlrpc::RtorrentClient;
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    fetch_file_tree(&client, &hash)
+        .await
+        .map_err(|e| ServerFnError::new(format!("RPC error: {}", e)))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn fetch_file_tree(
+    client: &crate::xmlrpc::RtorrentClient,
+    hash: &str,
+) -> Result<TorrentFileTree, crate::xmlrpc::XmlRpcError> {
+    use crate::xmlrpc::{parse_i64_response, parse_multicall_response, parse_string_response, RpcParam};
+    use crate::FileTreeNode;
+
+    let hash_param = vec![RpcParam::from(hash)];
+    let name = parse_string_response(&client.call("d.name", &hash_param).await?)?;
+    let multi_file = parse_i64_response(&client.call("d.is_multi_file", &hash_param).await?)? == 1;
+    let chunk_size = parse_i64_response(&client.call("d.chunk_size", &hash_param).await?)?;
+
+    let params = vec![
+        RpcParam::from(hash),
+        RpcParam::from(""),
+        RpcParam::from("f.path="),
+        RpcParam::from("f.size_bytes="),
+        RpcParam::from("f.completed_chunks="),
+        RpcParam::from("f.size_chunks="),
+        RpcParam::from("f.priority="),
+        RpcParam::from("f.prioritize_first="),
+        RpcParam::from("f.prioritize_last="),
+    ];
+    let rows = parse_multicall_response(&client.call("f.multicall", &params).await?)?;
+
+    let files = rows
+        .into_iter()
+        .enumerate()
+        .map(|(idx, row)| {
+            let num = |i: usize| row.get(i).and_then(|s| s.parse::<i64>().ok()).unwrap_or(0);
+            let size = num(1);
+            let (completed_chunks, size_chunks) = (num(2), num(3));
+            // Chunks may be shared with neighbouring files, so this is an estimate
+            let completed_bytes = if size_chunks > 0 && completed_chunks >= size_chunks {
+                size
+            } else {
+                (completed_chunks * chunk_size).min(size)
+            };
+            let path = row.first().map(String::as_str).unwrap_or_default();
+            FileTreeNode::file(
+                idx as u32,
+                path,
+                size,
+                completed_bytes,
+                num(4) as u8,
+                num(5) == 1 && num(6) == 1,
+            )
+        })
+        .collect();
+
+    Ok(TorrentFileTree {
+        multi_file,
+        root: crate::file_tree::build(&name, files),
+    })
+}
+
 #[server(GetPeers, "/api/server_fns")]
 pub async fn get_peers(hash: String) -> Result<Vec<TorrentPeer>, ServerFnError> {
-    use crate::xmlrpc::{parse_multicall_response, RpcParam, RtorrentClient};
+    use crate::xmlrpc::RtorrentClient;
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    get_peers_inner(&client, &hash).await
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_peers_inner(client: &crate::xmlrpc::RtorrentClient, hash: &str) -> Result<Vec<TorrentPeer>, ServerFnError> {
+    use crate::xmlrpc::{parse_multicall_response, RpcParam};
     let params = vec![
-        RpcParam::from(hash.as_str()),
+        RpcParam::from(hash),
         RpcParam::from(""),
         RpcParam::from("p.address="),
         RpcParam::from("p.client_version="),
@@ -186,11 +397,19 @@ pub async fn get_peers(hash: String) -> Result<Vec<TorrentPeer>, ServerFnError>
 
 #[server(GetTrackers, "/api/server_fns")]
 pub async fn get_trackers(hash: String) -> Result<Vec<TorrentTracker>, ServerFnError> {
-    use crate::xmlrpc::{parse_multicall_response, RpcParam, RtorrentClient};
+    use crate::xmlrpc::RtorrentClient;
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    get_trackers_inner(&client, &hash).await
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_trackers_inner(client: &crate::xmlrpc::RtorrentClient, hash: &str) -> Result<Vec<TorrentTracker>, ServerFnError> {
+    use crate::xmlrpc::{parse_multicall_response, RpcParam};
     let params = vec![
-        RpcParam::from(hash.as_str()),
+        RpcParam::from(hash),
         RpcParam::from(""),
         RpcParam::from("t.url="),
         RpcParam::from("t.activity_date_last="),
@@ -215,6 +434,47 @@ pub async fn get_trackers(hash: String) -> Result<Vec<TorrentTracker>, ServerFnE
         .collect())
 }
 
+#[server(GetPieceMap, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn get_piece_map(hash: String) -> Result<TorrentPieceMap, ServerFnError> {
+    use crate::xmlrpc::RtorrentClient;
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    fetch_piece_map(&client, &hash)
+        .await
+        .map_err(|e| ServerFnError::new(format!("RPC error: {}", e)))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn fetch_piece_map(
+    client: &crate::xmlrpc::RtorrentClient,
+    hash: &str,
+) -> Result<TorrentPieceMap, crate::xmlrpc::XmlRpcError> {
+    use crate::xmlrpc::{parse_i64_response, parse_string_response, RpcParam, XmlRpcError};
+
+    let params = vec![RpcParam::from(hash)];
+    let piece_count = parse_i64_response(&client.call("d.size_chunks", &params).await?)?;
+    let piece_size = parse_i64_response(&client.call("d.chunk_size", &params).await?)?;
+    // Both are hex strings; they are empty while the torrent is closed.
+    let bitfield = parse_string_response(&client.call("d.bitfield", &params).await?).unwrap_or_default();
+    let seen = parse_string_response(&client.call("d.chunks_seen", &params).await?).unwrap_or_default();
+
+    let decode = |hex: &str| -> Result<Vec<u8>, XmlRpcError> {
+        (0..hex.len() / 2)
+            .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16))
+            .collect::<Result<_, _>>()
+            .map_err(|e| XmlRpcError::Parse(format!("Invalid hex field: {}", e)))
+    };
+
+    Ok(TorrentPieceMap {
+        piece_count: piece_count.max(0) as u32,
+        piece_size,
+        bitfield: decode(&bitfield)?,
+        availability: decode(&seen)?,
+    })
+}
+
 #[server(SetFilePriority, "/api/server_fns")]
 pub async fn set_file_priority(
     hash: String,
@@ -224,6 +484,7 @@ pub async fn set_file_priority(
     use crate::xmlrpc::{RpcParam, RtorrentClient};
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
 
     let target = format!("{}:f{}", hash, file_index);
     let params = vec![
@@ -243,12 +504,522 @@ pub async fn set_file_priority(
     Ok(())
 }
 
+#[server(SetFilesPriority, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn set_files_priority(
+    hash: String,
+    file_indices: Vec<u32>,
+    priority: u8,
+) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::RtorrentClient;
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    set_files_priority_inner(&client, &hash, &file_indices, priority).await
+}
+
+/// Applies one priority to every file below `folder` (relative to the torrent root).
+#[server(SetFolderPriority, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn set_folder_priority(
+    hash: String,
+    folder: String,
+    priority: u8,
+) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::RtorrentClient;
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    let tree = fetch_file_tree(&client, &hash)
+        .await
+        .map_err(|e| ServerFnError::new(format!("RPC error: {}", e)))?;
+    let node = tree
+        .root
+        .find(&folder)
+        .ok_or_else(|| ServerFnError::new("Folder not found in torrent"))?;
+
+    set_files_priority_inner(&client, &hash, &node.file_indices(), priority).await
+}
+
+#[cfg(feature = "ssr")]
+pub async fn set_files_priority_inner(
+    client: &crate::xmlrpc::RtorrentClient,
+    hash: &str,
+    file_indices: &[u32],
+    priority: u8,
+) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::RpcParam;
+
+    if priority > 2 {
+        return Err(ServerFnError::new("Invalid priority"));
+    }
+    if file_indices.is_empty() {
+        return Ok(());
+    }
+
+    let calls: Vec<(&str, Vec<RpcParam>)> = file_indices
+        .iter()
+        .map(|idx| {
+            let target = format!("{}:f{}", hash, idx);
+            ("f.set_priority", vec![RpcParam::from(target.as_str()), RpcParam::from(priority as i64)])
+        })
+        .collect();
+
+    client
+        .multicall(&calls)
+        .await
+        .map_err(|e| ServerFnError::new(format!("RPC error: {}", e)))?;
+
+    let _ = client
+        .call("d.update_priorities", &[RpcParam::from(hash)])
+        .await;
+
+    Ok(())
+}
+
+/// Toggles downloading the first and last pieces of the given files early,
+/// which is usually enough for media players to start a preview.
+#[server(SetFilesPreview, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn set_files_preview(
+    hash: String,
+    file_indices: Vec<u32>,
+    enabled: bool,
+) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::{RpcParam, RtorrentClient};
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    if file_indices.is_empty() {
+        return Ok(());
+    }
+
+    let (first, last) = if enabled {
+        ("f.prioritize_first.enable", "f.prioritize_last.enable")
+    } else {
+        ("f.prioritize_first.disable", "f.prioritize_last.disable")
+    };
+    let targets: Vec<String> = file_indices
+        .iter()
+        .map(|idx| format!("{}:f{}", hash, idx))
+        .collect();
+    let calls: Vec<(&str, Vec<RpcParam>)> = targets
+        .iter()
+        .flat_map(|target| {
+            [
+                (first, vec![RpcParam::from(target.as_str())]),
+                (last, vec![RpcParam::from(target.as_str())]),
+            ]
+        })
+        .collect();
+
+    client
+        .multicall(&calls)
+        .await
+        .map_err(|e| ServerFnError::new(format!("RPC error: {}", e)))?;
+
+    let _ = client
+        .call("d.update_priorities", &[RpcParam::from(hash.as_str())])
+        .await;
+
+    Ok(())
+}
+
+/// Renames the top-level folder of a multi-file torrent on disk and points
+/// rTorrent at the new location. This is the only rename supported: the
+/// file of a single-file torrent and the files and subfolders inside a
+/// folder cannot be renamed, because rTorrent always resolves them through
+/// the names stored in the metainfo and has no command to change them.
+#[server(RenameTorrentData, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn rename_torrent_data(hash: String, new_name: String) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::RtorrentClient;
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    rename_torrent_data_inner(&client, &hash, new_name.trim()).await
+}
+
+#[cfg(feature = "ssr")]
+async fn rename_torrent_data_inner(
+    client: &crate::xmlrpc::RtorrentClient,
+    hash: &str,
+    new_name: &str,
+) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::{parse_i64_response, parse_string_response, RpcParam};
+
+    if !crate::paths::is_safe_file_name(new_name) {
+        return Err(ServerFnError::new("Invalid name"));
+    }
+
+    let params_hash = vec![RpcParam::from(hash)];
+    let rpc_err = |e: crate::xmlrpc::XmlRpcError| ServerFnError::new(format!("RPC error: {}", e));
+
+    let multi_file = client
+        .call("d.is_multi_file", &params_hash)
+        .await
+        .and_then(|xml| parse_i64_response(&xml))
+        .map_err(rpc_err)?;
+    if multi_file != 1 {
+        return Err(ServerFnError::new(
+            "Only the folder of a multi-file torrent can be renamed; rTorrent takes file names from the metainfo",
+        ));
+    }
+
+    // Unlike d.base_path, d.directory_base is also set while the torrent is closed
+    let current = client
+        .call("d.directory_base", &params_hash)
+        .await
+        .and_then(|xml| parse_string_response(&xml))
+        .map_err(rpc_err)?;
+
+    let root_path = crate::paths::download_root(client)
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+    let current_path = crate::paths::contain(&root_path, std::path::Path::new(&current))
+        .await
+        .map_err(|e| ServerFnError::new(e.to_string()))?;
+
+    let parent = current_path
+        .parent()
+        .ok_or_else(|| ServerFnError::new("Invalid data path"))?;
+    let new_path = parent.join(new_name);
+    if new_path == current_path {
+        return Ok(());
+    }
+    if tokio::fs::try_exists(&new_path).await.unwrap_or(true) {
+        return Err(ServerFnError::new("A file or folder with that name already exists"));
+    }
+
+    let was_active = client
+        .call("d.is_active", &params_hash)
+        .await
+        .and_then(|xml| parse_i64_response(&xml))
+        .map_err(rpc_err)?
+        == 1;
+
+    // Close the torrent so rTorrent releases its file handles before the move
+    client.call("d.stop", &params_hash).await.map_err(rpc_err)?;
+    client.call("d.close", &params_hash).await.map_err(rpc_err)?;
+
+    let result = match tokio::fs::rename(&current_path, &new_path).await {
+        Ok(()) => {
+            let new_path_str = new_path.to_string_lossy();
+            let moved = client
+                .call(
+                    "d.directory_base.set",
+                    &[RpcParam::from(hash), RpcParam::from(new_path_str.as_ref())],
+                )
+                .await;
+            match moved {
+                Ok(_) => Ok(()),
+                // rTorrent still points at the old path, so put the data back
+                Err(e) => match tokio::fs::rename(&new_path, &current_path).await {
+                    Ok(()) => Err(rpc_err(e)),
+                    Err(undo) => Err(ServerFnError::new(format!(
+                        "RPC error: {}; data left at {}: {}",
+                        e,
+                        new_path.display(),
+                        undo
+                    ))),
+                },
+            }
+        }
+        Err(e) => Err(ServerFnError::new(format!("Failed to rename: {}", e))),
+    };
+
+    if was_active {
+        client.call("d.start", &params_hash).await.map_err(rpc_err)?;
+    }
+
+    result
+}
+
+/// Prepares a file for streaming: high priority plus early first/last
+/// pieces (container headers and indexes usually live there). Also enables
+/// sequential download on rTorrent builds that support it; the return value
+/// says whether that worked.
+#[server(EnableStreaming, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn enable_streaming(hash: String, file_index: u32) -> Result<bool, ServerFnError> {
+    use crate::xmlrpc::{RpcParam, RtorrentClient};
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+
+    let target = format!("{}:f{}", hash, file_index);
+    let calls: Vec<(&str, Vec<RpcParam>)> = vec![
+        ("f.set_priority", vec![RpcParam::from(target.as_str()), RpcParam::from(2i64)]),
+        ("f.prioritize_first.enable", vec![RpcParam::from(target.as_str())]),
+        ("f.prioritize_last.enable", vec![RpcParam::from(target.as_str())]),
+    ];
+    client
+        .multicall(&calls)
+        .await
+        .map_err(|e| ServerFnError::new(format!("RPC error: {}", e)))?;
+
+    // Only available on patched builds (e.g. jesec/rtorrent); absent on vanilla
+    let sequential = client
+        .call("d.down.sequential.set", &[RpcParam::from(hash.as_str()), RpcParam::from(1i64)])
+        .await
+        .map(|xml| !xml.contains("faultCode"))
+        .unwrap_or(false);
+
+    let _ = client
+        .call("d.update_priorities", &[RpcParam::from(hash.as_str())])
+        .await;
+
+    Ok(sequential)
+}
+
+/// Where a file lives inside its torrent's piece space.
+#[cfg(feature = "ssr")]
+pub struct FileSpan {
+    /// Path relative to the torrent root (empty for single-file torrents)
+    pub path: String,
+    /// Byte offset of the file within the torrent
+    pub offset: i64,
+    pub size: i64,
+}
+
+#[cfg(feature = "ssr")]
+pub async fn fetch_file_span(
+    client: &crate::xmlrpc::RtorrentClient,
+    hash: &str,
+    file_index: u32,
+) -> Result<FileSpan, crate::xmlrpc::XmlRpcError> {
+    use crate::xmlrpc::{parse_i64_response, parse_string_response, RpcParam};
+
+    let multi_file = parse_i64_response(&client.call("d.is_multi_file", &[RpcParam::from(hash)]).await?)? == 1;
+    let target = format!("{}:f{}", hash, file_index);
+    let params = vec![RpcParam::from(target.as_str())];
+
+    let path = parse_string_response(&client.call("f.path", &params).await?)?;
+    Ok(FileSpan {
+        path: if multi_file { path } else { String::new() },
+        offset: parse_i64_response(&client.call("f.offset", &params).await?)?,
+        size: parse_i64_response(&client.call("f.size_bytes", &params).await?)?,
+    })
+}
+
+/// Points a torrent at another directory inside the allowed roots,
+/// optionally moving its data there first. Data can only be moved within
+/// one filesystem; copying across devices would outlive the request.
+#[server(MoveTorrent, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn move_torrent(hash: String, destination: String, move_data: bool) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::{parse_i64_response, parse_string_response, RpcParam, RtorrentClient};
+    let ctx = expect_context::<crate::ServerContext>();
+    let client = RtorrentClient::new(&ctx.scgi_socket_path);
+    require_owner(&client, &hash).await?;
+    let rpc_err = |e: crate::xmlrpc::XmlRpcError| ServerFnError::new(format!("RPC error: {}", e));
+
+    let (_, dest) = super::browse::resolve_directory(&destination).await?;
+    let params_hash = vec![RpcParam::from(hash.as_str())];
+
+    let multi_file = client
+        .call("d.is_multi_file", &params_hash)
+        .await
+        .and_then(|xml| parse_i64_response(&xml))
+        .map_err(rpc_err)?
+        == 1;
+    let base = client
+        .call("d.directory_base", &params_hash)
+        .await
+        .and_then(|xml| parse_string_response(&xml))
+        .map_err(rpc_err)?;
+    let mut current = std::path::PathBuf::from(base);
+    if !multi_file {
+        let name = client
+            .call("d.name", &params_hash)
+            .await
+            .and_then(|xml| parse_string_response(&xml))
+            .map_err(rpc_err)?;
+        current.push(name);
+    }
+    let file_name = current
+        .file_name()
+        .map(|n| n.to_os_string())
+        .ok_or_else(|| ServerFnError::new("Invalid data path"))?;
+    let new_path = dest.join(&file_name);
+
+    if move_data && tokio::fs::try_exists(&current).await.unwrap_or(false) {
+        let ctx_roots = crate::paths::browse_roots(&client, &ctx.browse_roots)
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        let (root, current_path) = crate::paths::contain_in_roots(&ctx_roots, &current)
+            .await
+            .map_err(|e| ServerFnError::new(e.to_string()))?;
+        if current_path == root {
+            return Err(ServerFnError::new(crate::paths::PathError::IsRoot.to_string()));
+        }
+        if current_path == new_path {
+            return Ok(());
+        }
+        if tokio::fs::try_exists(&new_path).await.unwrap_or(true) {
+            return Err(ServerFnError::new("Destination already contains an item with that name"));
+        }
+        current = current_path;
+    }
+
+    let was_active = client
+        .call("d.is_active", &params_hash)
+        .await
+        .and_then(|xml| parse_i64_response(&xml))
+        .map_err(rpc_err)?
+        == 1;
+    client.call("d.stop", &params_hash).await.map_err(rpc_err)?;
+    client.call("d.close", &params_hash).await.map_err(rpc_err)?;
+
+    let moved = move_data && tokio::fs::try_exists(&current).await.unwrap_or(false);
+    let mut move_result = if moved {
+        tokio::fs::rename(&current, &new_path).await.map_err(|e| {
+            if e.raw_os_error() == Some(libc::EXDEV) {
+                ServerFnError::new("Moving data to another filesystem is not supported")
+            } else {
+                ServerFnError::new(format!("Failed to move data: {}", e))
+            }
+        })
+    } else {
+        Ok(())
+    };
+
+    if move_result.is_ok() {
+        // Multi-file torrents may have been renamed, so keep the folder name
+        let (method, target) = if multi_file {
+            ("d.directory_base.set", new_path.to_string_lossy().into_owned())
+        } else {
+            ("d.directory.set", dest.to_string_lossy().into_owned())
+        };
+        if let Err(e) = client
+            .call(method, &[RpcParam::from(hash.as_str()), RpcParam::from(target)])
+            .await
+        {
+            // rTorrent still points at the old path, so put the data back
+            if moved {
+                if let Err(undo) = tokio::fs::rename(&new_path, &current).await {
+                    tracing::error!("Failed to move {} back to {}: {}", new_path.display(), current.display(), undo);
+                }
+            }
+            move_result = Err(rpc_err(e));
+        }
+    }
+
+    if was_active {
+        client.call("d.start", &params_hash).await.map_err(rpc_err)?;
+    }
+
+    move_result
+}
+
 #[server(SetLabel, "/api/server_fns")]
 pub async fn set_label(hash: String, label: String) -> Result<(), ServerFnError> {
-    use crate::xmlrpc::{RpcParam, RtorrentClient};
+    use crate::xmlrpc::RtorrentClient;
     let ctx = expect_context::<crate::ServerContext>();
     let client = RtorrentClient::new(&ctx.scgi_socket_path);
-    let params = vec![RpcParam::from(hash.as_str()), RpcParam::from(label)];
+    require_owner(&client, &hash).await?;
+
+    set_label_inner(&client, &hash, &label).await
+}
+
+/// Per-torrent fields the poller does not fetch, for the qBittorrent and
+/// Transmission compatibility APIs.
+#[cfg(feature = "ssr")]
+#[derive(Debug, Default, Clone)]
+pub struct TorrentExtra {
+    /// rTorrent's `d.directory`: the torrent's own folder for multi-file
+    /// torrents, the parent folder otherwise
+    pub directory: String,
+    pub multi_file: bool,
+    pub uploaded: i64,
+    /// Per mille, like rTorrent reports it
+    pub ratio: i64,
+    pub seeds: i64,
+    pub leechs: i64,
+    pub finished_at: i64,
+    pub chunk_size: i64,
+    pub chunks: i64,
+    pub completed_chunks: i64,
+}
+
+#[cfg(feature = "ssr")]
+impl TorrentExtra {
+    /// Folder the torrent was saved into.
+    pub fn save_path(&self) -> String {
+        if self.multi_file {
+            std::path::Path::new(&self.directory)
+                .parent()
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        } else {
+            self.directory.clone()
+        }
+    }
+
+    /// The torrent's file, or its folder for multi-file torrents.
+    pub fn content_path(&self, name: &str) -> String {
+        if self.multi_file {
+            self.directory.clone()
+        } else {
+            std::path::Path::new(&self.directory).join(name).to_string_lossy().into_owned()
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+const EXTRA_FIELDS: [&str; 11] = [
+    "d.hash=",
+    "d.directory=",
+    "d.is_multi_file=",
+    "d.up.total=",
+    "d.ratio=",
+    "d.peers_complete=",
+    "d.peers_accounted=",
+    "d.timestamp.finished=",
+    "d.chunk_size=",
+    "d.size_chunks=",
+    "d.completed_chunks=",
+];
+
+/// `TorrentExtra` for every torrent, keyed by upper-case hash.
+#[cfg(feature = "ssr")]
+pub async fn get_torrent_extras_inner(
+    client: &crate::xmlrpc::RtorrentClient,
+) -> Result<std::collections::HashMap<String, TorrentExtra>, ServerFnError> {
+    use crate::xmlrpc::{parse_multicall_response, RpcParam};
+
+    let mut params = vec![RpcParam::from(""), RpcParam::from("main")];
+    params.extend(EXTRA_FIELDS.iter().map(|f| RpcParam::from(*f)));
+    let xml = client
+        .call("d.multicall2", &params)
+        .await
+        .map_err(|e| ServerFnError::new(format!("RPC error: {}", e)))?;
+    let rows = parse_multicall_response(&xml)
+        .map_err(|e| ServerFnError::new(format!("Parse error: {}", e)))?;
+
+    let num = |row: &[String], i: usize| row.get(i).and_then(|v| v.parse::<i64>().ok()).unwrap_or(0);
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let extra = TorrentExtra {
+                directory: row.get(1).cloned().unwrap_or_default(),
+                multi_file: num(&row, 2) != 0,
+                uploaded: num(&row, 3),
+                ratio: num(&row, 4),
+                seeds: num(&row, 5),
+                leechs: num(&row, 6),
+                finished_at: num(&row, 7),
+                chunk_size: num(&row, 8),
+                chunks: num(&row, 9),
+                completed_chunks: num(&row, 10),
+            };
+            (row.first().cloned().unwrap_or_default().to_uppercase(), extra)
+        })
+        .collect())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn set_label_inner(client: &crate::xmlrpc::RtorrentClient, hash: &str, label: &str) -> Result<(), ServerFnError> {
+    use crate::xmlrpc::RpcParam;
+    let params = vec![RpcParam::from(hash), RpcParam::from(label)];
 
     client
         .call("d.custom1.set", &params)
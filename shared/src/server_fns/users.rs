@@ -0,0 +1,144 @@
+use leptos::prelude::*;
+use crate::codec::MsgPack;
+use crate::{User, UserRole};
+
+#[cfg(feature = "ssr")]
+use super::auth::require_admin;
+
+/// Rejects changes an admin makes to their own account, which could
+/// otherwise leave nobody able to manage users.
+#[cfg(feature = "ssr")]
+fn ensure_other_user(admin_id: i64, id: i64) -> Result<(), ServerFnError> {
+    if admin_id == id {
+        return Err(ServerFnError::new("You cannot change your own account here"));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+fn not_found(found: bool) -> Result<(), ServerFnError> {
+    if !found {
+        return Err(ServerFnError::new("User not found"));
+    }
+    Ok(())
+}
+
+#[server(ListUsers, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn list_users() -> Result<Vec<User>, ServerFnError> {
+    require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+    db.list_users()
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))
+}
+
+#[server(CreateUser, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn create_user(username: String, password: String, role: UserRole) -> Result<User, ServerFnError> {
+    require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+
+    let username = username.trim();
+    if username.is_empty() {
+        return Err(ServerFnError::new("Username is required"));
+    }
+    if password.is_empty() {
+        return Err(ServerFnError::new("Password is required"));
+    }
+    if db
+        .get_user_by_username(username)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?
+        .is_some()
+    {
+        return Err(ServerFnError::new("Username is taken"));
+    }
+
+    // Same cost as setup (low for MIPS)
+    let password_hash = bcrypt::hash(&password, 6).map_err(|_| ServerFnError::new("Hashing error"))?;
+    let id = db
+        .create_user(username, &password_hash, role)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+
+    db.list_users()
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?
+        .into_iter()
+        .find(|u| u.id == id)
+        .ok_or_else(|| ServerFnError::new("User not found"))
+}
+
+#[server(DeleteUser, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn delete_user(id: i64) -> Result<(), ServerFnError> {
+    let admin_id = require_admin().await?;
+    ensure_other_user(admin_id, id)?;
+    let db = expect_context::<crate::DbContext>().db;
+    let found = db
+        .delete_user(id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    not_found(found)
+}
+
+#[server(SetUserRole, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn set_user_role(id: i64, role: UserRole) -> Result<(), ServerFnError> {
+    let admin_id = require_admin().await?;
+    ensure_other_user(admin_id, id)?;
+    let db = expect_context::<crate::DbContext>().db;
+    let found = db
+        .set_user_role(id, role)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    not_found(found)
+}
+
+/// Disabled users are logged out on their next request and cannot log in.
+#[server(SetUserDisabled, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn set_user_disabled(id: i64, disabled: bool) -> Result<(), ServerFnError> {
+    let admin_id = require_admin().await?;
+    ensure_other_user(admin_id, id)?;
+    let db = expect_context::<crate::DbContext>().db;
+    let found = db
+        .set_user_disabled(id, disabled)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    not_found(found)?;
+    if disabled {
+        db.delete_all_sessions_for_user(id)
+            .await
+            .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Sets a random password, like `--reset-password`, and returns it once.
+#[server(ResetUserPassword, "/api/server_fns", input = MsgPack, output = MsgPack)]
+pub async fn reset_user_password(id: i64) -> Result<String, ServerFnError> {
+    use rand::{distributions::Alphanumeric, Rng};
+
+    require_admin().await?;
+    let db = expect_context::<crate::DbContext>().db;
+
+    let new_password: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(12)
+        .map(char::from)
+        .collect();
+    let password_hash = bcrypt::hash(&new_password, 6).map_err(|_| ServerFnError::new("Hashing error"))?;
+
+    if db
+        .get_username_by_id(id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?
+        .is_none()
+    {
+        return Err(ServerFnError::new("User not found"));
+    }
+    db.update_password(id, &password_hash)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    db.delete_all_sessions_for_user(id)
+        .await
+        .map_err(|e| ServerFnError::new(format!("DB error: {}", e)))?;
+    Ok(new_password)
+}
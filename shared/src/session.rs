@@ -0,0 +1,131 @@
+//! Login sessions. The cookie holds a random token and the database only
+//! its SHA-256, like API tokens, so logging out or revoking a session from
+//! another device takes effect on the next request. Expiry slides forward
+//! while a session is in use.
+
+use axum::http::HeaderMap;
+use cookie::{Cookie, SameSite};
+use rand::RngCore;
+use std::net::SocketAddr;
+
+pub const COOKIE_NAME: &str = "auth_token";
+
+/// Idle time after which a session ends.
+pub const IDLE_TIMEOUT: i64 = 24 * 3600;
+
+/// Idle time of "remember me" sessions, which also get a persistent cookie.
+pub const REMEMBER_TIMEOUT: i64 = 30 * 24 * 3600;
+
+/// Expiry is pushed forward at most this often, so busy clients do not
+/// turn every request into a write.
+const TOUCH_INTERVAL: i64 = 300;
+
+/// The session a request was authenticated with, attached by the auth
+/// middleware.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CurrentSession {
+    pub id: i64,
+}
+
+pub fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+pub fn generate() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    crate::api_token::to_hex(&bytes)
+}
+
+pub fn hash(token: &str) -> String {
+    crate::api_token::hash(token)
+}
+
+pub fn timeout(remember: bool) -> i64 {
+    if remember {
+        REMEMBER_TIMEOUT
+    } else {
+        IDLE_TIMEOUT
+    }
+}
+
+/// Whether a session last extended at `last_seen_at` is due for another extension.
+pub fn needs_touch(last_seen_at: i64, now: i64) -> bool {
+    now - last_seen_at >= TOUCH_INTERVAL
+}
+
+/// Session cookie for `token`; remembered sessions outlive the browser.
+pub fn cookie(name: &'static str, token: &str, remember: bool) -> Cookie<'static> {
+    let mut builder = Cookie::build((name, token.to_string()))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict);
+    if remember {
+        builder = builder.max_age(cookie::time::Duration::seconds(REMEMBER_TIMEOUT));
+    }
+    builder.build()
+}
+
+pub fn removal_cookie(name: &'static str) -> Cookie<'static> {
+    Cookie::build((name, ""))
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .max_age(cookie::time::Duration::seconds(0))
+        .build()
+}
+
+/// The value of cookie `name` in the request headers.
+pub fn token_from_headers(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(';'))
+        .filter_map(|c| Cookie::parse(c.trim()).ok())
+        .find(|c| c.name() == name)
+        .map(|c| c.value().to_string())
+}
+
+/// Client address and user agent to show in the session list. Behind a
+/// reverse proxy the peer is the proxy, so `X-Forwarded-For` wins; it is
+/// only displayed, never trusted for access decisions.
+pub fn client_info(headers: &HeaderMap, peer: Option<SocketAddr>) -> (Option<String>, Option<String>) {
+    let forwarded = headers
+        .get("x-forwarded-for")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.split(',').next())
+        .map(str::trim)
+        .filter(|ip| !ip.is_empty())
+        .map(str::to_string);
+    let ip = forwarded.or_else(|| peer.map(|addr| addr.ip().to_string()));
+    let user_agent = headers
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|h| h.to_str().ok())
+        .map(|ua| ua.chars().take(512).collect());
+    (ip, user_agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_helpers() {
+        assert_eq!(generate().len(), 64);
+        assert!(!needs_touch(1_000, 1_000 + TOUCH_INTERVAL - 1));
+        assert!(needs_touch(1_000, 1_000 + TOUCH_INTERVAL));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(axum::http::header::COOKIE, "a=1; auth_token=abc".parse().unwrap());
+        headers.insert("x-forwarded-for", "203.0.113.7, 10.0.0.1".parse().unwrap());
+        assert_eq!(token_from_headers(&headers, COOKIE_NAME).as_deref(), Some("abc"));
+        assert_eq!(token_from_headers(&headers, "SID"), None);
+        let peer = "127.0.0.1:5000".parse().ok();
+        assert_eq!(client_info(&headers, peer).0.as_deref(), Some("203.0.113.7"));
+        assert_eq!(client_info(&HeaderMap::new(), peer).0.as_deref(), Some("127.0.0.1"));
+    }
+}
@@ -5,6 +5,7 @@ use quick_xml::de::from_str;
 use quick_xml::se::to_string;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
 #[derive(Error, Debug)]
 pub enum XmlRpcError {
@@ -23,6 +24,10 @@ pub enum XmlRpcError {
 pub enum RpcParam {
     String(String),
     Int(i64),
+    /// Raw bytes, sent as `<base64>` (e.g. `.torrent` files for `load.raw`)
+    Base64(Vec<u8>),
+    Array(Vec<RpcParam>),
+    Struct(Vec<(String, RpcParam)>),
 }
 
 impl From<&str> for RpcParam {
@@ -77,6 +82,69 @@ struct RequestValueInner<'a> {
     // Casting i64 to i32 is safe for typical speed limits.
     #[serde(skip_serializing_if = "Option::is_none")]
     i4: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base64: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    array: Option<RequestArray<'a>>,
+    #[serde(rename = "struct", skip_serializing_if = "Option::is_none")]
+    structure: Option<RequestStruct<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestArray<'a> {
+    data: RequestArrayData<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestArrayData<'a> {
+    value: Vec<RequestValueInner<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestStruct<'a> {
+    member: Vec<RequestMember<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+struct RequestMember<'a> {
+    name: &'a str,
+    value: RequestValueInner<'a>,
+}
+
+impl<'a> RequestValueInner<'a> {
+    fn from_param(param: &'a RpcParam) -> Self {
+        let mut value = RequestValueInner {
+            string: None,
+            i4: None,
+            base64: None,
+            array: None,
+            structure: None,
+        };
+        match param {
+            RpcParam::String(s) => value.string = Some(s),
+            RpcParam::Int(i) => value.i4 = Some(*i as i32),
+            RpcParam::Base64(bytes) => value.base64 = Some(BASE64.encode(bytes)),
+            RpcParam::Array(items) => {
+                value.array = Some(RequestArray {
+                    data: RequestArrayData {
+                        value: items.iter().map(RequestValueInner::from_param).collect(),
+                    },
+                })
+            }
+            RpcParam::Struct(members) => {
+                value.structure = Some(RequestStruct {
+                    member: members
+                        .iter()
+                        .map(|(name, v)| RequestMember {
+                            name,
+                            value: RequestValueInner::from_param(v),
+                        })
+                        .collect(),
+                })
+            }
+        }
+        value
+    }
 }
 
 // --- Response Models for d.multicall2 ---
@@ -223,16 +291,7 @@ impl RtorrentClient {
             param: params
                 .iter()
                 .map(|p| RequestParam {
-                    value: match p {
-                        RpcParam::String(s) => RequestValueInner {
-                            string: Some(s),
-                            i4: None,
-                        },
-                        RpcParam::Int(i) => RequestValueInner {
-                            string: None,
-                            i4: Some(*i as i32),
-                        },
-                    },
+                    value: RequestValueInner::from_param(p),
                 })
                 .collect(),
         };
@@ -250,10 +309,50 @@ impl RtorrentClient {
         let xml = self.build_method_call(method, params)?;
         let req = ScgiRequest::new().body(xml.into_bytes());
 
-        let bytes = send_request(&self.socket_path, req).await?;
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        let result = send_request(&self.socket_path, req).await;
+        #[cfg(feature = "metrics")]
+        {
+            let method = method.to_string();
+            metrics::histogram!("vibetorrent_scgi_call_duration_seconds", "method" => method.clone())
+                .record(started.elapsed().as_secs_f64());
+            if result.is_err() {
+                metrics::counter!("vibetorrent_scgi_errors_total", "method" => method).increment(1);
+            }
+        }
+
+        let bytes = result?;
         let s = String::from_utf8_lossy(&bytes).to_string();
         Ok(s)
     }
+
+    /// Batches several calls into a single `system.multicall` round trip.
+    pub async fn multicall(&self, calls: &[(&str, Vec<RpcParam>)]) -> Result<String, XmlRpcError> {
+        let param = build_multicall_param(calls);
+        let xml = self.call("system.multicall", &[param]).await?;
+        if xml.contains("<name>faultCode</name>") {
+            return Err(XmlRpcError::Parse(format!(
+                "system.multicall returned a fault: {}",
+                xml
+            )));
+        }
+        Ok(xml)
+    }
+}
+
+fn build_multicall_param(calls: &[(&str, Vec<RpcParam>)]) -> RpcParam {
+    RpcParam::Array(
+        calls
+            .iter()
+            .map(|(method, params)| {
+                RpcParam::Struct(vec![
+                    ("methodName".to_string(), RpcParam::from(*method)),
+                    ("params".to_string(), RpcParam::Array(params.clone())),
+                ])
+            })
+            .collect(),
+    )
 }
 
 pub fn parse_multicall_response(xml: &str) -> Result<Vec<Vec<String>>, XmlRpcError> {
@@ -318,6 +417,27 @@ mod tests {
         assert!(xml.contains("<i4>1024</i4>"));
     }
 
+    #[test]
+    fn test_build_method_call_base64() {
+        let client = RtorrentClient::new("dummy");
+        let params = vec![RpcParam::from(""), RpcParam::Base64(b"d4:infoe".to_vec())];
+        let xml = client.build_method_call("load.raw", &params).unwrap();
+        assert!(xml.contains("<base64>ZDQ6aW5mb2U=</base64>"));
+    }
+
+    #[test]
+    fn test_build_system_multicall() {
+        let client = RtorrentClient::new("dummy");
+        let param = build_multicall_param(&[
+            ("ipv4_filter.add_address", vec![RpcParam::from("10.0.0.0/8"), RpcParam::from("unwanted")]),
+        ]);
+        let xml = client.build_method_call("system.multicall", &[param]).unwrap();
+
+        assert!(xml.contains("<methodName>system.multicall</methodName>"));
+        assert!(xml.contains("<member><name>methodName</name><value><string>ipv4_filter.add_address</string></value></member>"));
+        assert!(xml.contains("<value><string>10.0.0.0/8</string></value>"));
+    }
+
     #[test]
     fn test_parse_multicall_response() {
         let xml = r#"<methodResponse>